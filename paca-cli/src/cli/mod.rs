@@ -1,6 +1,7 @@
 #![cfg_attr(feature = "strict", deny(warnings))]
 
 use clap::{Args, Parser, Subcommand};
+use std::net::IpAddr;
 use std::path::PathBuf;
 
 /// Command-line interface for the llama.cpp model downloader
@@ -10,26 +11,171 @@ use std::path::PathBuf;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// How to print results: json and ndjson follow the schema in the
+    /// README, which stays stable across releases
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub format: crate::output::Format,
+
+    /// Config file profile to use (default: the file's `profile`, then
+    /// "default")
+    #[arg(long, global = true, env = "PACA_PROFILE", value_name = "NAME")]
+    pub profile: Option<String>,
+
+    #[command(flatten)]
+    pub network: NetworkArgs,
+
+    #[command(flatten)]
+    pub progress: ProgressArgs,
+}
+
+/// How downloads report their progress on stderr.
+#[derive(Args, Debug, PartialEq)]
+#[command(next_help_heading = "Progress")]
+pub struct ProgressArgs {
+    /// Show progress as bars, or as JSON lines for logs and other
+    /// programs; auto picks bars when stderr is a terminal
+    #[arg(long = "progress", global = true, value_enum, default_value_t)]
+    pub mode: crate::progress::ProgressMode,
+
+    /// Seconds between JSON progress events for a file
+    #[arg(long = "progress-interval", global = true, value_name = "SECONDS", default_value = "1", value_parser = crate::progress::parse_interval)]
+    pub interval: std::time::Duration,
+}
+
+/// Connection settings for every request paca makes. Each overrides the
+/// `PACA_*` variable of the same name.
+#[derive(Args, Debug, Default, PartialEq)]
+#[command(next_help_heading = "Network")]
+pub struct NetworkArgs {
+    /// Send requests through this proxy, credentials in the URL if needed
+    /// (default: $PACA_PROXY, then $HTTPS_PROXY and friends)
+    #[arg(long, global = true, value_name = "URL")]
+    pub proxy: Option<String>,
+
+    /// Comma-separated hosts to reach without --proxy
+    #[arg(long, global = true, value_name = "HOSTS")]
+    pub no_proxy: Option<String>,
+
+    /// Also trust the root certificates in this PEM file; repeatable
+    #[arg(long, global = true, value_name = "PATH")]
+    pub ca_cert: Vec<PathBuf>,
+
+    /// Trust only the --ca-cert certificates, not the system's
+    #[arg(long, global = true)]
+    pub no_system_roots: bool,
+
+    /// Local address to connect from, to pick a network interface
+    #[arg(long, global = true, value_name = "IP", value_parser = paca::http::parse_local_address)]
+    pub bind_address: Option<IpAddr>,
+
+    /// Connect to HOST at IP instead of looking it up; repeatable
+    #[arg(long, global = true, value_name = "HOST:IP", value_parser = paca::http::parse_resolve)]
+    pub resolve: Vec<(String, IpAddr)>,
+
+    /// Text appended to the llama-cpp user agent
+    #[arg(long, global = true, value_name = "TEXT")]
+    pub user_agent_suffix: Option<String>,
+
+    /// Connections to split a large file's download across
+    #[arg(long, global = true, value_name = "N", value_parser = paca::http::parse_concurrency)]
+    pub concurrency: Option<usize>,
+
+    /// Cap download speed, in bytes per second with an optional K, M or G
+    /// suffix (e.g. 50M)
+    #[arg(long, global = true, value_name = "RATE", value_parser = paca::http::parse_rate)]
+    pub rate_limit: Option<u64>,
+}
+
+impl NetworkArgs {
+    /// Layers the flags that were given over `options`.
+    pub fn apply(&self, options: &mut paca::http::HttpOptions) {
+        if let Some(proxy) = &self.proxy {
+            options.proxy = Some(proxy.clone());
+        }
+        if let Some(no_proxy) = &self.no_proxy {
+            options.no_proxy = Some(no_proxy.clone());
+        }
+        if !self.ca_cert.is_empty() {
+            options.root_certificates.clone_from(&self.ca_cert);
+        }
+        if self.no_system_roots {
+            options.system_roots = false;
+        }
+        if let Some(address) = self.bind_address {
+            options.local_address = Some(address);
+        }
+        if !self.resolve.is_empty() {
+            options.resolve.clone_from(&self.resolve);
+        }
+        if let Some(suffix) = &self.user_agent_suffix {
+            options.user_agent_suffix = Some(suffix.clone());
+        }
+        if let Some(concurrency) = self.concurrency {
+            options.concurrency = concurrency;
+        }
+        if let Some(rate) = self.rate_limit {
+            options.rate_limit = Some(rate);
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Subcommand)]
 pub enum Commands {
     /// Remove stale files from the cache
     Clean(CommonArgs),
-    /// Download a model from HuggingFace
+    /// Show or change settings in the config file's profile
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Download a model from HuggingFace or another registry
     #[command(aliases = ["dl"])]
     Download(ModelArgs),
+    /// Write a llama-server preset or llama-swap config for every cached model
+    ExportConfig(ExportConfigArgs),
+    /// Import models already downloaded by Ollama or LM Studio
+    Import(ImportArgs),
+    /// Link a downloaded model into llama.cpp's cache for `-hf --offline`
+    LinkLlama(LinkLlamaArgs),
     /// List all downloaded models
     #[command(aliases = ["ls"])]
     List(CommonArgs),
     /// Check which downloaded models have outdated files
     #[command(aliases = ["o"])]
     Outdated(CommonArgs),
+    /// Print the local path of a downloaded model, for llama.cpp's `-m`
+    Path(PathArgs),
+    /// Store a HuggingFace access token, read from stdin
+    Login,
+    /// Move models from another tool's cache into the hub cache
+    #[command(subcommand)]
+    Migrate(MigrateSource),
+    /// Delete the stored HuggingFace access token
+    Logout,
+    /// Launch llama.cpp, or another command, on a downloaded model
+    Run(RunArgs),
     /// Remove a downloaded model or tag
     #[command(aliases = ["rm"])]
     Remove(RemoveArgs),
+    /// Serve the cache to other hosts as a HuggingFace mirror (HF_ENDPOINT)
+    Serve(ServeArgs),
     /// Print version information
     Version,
+    /// Show which account the HuggingFace token belongs to
+    Whoami,
+}
+
+/// Keys are `hub-dir`, `endpoints`, `token.HOST`, `token-command.HOST`,
+/// `concurrency`, `rate-limit` and `protected`.
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum ConfigCommand {
+    /// Print every setting of the profile, with tokens hidden
+    List,
+    /// Print one setting
+    Get { key: String },
+    /// Change one setting; lists are comma-separated
+    Set { key: String, value: String },
+    /// Remove one setting
+    Unset { key: String },
 }
 
 #[derive(Args, Debug, PartialEq)]
@@ -45,10 +191,165 @@ pub struct ModelArgs {
     #[arg(long)]
     pub hub_dir: Option<PathBuf>,
 
-    /// Model identifier (e.g., unsloth/GLM-4.7-Flash-GGUF:Q2_K_XL)
+    /// Registry to download from: huggingface, modelscope, or the URL of
+    /// a plain HTTP directory of GGUF files
+    #[arg(long, default_value = "huggingface")]
+    pub registry: String,
+
+    /// Also link the files into llama.cpp's cache ($LLAMA_CACHE), so
+    /// `llama-server -hf <model> --offline` finds them
+    #[arg(long)]
+    pub link_llama: bool,
+
+    /// Model identifier (e.g., unsloth/GLM-4.7-Flash-GGUF:Q2_K_XL, or
+    /// oci://registry.ollama.ai/library/gemma3:4b). Without a tag, the
+    /// repository's default quantization is downloaded.
     pub model: String,
 }
 
+#[derive(Args, Debug, PartialEq)]
+pub struct ExportConfigArgs {
+    /// Override the HuggingFace Hub cache directory
+    #[arg(long)]
+    pub hub_dir: Option<PathBuf>,
+
+    /// Config to write: llama-server-preset or llama-swap
+    pub config: paca::export::ConfigFormat,
+
+    /// INI file of llama-server options per model, under [*],
+    /// [owner/repo] or [owner/repo:tag] sections
+    #[arg(long)]
+    pub template: Option<PathBuf>,
+
+    /// llama-server binary the llama-swap commands launch
+    #[arg(long, env = "PACA_LLAMA_BIN", default_value = paca::run::DEFAULT_BINARY)]
+    pub bin: String,
+}
+
+#[derive(Args, Debug, PartialEq)]
+pub struct ImportArgs {
+    /// Override the HuggingFace Hub cache directory
+    #[arg(long)]
+    pub hub_dir: Option<PathBuf>,
+
+    /// Store to import from: ollama or lmstudio
+    #[arg(long)]
+    pub from: paca::import::ImportSource,
+
+    /// Override where the store is looked for (default: $OLLAMA_MODELS or
+    /// ~/.ollama/models, ~/.lmstudio/models)
+    #[arg(long)]
+    pub source_dir: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, PartialEq)]
+pub struct RunArgs {
+    /// Override the HuggingFace Hub cache directory
+    #[arg(long)]
+    pub hub_dir: Option<PathBuf>,
+
+    /// Registry to download from with --pull: huggingface, modelscope, or
+    /// the URL of a plain HTTP directory of GGUF files
+    #[arg(long, default_value = "huggingface")]
+    pub registry: String,
+
+    /// Download the model first when it is not in the cache
+    #[arg(long)]
+    pub pull: bool,
+
+    /// llama.cpp binary to launch with `-m <model>` and `--mmproj`
+    #[arg(long, env = "PACA_LLAMA_BIN", default_value = paca::run::DEFAULT_BINARY)]
+    pub bin: String,
+
+    /// Run the arguments after `--` as the whole command, substituting
+    /// {model}, {mmproj} and {dir}, instead of launching --bin
+    #[arg(long)]
+    pub exec: bool,
+
+    /// Downloaded model and tag (e.g., unsloth/GLM-4.7-Flash-GGUF:Q2_K_XL)
+    pub model: String,
+
+    /// Arguments passed through to the binary, after `--`
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Args, Debug, PartialEq)]
+pub struct ServeArgs {
+    /// Override the HuggingFace Hub cache directory
+    #[arg(long)]
+    pub hub_dir: Option<PathBuf>,
+
+    /// Address to listen on; use 0.0.0.0:8080 to serve other hosts
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub bind: String,
+
+    /// Fetch models missing from the cache from this hub, caching them as
+    /// they are served (e.g. https://huggingface.co)
+    #[arg(long, value_name = "URL")]
+    pub upstream: Option<String>,
+}
+
+#[derive(Args, Debug, PartialEq)]
+pub struct PathArgs {
+    /// Override the HuggingFace Hub cache directory
+    #[arg(long)]
+    pub hub_dir: Option<PathBuf>,
+
+    /// Print the multimodal projector instead of the model
+    #[arg(long, group = "output")]
+    pub mmproj: bool,
+
+    /// Print every shard, then the projector if there is one
+    #[arg(long, group = "output")]
+    pub all: bool,
+
+    /// Print `PACA_MODEL` and `PACA_MMPROJ` assignments for `eval`
+    #[arg(long, group = "output")]
+    pub shell_env: bool,
+
+    /// Downloaded model and tag (e.g., unsloth/GLM-4.7-Flash-GGUF:Q2_K_XL)
+    pub model: String,
+}
+
+#[derive(Args, Debug, PartialEq)]
+pub struct LinkLlamaArgs {
+    /// Override the HuggingFace Hub cache directory
+    #[arg(long)]
+    pub hub_dir: Option<PathBuf>,
+
+    /// Override llama.cpp's cache directory (default: $LLAMA_CACHE or
+    /// ~/.cache/llama.cpp)
+    #[arg(long)]
+    pub llama_cache: Option<PathBuf>,
+
+    /// Downloaded model and tag (e.g., unsloth/GLM-4.7-Flash-GGUF:Q2_K_XL)
+    pub model: String,
+}
+
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum MigrateSource {
+    /// Models llama.cpp downloaded itself with `-hf`, from LLAMA_CACHE
+    LlamaCache(MigrateArgs),
+}
+
+#[derive(Args, Debug, PartialEq)]
+pub struct MigrateArgs {
+    /// Override the HuggingFace Hub cache directory
+    #[arg(long)]
+    pub hub_dir: Option<PathBuf>,
+
+    /// Override where llama.cpp's cache is looked for (default:
+    /// $LLAMA_CACHE or ~/.cache/llama.cpp)
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Replace each moved file with a symlink into the hub cache, so
+    /// llama.cpp keeps finding it
+    #[arg(long)]
+    pub leave_symlinks: bool,
+}
+
 #[derive(Args, Debug, PartialEq)]
 pub struct RemoveArgs {
     /// Override the HuggingFace Hub cache directory
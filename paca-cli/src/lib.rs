@@ -1,80 +1,460 @@
 #![cfg_attr(feature = "strict", deny(warnings))]
 
 pub mod cli;
+pub mod output;
 pub mod progress;
 
+use anyhow::Context;
 use cli::Cli;
+use output::emit;
+use paca::error::{ExitStatus, ModelRefError, PacaError};
+use serde_json::json;
+
+/// Executes the command-line interface logic, returning the status to
+/// exit with when the command ran but its outcome is worth reporting.
+pub async fn run(cli: Cli) -> anyhow::Result<ExitStatus> {
+    let format = cli.format;
+    let config_path = paca::env::config_path()?;
+    // Loaded first: the settings below fall back on the profile.
+    let config = paca::config::Config::load(&config_path)
+        .with_context(|| format!("Failed to load {}", config_path.display()))?;
+    let name = config.profile_name(cli.profile.as_deref()).to_string();
+    let profile = config.profile(&name);
+    // `config` takes the profile as it is, so a bad value can be fixed.
+    if !matches!(cli.command, cli::Commands::Config(_)) {
+        profile
+            .validate()
+            .with_context(|| format!("Invalid profile {name} in {}", config_path.display()))?;
+    }
+    // Where a command names no hub directory: the environment's, else
+    // the profile's.
+    let hub = |dir: Option<std::path::PathBuf>| -> anyhow::Result<_> {
+        match dir {
+            Some(dir) => Ok(Some(dir)),
+            None => Ok(Some(paca::env::hub_cache_with(profile.hub_dir.as_deref())?)),
+        }
+    };
+
+    let mut http = paca::http::HttpOptions::from_env_with(&profile)?;
+    cli.network.apply(&mut http);
+    let mut status = ExitStatus::Success;
 
-/// Executes the command-line interface logic
-pub async fn run(cli: Cli) -> anyhow::Result<()> {
     match cli.command {
         cli::Commands::Clean(args) => {
-            let result = paca::cache::clean::clean_cache(args.hub_dir)?;
-            if result.removed_files.is_empty() {
-                println!("Cache is clean.");
-            } else {
-                for file in &result.removed_files {
+            let result = paca::cache::clean::clean_cache(hub(args.hub_dir)?)?;
+            emit(format, &result, &result.removed_files, || {
+                if result.removed_files.is_empty() {
+                    println!("Cache is clean.");
+                } else {
+                    for file in &result.removed_files {
+                        println!("{}", file.path.display());
+                    }
+                }
+            })?;
+        }
+        cli::Commands::Config(command) => {
+            configure(&config_path, config, &name, command, format)?;
+        }
+        cli::Commands::Download(mut args) => {
+            args.hub_dir = hub(args.hub_dir)?;
+            let (model_ref, files) = download(&args, &profile, &http, &cli.progress).await?;
+            let document = json!({ "model_ref": model_ref, "files": files });
+            emit(format, &document, &files, || {
+                for file in &files {
                     println!("{}", file.path.display());
                 }
-            }
+            })?;
         }
-        cli::Commands::Download(args) => {
-            let manifest = paca::download::fetch_manifest(&args.model).await?;
-            let (_multi, reporters) = progress::build_progress(manifest.files());
-            let paths = paca::download::download_model(manifest, args.hub_dir, reporters).await?;
-            for path in &paths {
-                println!("{}", path.display());
+        cli::Commands::ExportConfig(args) => {
+            let template = match &args.template {
+                Some(path) => std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?
+                    .parse()?,
+                None => paca::export::Template::default(),
+            };
+            let config =
+                paca::export::export_config(args.config, hub(args.hub_dir)?, &template, &args.bin)?;
+            emit(format, &config, [&config], || {
+                print!("{}", config.contents);
+                for model_ref in &config.skipped {
+                    eprintln!("skipped {model_ref}: files missing from the cache");
+                }
+            })?;
+        }
+        cli::Commands::Import(args) => {
+            let report =
+                paca::import::import_models(args.from, args.source_dir, hub(args.hub_dir)?)?;
+            print_import_report(format, &report)?;
+        }
+        cli::Commands::Serve(args) => {
+            let mut server = paca::serve::Server::bind(&args.bind, hub(args.hub_dir)?)
+                .await?
+                .with_http(http.clone());
+            if let Some(upstream) = args.upstream {
+                let token = paca::auth::resolve_token_with(&upstream, &profile)?;
+                server = server.with_upstream_token(upstream, token.as_deref())?;
             }
+            eprintln!(
+                "Serving {} at http://{}",
+                server.hub_dir().display(),
+                server.local_addr()?
+            );
+            server.run().await?;
         }
         cli::Commands::Version => {
-            println!("paca {}", env!("CARGO_PKG_VERSION"));
+            let version = env!("CARGO_PKG_VERSION");
+            let document = json!({ "version": version });
+            emit(format, &document, [&document], || {
+                println!("paca {version}")
+            })?;
+        }
+        cli::Commands::LinkLlama(args) => {
+            let paths = paca::llama::link_model(&args.model, hub(args.hub_dir)?, args.llama_cache)?;
+            let records: Vec<_> = paths.iter().map(|path| json!({ "path": path })).collect();
+            emit(format, &json!({ "paths": paths }), records, || {
+                for path in &paths {
+                    println!("{}", path.display());
+                }
+            })?;
         }
         cli::Commands::List(args) => {
-            let entries = paca::cache::list_models(args.hub_dir)?;
-            if entries.is_empty() {
-                println!("No downloaded models found.");
-            } else {
-                let rows: Vec<(String, String)> = entries
-                    .iter()
-                    .map(|e| {
-                        (
-                            e.model_ref.to_string(),
-                            humansize::format_size(e.size, humansize::BINARY),
-                        )
-                    })
-                    .collect();
-                let model_width = rows.iter().map(|(m, _)| m.len()).max().unwrap_or(0);
-                for (model, size) in &rows {
-                    println!("{model:<model_width$}  {size}");
+            let entries = paca::cache::list_models(hub(args.hub_dir)?)?;
+            emit(format, &json!({ "models": entries }), &entries, || {
+                print_model_table(&entries)
+            })?;
+        }
+        cli::Commands::Outdated(args) => {
+            let report = builder(&profile, &http, hub(args.hub_dir)?)
+                .build()?
+                .outdated()
+                .await?;
+            emit(format, &report, output::outdated_records(&report), || {
+                print_outdated_table(&report)
+            })?;
+            status = report.exit_status();
+        }
+        cli::Commands::Login => {
+            let token = read_token()?;
+            let path = paca::auth::login(&token)?;
+            let document = json!({ "token_path": path });
+            emit(format, &document, [&document], || {
+                println!("Token saved to {}", path.display());
+            })?;
+        }
+        cli::Commands::Logout => {
+            let removed = paca::auth::logout()?;
+            let document = json!({ "removed": removed });
+            emit(format, &document, [&document], || {
+                if removed {
+                    println!("Token removed.");
+                } else {
+                    println!("Not logged in.");
+                }
+            })?;
+        }
+        cli::Commands::Migrate(cli::MigrateSource::LlamaCache(args)) => {
+            let report = paca::import::migrate_llama_cache(
+                args.cache_dir,
+                hub(args.hub_dir)?,
+                args.leave_symlinks,
+            )?;
+            print_import_report(format, &report)?;
+        }
+        cli::Commands::Path(args) => {
+            let model_ref: paca::model::ModelRef = args.model.parse()?;
+            let local = paca::cache::resolve_local_model(&model_ref, hub(args.hub_dir)?)?;
+            if format != output::Format::Table {
+                // The whole model, whichever of the files was asked for.
+                emit(format, &local, [&local], || {})?;
+            } else if args.mmproj {
+                let mmproj = local
+                    .mmproj
+                    .ok_or_else(|| anyhow::anyhow!("{model_ref} has no multimodal projector"))?;
+                println!("{}", mmproj.display());
+            } else if args.all {
+                for path in local.shards.iter().chain(&local.mmproj) {
+                    println!("{}", path.display());
+                }
+            } else if args.shell_env {
+                println!("PACA_MODEL={}", shell_quote(&local.model));
+                if let Some(mmproj) = &local.mmproj {
+                    println!("PACA_MMPROJ={}", shell_quote(mmproj));
                 }
+            } else {
+                println!("{}", local.model.display());
             }
         }
-        cli::Commands::Outdated(args) => {
-            let report = paca::cache::check_outdated_models(args.hub_dir).await?;
+        cli::Commands::Run(mut args) => {
+            args.hub_dir = hub(args.hub_dir)?;
+            let local = resolve_or_pull(&args, &profile, &http, &cli.progress).await?;
+            let mut command = if args.exec {
+                paca::run::custom_command(&args.args, &local)?
+            } else {
+                paca::run::llama_command(&args.bin, &local, &args.args)?
+            };
+            // Only returns if the program could not be started.
+            let error = std::os::unix::process::CommandExt::exec(&mut command);
+            anyhow::bail!("Failed to run {:?}: {error}", command.get_program());
+        }
+        cli::Commands::Remove(args) => {
+            let result =
+                paca::cache::remove::remove_model_with(&args.target, hub(args.hub_dir)?, &profile)?;
+            let records = result
+                .removed_files
+                .iter()
+                .map(|path| json!({ "path": path }));
+            emit(format, &result, records, || {
+                for path in &result.removed_files {
+                    println!("{}", path.display());
+                }
+            })?;
+        }
+        cli::Commands::Whoami => {
+            let who = paca::auth::whoami_with(&profile, &http).await?;
+            emit(format, &who, [&who], || {
+                println!("{}", who.name);
+                if let Some(token_name) = &who.token_name {
+                    let role = who.token_role.as_deref().unwrap_or("unknown");
+                    println!("token: {token_name} ({role})");
+                }
+            })?;
+        }
+    }
+    Ok(status)
+}
 
-            for repo in &report.unreachable {
-                eprintln!("error: unable to check {}: {}", repo.repo, repo.reason);
+/// The status to exit with after `error`: the first [`PacaError`] in its
+/// chain decides, since context only wraps it.
+pub fn exit_status(error: &anyhow::Error) -> ExitStatus {
+    error
+        .chain()
+        .find_map(|cause| {
+            if let Some(error) = cause.downcast_ref::<PacaError>() {
+                Some(error.exit_status())
+            } else {
+                cause
+                    .downcast_ref::<ModelRefError>()
+                    .map(|_| ExitStatus::InvalidReference)
             }
+        })
+        .unwrap_or(ExitStatus::Failure)
+}
 
-            if !report.outdated.is_empty() {
-                for model in &report.outdated {
-                    println!("{}  {}", model.model_ref, model.filename);
+/// A handle on the cache at `hub_dir`, else the profile's, with the
+/// profile's settings and the connection options flags and environment
+/// give.
+fn builder(
+    profile: &paca::config::Profile,
+    http: &paca::http::HttpOptions,
+    hub_dir: Option<std::path::PathBuf>,
+) -> paca::PacaBuilder {
+    let builder = paca::Paca::builder()
+        .profile(profile.clone())
+        .http(http.clone());
+    match hub_dir {
+        Some(hub_dir) => builder.hub_dir(hub_dir),
+        None => builder,
+    }
+}
+
+/// Downloads a model, reporting progress as `progress` asks, returning
+/// the reference it resolved to and the downloaded files.
+async fn download(
+    args: &cli::ModelArgs,
+    profile: &paca::config::Profile,
+    http: &paca::http::HttpOptions,
+    progress: &cli::ProgressArgs,
+) -> anyhow::Result<(paca::model::ModelRef, Vec<paca::download::DownloadedFile>)> {
+    let (registry, model) =
+        paca::registry::for_model_with(&args.registry, &args.model, profile, http)?;
+    let paca = builder(profile, http, args.hub_dir.clone())
+        .registry(registry)
+        .build()?;
+    let mut manifest = paca.manifest(&model).await?;
+    if args.link_llama {
+        manifest = manifest.with_llama_cache(paca::env::llama_cache()?);
+    }
+    if manifest.tag_defaulted() {
+        // stderr, so stdout stays a clean list of paths for scripts.
+        eprintln!("Using default tag: {}", manifest.model_ref());
+    }
+    let model_ref = manifest.model_ref().clone();
+    let (display, reporters) =
+        progress::build_progress(progress.mode, progress.interval, manifest.files());
+    let files = paca.download_manifest_with(manifest, reporters).await?;
+    display.finish();
+    Ok((model_ref, files))
+}
+
+/// Finds the model to run in the cache, downloading it first when it is
+/// missing and `--pull` was given.
+async fn resolve_or_pull(
+    args: &cli::RunArgs,
+    profile: &paca::config::Profile,
+    http: &paca::http::HttpOptions,
+    progress: &cli::ProgressArgs,
+) -> anyhow::Result<paca::cache::LocalModel> {
+    let (_, model) = paca::registry::for_model_with(&args.registry, &args.model, profile, http)?;
+    let installed = model
+        .parse()
+        .map_err(PacaError::from)
+        .and_then(|model_ref| paca::cache::resolve_local_model(&model_ref, args.hub_dir.clone()));
+    // An untagged reference is missing too: the download picks the tag.
+    let missing = matches!(
+        installed,
+        Err(PacaError::ModelNotInstalled(_) | PacaError::ModelRef(ModelRefError::MissingTag))
+    );
+    if !(missing && args.pull) {
+        return Ok(installed?);
+    }
+
+    let model_args = cli::ModelArgs {
+        hub_dir: args.hub_dir.clone(),
+        registry: args.registry.clone(),
+        link_llama: false,
+        model: args.model.clone(),
+    };
+    let (model_ref, _) = download(&model_args, profile, http, progress).await?;
+    Ok(paca::cache::resolve_local_model(
+        &model_ref,
+        args.hub_dir.clone(),
+    )?)
+}
+
+/// Runs `paca config`, saving the file after a change.
+fn configure(
+    path: &std::path::Path,
+    mut config: paca::config::Config,
+    profile: &str,
+    command: cli::ConfigCommand,
+    format: output::Format,
+) -> anyhow::Result<()> {
+    match command {
+        cli::ConfigCommand::List => {
+            let mut entries = config.profile(profile).entries();
+            for (key, value) in &mut entries {
+                // Tokens stay out of terminal scrollback; `get` shows one.
+                if key.starts_with("token.") {
+                    *value = String::from("<hidden>");
                 }
-            } else if report.unreachable.is_empty() {
-                // Only claim everything is current when every repo answered.
-                println!("All downloaded models are up to date.");
             }
+            let document: serde_json::Map<_, _> = entries
+                .iter()
+                .map(|(key, value)| (key.clone(), json!(value)))
+                .collect();
+            let records = entries
+                .iter()
+                .map(|(key, value)| json!({ "key": key, "value": value }));
+            emit(format, &document, records, || {
+                for (key, value) in &entries {
+                    println!("{key} = {value}");
+                }
+            })?;
         }
-        cli::Commands::Remove(args) => {
-            let result = paca::cache::remove::remove_model(&args.target, args.hub_dir)?;
-            for path in &result.removed_files {
-                println!("{}", path.display());
+        cli::ConfigCommand::Get { key } => match config.profile(profile).get(&key)? {
+            Some(value) => {
+                let document = json!({ "key": key, "value": value });
+                emit(format, &document, [&document], || println!("{value}"))?;
             }
+            None => anyhow::bail!("{key} is not set in profile {profile}"),
+        },
+        cli::ConfigCommand::Set { key, value } => {
+            config.profile_mut(profile).set(&key, &value)?;
+            config.save(path)?;
+        }
+        cli::ConfigCommand::Unset { key } => {
+            config.profile_mut(profile).unset(&key)?;
+            config.save(path)?;
         }
     }
     Ok(())
 }
 
+fn print_import_report(
+    format: output::Format,
+    report: &paca::import::ImportReport,
+) -> anyhow::Result<()> {
+    emit(format, report, output::import_records(report), || {
+        for file in &report.imported {
+            println!(
+                "{}  {} ({})",
+                file.model_ref,
+                file.path.display(),
+                file.method
+            );
+        }
+        for model in &report.unmapped {
+            eprintln!("skipped {}: {}", model.path.display(), model.reason);
+        }
+        if report.imported.is_empty() && report.unmapped.is_empty() {
+            println!("No models found to import.");
+        }
+    })
+}
+
+/// Models and their sizes in aligned columns.
+fn print_model_table(entries: &[paca::cache::ModelEntry]) {
+    if entries.is_empty() {
+        println!("No downloaded models found.");
+        return;
+    }
+    let rows: Vec<(String, String)> = entries
+        .iter()
+        .map(|e| {
+            (
+                e.model_ref.to_string(),
+                humansize::format_size(e.size, humansize::BINARY),
+            )
+        })
+        .collect();
+    let model_width = rows.iter().map(|(m, _)| m.len()).max().unwrap_or(0);
+    for (model, size) in &rows {
+        println!("{model:<model_width$}  {size}");
+    }
+}
+
+fn print_outdated_table(report: &paca::cache::OutdatedReport) {
+    for repo in &report.unreachable {
+        eprintln!("error: unable to check {}: {}", repo.repo, repo.reason);
+    }
+
+    for repo in &report.skipped_offline {
+        println!("{repo}  skipped (offline)");
+    }
+
+    for repo in &report.unchecked {
+        println!("{}  skipped (imported from {})", repo.repo, repo.origin);
+    }
+
+    if !report.outdated.is_empty() {
+        for model in &report.outdated {
+            println!("{}  {}", model.model_ref, model.filename);
+        }
+    } else if report.unreachable.is_empty() && report.skipped_offline.is_empty() {
+        // Only claim everything is current when every repo answered.
+        println!("All downloaded models are up to date.");
+    }
+}
+
+/// Single-quotes a path for a POSIX shell.
+fn shell_quote(path: &std::path::Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', "'\\''"))
+}
+
+/// Reads the token without echoing it when attached to a terminal, or as
+/// a single line when piped (`paca login < token.txt`).
+fn read_token() -> anyhow::Result<String> {
+    use std::io::IsTerminal;
+
+    if std::io::stdin().is_terminal() {
+        return Ok(rpassword::prompt_password("Token: ")?);
+    }
+
+    let mut token = String::new();
+    std::io::stdin().read_line(&mut token)?;
+    Ok(token)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -132,6 +512,8 @@ mod tests {
             cli::Commands::Download(ModelArgs {
                 hub_dir: None,
                 model: String::from("owner/model:tag"),
+                registry: String::from("huggingface"),
+                link_llama: false,
             })
         );
     }
@@ -152,16 +534,459 @@ mod tests {
             cli::Commands::Download(ModelArgs {
                 hub_dir: Some(PathBuf::from("/tmp/models")),
                 model: String::from("owner/model:tag"),
+                registry: String::from("huggingface"),
+                link_llama: false,
             })
         );
     }
 
+    #[test]
+    fn cli_parses_download_without_a_tag() {
+        let cli = Cli::try_parse_from(["paca", "download", "owner/model"]).unwrap();
+        assert_eq!(
+            cli.command,
+            cli::Commands::Download(ModelArgs {
+                hub_dir: None,
+                model: String::from("owner/model"),
+                registry: String::from("huggingface"),
+                link_llama: false,
+            })
+        );
+    }
+
+    #[test]
+    fn cli_parses_download_with_registry() {
+        let cli = Cli::try_parse_from([
+            "paca",
+            "download",
+            "--registry",
+            "modelscope",
+            "owner/model:tag",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            cli::Commands::Download(ModelArgs {
+                hub_dir: None,
+                model: String::from("owner/model:tag"),
+                registry: String::from("modelscope"),
+                link_llama: false,
+            })
+        );
+    }
+
+    #[test]
+    fn cli_parses_download_with_link_llama() {
+        let cli =
+            Cli::try_parse_from(["paca", "download", "--link-llama", "owner/model:tag"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            cli::Commands::Download(ModelArgs {
+                link_llama: true,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn cli_parses_link_llama_subcommand() {
+        let cli = Cli::try_parse_from(["paca", "link-llama", "owner/model:tag"]).unwrap();
+        assert_eq!(
+            cli.command,
+            cli::Commands::LinkLlama(cli::LinkLlamaArgs {
+                hub_dir: None,
+                llama_cache: None,
+                model: String::from("owner/model:tag"),
+            })
+        );
+    }
+
+    #[test]
+    fn cli_parses_path_subcommand() {
+        let cli = Cli::try_parse_from(["paca", "path", "--all", "owner/model:tag"]).unwrap();
+        assert_eq!(
+            cli.command,
+            cli::Commands::Path(cli::PathArgs {
+                hub_dir: None,
+                mmproj: false,
+                all: true,
+                shell_env: false,
+                model: String::from("owner/model:tag"),
+            })
+        );
+    }
+
+    #[test]
+    fn cli_rejects_conflicting_path_output_modes() {
+        let result = Cli::try_parse_from(["paca", "path", "--mmproj", "--all", "owner/model:tag"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(
+            shell_quote(std::path::Path::new("/models/it's.gguf")),
+            "'/models/it'\\''s.gguf'"
+        );
+    }
+
+    #[test]
+    fn cli_parses_run_subcommand_with_passthrough_args() {
+        let cli = Cli::try_parse_from([
+            "paca",
+            "run",
+            "--pull",
+            "--bin",
+            "llama-cli",
+            "owner/model:tag",
+            "--",
+            "--ctx-size",
+            "32768",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            cli::Commands::Run(cli::RunArgs {
+                hub_dir: None,
+                registry: String::from("huggingface"),
+                pull: true,
+                bin: String::from("llama-cli"),
+                exec: false,
+                model: String::from("owner/model:tag"),
+                args: vec![String::from("--ctx-size"), String::from("32768")],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn run_without_pull_fails_for_a_missing_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let args = cli::RunArgs {
+            hub_dir: Some(dir.path().to_path_buf()),
+            registry: String::from("huggingface"),
+            pull: false,
+            bin: String::from("llama-server"),
+            exec: false,
+            model: String::from("owner/model:tag"),
+            args: Vec::new(),
+        };
+
+        let progress = cli::ProgressArgs {
+            mode: progress::ProgressMode::Auto,
+            interval: std::time::Duration::from_secs(1),
+        };
+
+        let profile = paca::config::Profile::default();
+        let http = paca::http::HttpOptions::default();
+        let error = resolve_or_pull(&args, &profile, &http, &progress)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<PacaError>(),
+            Some(PacaError::ModelNotInstalled(_))
+        ));
+    }
+
+    #[test]
+    fn cli_parses_export_config_subcommand() {
+        let cli = Cli::try_parse_from([
+            "paca",
+            "export-config",
+            "llama-swap",
+            "--template",
+            "models.ini",
+            "--bin",
+            "/opt/llama-server",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            cli::Commands::ExportConfig(cli::ExportConfigArgs {
+                hub_dir: None,
+                config: paca::export::ConfigFormat::LlamaSwap,
+                template: Some(PathBuf::from("models.ini")),
+                bin: "/opt/llama-server".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn exit_status_looks_through_context() {
+        let error = anyhow::Error::from(PacaError::ModelNotInstalled(String::from("owner/model")))
+            .context("Failed to remove");
+        assert_eq!(exit_status(&error), ExitStatus::NotInstalled);
+
+        let error = anyhow::Error::from(ModelRefError::MissingOwner);
+        assert_eq!(exit_status(&error), ExitStatus::InvalidReference);
+
+        assert_eq!(exit_status(&anyhow::anyhow!("boom")), ExitStatus::Failure);
+    }
+
+    #[test]
+    fn cli_parses_progress_options() {
+        let cli = Cli::try_parse_from([
+            "paca",
+            "download",
+            "--progress",
+            "json",
+            "--progress-interval",
+            "0.25",
+            "owner/model:tag",
+        ])
+        .unwrap();
+        assert_eq!(cli.progress.mode, progress::ProgressMode::Json);
+        assert_eq!(cli.progress.interval, std::time::Duration::from_millis(250));
+
+        let cli = Cli::try_parse_from(["paca", "download", "owner/model:tag"]).unwrap();
+        assert_eq!(cli.progress.mode, progress::ProgressMode::Auto);
+        assert!(Cli::try_parse_from(["paca", "list", "--progress", "dots"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_a_global_output_format() {
+        let cli = Cli::try_parse_from(["paca", "list", "--format", "ndjson"]).unwrap();
+        assert_eq!(cli.format, output::Format::Ndjson);
+        let cli = Cli::try_parse_from(["paca", "outdated"]).unwrap();
+        assert_eq!(cli.format, output::Format::Table);
+        assert!(Cli::try_parse_from(["paca", "--format", "yaml", "list"]).is_err());
+    }
+
+    #[test]
+    fn cli_rejects_unknown_export_format() {
+        let result = Cli::try_parse_from(["paca", "export-config", "ollama"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn cli_parses_serve_subcommand() {
+        let cli = Cli::try_parse_from(["paca", "serve", "--bind", "0.0.0.0:8080"]).unwrap();
+        assert_eq!(
+            cli.command,
+            cli::Commands::Serve(cli::ServeArgs {
+                hub_dir: None,
+                bind: String::from("0.0.0.0:8080"),
+                upstream: None,
+            })
+        );
+    }
+
+    #[test]
+    fn cli_parses_serve_upstream() {
+        let cli =
+            Cli::try_parse_from(["paca", "serve", "--upstream", "https://huggingface.co"]).unwrap();
+        assert_eq!(
+            cli.command,
+            cli::Commands::Serve(cli::ServeArgs {
+                hub_dir: None,
+                bind: String::from("127.0.0.1:8080"),
+                upstream: Some(String::from("https://huggingface.co")),
+            })
+        );
+    }
+
+    #[test]
+    fn cli_parses_network_flags_after_the_subcommand() {
+        let cli = Cli::try_parse_from([
+            "paca",
+            "download",
+            "--proxy",
+            "http://proxy.lan:3128",
+            "--ca-cert",
+            "/etc/ca/corp.pem",
+            "--no-system-roots",
+            "--resolve",
+            "huggingface.co:10.0.0.5",
+            "--bind-address",
+            "192.168.1.20",
+            "--user-agent-suffix",
+            "ci/1",
+            "owner/model:tag",
+        ])
+        .unwrap();
+
+        let mut http = paca::http::HttpOptions::default();
+        cli.network.apply(&mut http);
+
+        assert_eq!(http.proxy.as_deref(), Some("http://proxy.lan:3128"));
+        assert_eq!(http.root_certificates, [PathBuf::from("/etc/ca/corp.pem")]);
+        assert!(!http.system_roots);
+        assert_eq!(
+            http.resolve,
+            [("huggingface.co".to_string(), "10.0.0.5".parse().unwrap())]
+        );
+        assert_eq!(http.local_address, Some("192.168.1.20".parse().unwrap()));
+        assert_eq!(http.user_agent_suffix.as_deref(), Some("ci/1"));
+    }
+
+    #[test]
+    fn cli_network_flags_override_the_environment() {
+        let cli = Cli::try_parse_from(["paca", "--proxy", "http://flag:3128", "list"]).unwrap();
+        let mut http = paca::http::HttpOptions {
+            proxy: Some("http://env:3128".to_string()),
+            no_proxy: Some("localhost".to_string()),
+            ..paca::http::HttpOptions::default()
+        };
+        cli.network.apply(&mut http);
+
+        assert_eq!(http.proxy.as_deref(), Some("http://flag:3128"));
+        assert_eq!(http.no_proxy.as_deref(), Some("localhost"));
+    }
+
+    #[test]
+    fn cli_parses_concurrency_and_rate_limit() {
+        let cli = Cli::try_parse_from([
+            "paca",
+            "download",
+            "--concurrency",
+            "8",
+            "--rate-limit",
+            "50M",
+            "owner/model:tag",
+        ])
+        .unwrap();
+        let mut http = paca::http::HttpOptions {
+            rate_limit: Some(1024),
+            ..paca::http::HttpOptions::default()
+        };
+        cli.network.apply(&mut http);
+
+        assert_eq!(http.concurrency, 8);
+        assert_eq!(http.rate_limit, Some(50 * 1024 * 1024));
+        assert!(Cli::try_parse_from(["paca", "list", "--concurrency", "0"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_config_subcommands() {
+        let cli = Cli::try_parse_from(["paca", "--profile", "work", "config", "list"]).unwrap();
+        assert_eq!(cli.profile.as_deref(), Some("work"));
+        assert_eq!(cli.command, cli::Commands::Config(cli::ConfigCommand::List));
+
+        let cli = Cli::try_parse_from(["paca", "config", "set", "concurrency", "8"]).unwrap();
+        assert_eq!(
+            cli.command,
+            cli::Commands::Config(cli::ConfigCommand::Set {
+                key: String::from("concurrency"),
+                value: String::from("8"),
+            })
+        );
+        assert!(Cli::try_parse_from(["paca", "config", "get"]).is_err());
+    }
+
+    #[test]
+    fn config_set_and_unset_write_the_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("paca").join("config.toml");
+        let table = output::Format::Table;
+        let set = |key: &str, value: &str| cli::ConfigCommand::Set {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+
+        configure(
+            &path,
+            Default::default(),
+            "work",
+            set("hub-dir", "/srv/hub"),
+            table,
+        )
+        .unwrap();
+        let config = paca::config::Config::load(&path).unwrap();
+        configure(&path, config, "work", set("rate-limit", "1M"), table).unwrap();
+        let config = paca::config::Config::load(&path).unwrap();
+        let unset = cli::ConfigCommand::Unset {
+            key: String::from("hub-dir"),
+        };
+        configure(&path, config, "work", unset, table).unwrap();
+
+        let config = paca::config::Config::load(&path).unwrap();
+        assert_eq!(config.profile("work").hub_dir, None);
+        assert_eq!(config.profile("work").rate_limit, Some(1024 * 1024));
+        assert_eq!(config.profile("default").rate_limit, None);
+        assert!(configure(&path, config, "work", set("colour", "blue"), table).is_err());
+    }
+
+    #[test]
+    fn config_set_repairs_a_profile_that_fails_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[profiles.default]\nconcurrency = 0\n").unwrap();
+
+        let config = paca::config::Config::load(&path).unwrap();
+        assert!(config.profile("default").validate().is_err());
+        let set = cli::ConfigCommand::Set {
+            key: String::from("concurrency"),
+            value: String::from("4"),
+        };
+        configure(&path, config, "default", set, output::Format::Table).unwrap();
+
+        let config = paca::config::Config::load(&path).unwrap();
+        assert!(config.profile("default").validate().is_ok());
+    }
+
+    #[test]
+    fn cli_rejects_a_malformed_resolve() {
+        let result = Cli::try_parse_from(["paca", "list", "--resolve", "huggingface.co"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn cli_parses_download_requires_model_argument() {
         let result = Cli::try_parse_from(["paca", "download"]);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn cli_parses_import_subcommand() {
+        let cli = Cli::try_parse_from(["paca", "import", "--from", "ollama"]).unwrap();
+        assert_eq!(
+            cli.command,
+            cli::Commands::Import(cli::ImportArgs {
+                hub_dir: None,
+                from: paca::import::ImportSource::Ollama,
+                source_dir: None,
+            })
+        );
+    }
+
+    #[test]
+    fn cli_parses_import_requires_a_source() {
+        assert!(Cli::try_parse_from(["paca", "import"]).is_err());
+        assert!(Cli::try_parse_from(["paca", "import", "--from", "gpt4all"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_migrate_llama_cache() {
+        let cli =
+            Cli::try_parse_from(["paca", "migrate", "llama-cache", "--leave-symlinks"]).unwrap();
+        assert_eq!(
+            cli.command,
+            cli::Commands::Migrate(cli::MigrateSource::LlamaCache(cli::MigrateArgs {
+                hub_dir: None,
+                cache_dir: None,
+                leave_symlinks: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn cli_parses_login_subcommand() {
+        let cli = Cli::try_parse_from(["paca", "login"]).unwrap();
+        assert_eq!(cli.command, cli::Commands::Login);
+    }
+
+    #[test]
+    fn cli_parses_logout_subcommand() {
+        let cli = Cli::try_parse_from(["paca", "logout"]).unwrap();
+        assert_eq!(cli.command, cli::Commands::Logout);
+    }
+
+    #[test]
+    fn cli_parses_whoami_subcommand() {
+        let cli = Cli::try_parse_from(["paca", "whoami"]).unwrap();
+        assert_eq!(cli.command, cli::Commands::Whoami);
+    }
+
     #[test]
     fn cli_parses_outdated_subcommand() {
         let result = Cli::try_parse_from(["paca", "outdated"]);
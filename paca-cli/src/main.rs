@@ -1,9 +1,17 @@
-use anyhow::Result;
+use std::process::ExitCode;
+
 use clap::Parser;
 use paca_cli::cli::Cli;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
-    paca_cli::run(cli).await
+    match paca_cli::run(cli).await {
+        Ok(status) => status.into(),
+        Err(error) => {
+            // The same report anyhow gives when main returns the error.
+            eprintln!("Error: {error:?}");
+            paca_cli::exit_status(&error).into()
+        }
+    }
 }
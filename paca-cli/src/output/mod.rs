@@ -0,0 +1,171 @@
+//! Printing command results: text for people, or JSON for scripts.
+//!
+//! `json` prints one document per command and `ndjson` one record per
+//! line, so a long listing can be streamed through `jq -c` or a line
+//! reader. Reports that mix kinds of record tag each with a `status`.
+
+use std::io::{self, Write};
+
+use paca::cache::{OutdatedModelInfo, OutdatedReport, UncheckedRepo, UnreachableRepo};
+use paca::import::{ImportReport, ImportedFile, UnmappedModel};
+use serde::Serialize;
+
+/// How results are printed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum Format {
+    /// One JSON document
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Text for reading
+    #[default]
+    Table,
+}
+
+/// Prints `document` as JSON, or `records` one per line as NDJSON;
+/// `table` prints the text form.
+pub fn emit<T, R>(
+    format: Format,
+    document: &T,
+    records: impl IntoIterator<Item = R>,
+    table: impl FnOnce(),
+) -> anyhow::Result<()>
+where
+    T: Serialize + ?Sized,
+    R: Serialize,
+{
+    if format == Format::Table {
+        table();
+        return Ok(());
+    }
+    let mut out = io::stdout().lock();
+    write_json(&mut out, format, document, records)?;
+    Ok(out.flush()?)
+}
+
+fn write_json<T, R>(
+    out: &mut impl Write,
+    format: Format,
+    document: &T,
+    records: impl IntoIterator<Item = R>,
+) -> serde_json::Result<()>
+where
+    T: Serialize + ?Sized,
+    R: Serialize,
+{
+    if format == Format::Json {
+        serde_json::to_writer_pretty(&mut *out, document)?;
+        writeln!(out).map_err(serde_json::Error::io)?;
+        return Ok(());
+    }
+    for record in records {
+        serde_json::to_writer(&mut *out, &record)?;
+        writeln!(out).map_err(serde_json::Error::io)?;
+    }
+    Ok(())
+}
+
+/// A line of `outdated --format ndjson`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum OutdatedRecord<'a> {
+    Outdated(&'a OutdatedModelInfo),
+    SkippedOffline { repo: &'a str },
+    Unchecked(&'a UncheckedRepo),
+    Unreachable(&'a UnreachableRepo),
+}
+
+pub fn outdated_records(report: &OutdatedReport) -> impl Iterator<Item = OutdatedRecord<'_>> {
+    let outdated = report.outdated.iter().map(OutdatedRecord::Outdated);
+    let skipped = report
+        .skipped_offline
+        .iter()
+        .map(|repo| OutdatedRecord::SkippedOffline { repo });
+    let unchecked = report.unchecked.iter().map(OutdatedRecord::Unchecked);
+    let unreachable = report.unreachable.iter().map(OutdatedRecord::Unreachable);
+    outdated.chain(skipped).chain(unchecked).chain(unreachable)
+}
+
+/// A line of `import` or `migrate` with `--format ndjson`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ImportRecord<'a> {
+    Imported(&'a ImportedFile),
+    Unmapped(&'a UnmappedModel),
+}
+
+pub fn import_records(report: &ImportReport) -> impl Iterator<Item = ImportRecord<'_>> {
+    let imported = report.imported.iter().map(ImportRecord::Imported);
+    let unmapped = report.unmapped.iter().map(ImportRecord::Unmapped);
+    imported.chain(unmapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render<T: Serialize, R: Serialize>(
+        format: Format,
+        document: &T,
+        records: impl IntoIterator<Item = R>,
+    ) -> String {
+        let mut out = Vec::new();
+        write_json(&mut out, format, document, records).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn json_prints_the_document_and_ndjson_the_records() {
+        let document = json!({ "models": [1, 2] });
+
+        let text = render(Format::Json, &document, [1, 2]);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&text).unwrap(),
+            document
+        );
+
+        assert_eq!(render(Format::Ndjson, &document, [1, 2]), "1\n2\n");
+    }
+
+    #[test]
+    fn outdated_records_are_tagged_by_status() {
+        let report = OutdatedReport {
+            outdated: vec![OutdatedModelInfo {
+                model_ref: "owner/model:Q4_K_M".parse().unwrap(),
+                filename: "model-Q4_K_M.gguf".to_string(),
+                file_path: "/hub/model-Q4_K_M.gguf".into(),
+            }],
+            skipped_offline: vec!["owner/offline".to_string()],
+            unchecked: vec![UncheckedRepo {
+                origin: "lmstudio".to_string(),
+                repo: "owner/imported".to_string(),
+            }],
+            unreachable: vec![UnreachableRepo {
+                reason: "timed out".to_string(),
+                repo: "owner/gone".to_string(),
+            }],
+        };
+
+        let lines: Vec<serde_json::Value> =
+            render(Format::Ndjson, &report, outdated_records(&report))
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+
+        assert_eq!(
+            lines,
+            [
+                json!({
+                    "status": "outdated",
+                    "model_ref": "owner/model:Q4_K_M",
+                    "filename": "model-Q4_K_M.gguf",
+                    "file_path": "/hub/model-Q4_K_M.gguf",
+                }),
+                json!({ "status": "skipped_offline", "repo": "owner/offline" }),
+                json!({ "status": "unchecked", "origin": "lmstudio", "repo": "owner/imported" }),
+                json!({ "status": "unreachable", "reason": "timed out", "repo": "owner/gone" }),
+            ]
+        );
+    }
+}
@@ -1,63 +1,731 @@
-use std::sync::Arc;
+use std::collections::VecDeque;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
 
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use paca::progress::FileProgress;
+use indicatif::{
+    HumanBytes, HumanDuration, MultiProgress, ProgressBar, ProgressDrawTarget, ProgressState,
+    ProgressStyle,
+};
+use paca::progress::{FileProgress, ProgressEvent};
+use serde_json::{Value, json};
 
+/// How download progress is shown.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum ProgressMode {
+    /// Bars on a terminal, JSON lines otherwise
+    #[default]
+    Auto,
+    /// Progress bars
+    Bar,
+    /// One JSON event per line on stderr
+    Json,
+}
+
+impl ProgressMode {
+    /// Settles `auto` by whether stderr, where progress goes, is a
+    /// terminal.
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto if io::stderr().is_terminal() => Self::Bar,
+            Self::Auto => Self::Json,
+            mode => mode,
+        }
+    }
+}
+
+/// Parses `--progress-interval`, in seconds.
+pub fn parse_interval(s: &str) -> Result<Duration, String> {
+    s.trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .filter(|interval| !interval.is_zero())
+        .ok_or_else(|| format!("'{s}' is not a positive number of seconds"))
+}
+
+/// A download's progress display, kept alive while the reporters run.
+pub enum Progress {
+    Bars(Arc<Bars>),
+    Json(Arc<JsonLog>),
+}
+
+impl Progress {
+    /// Reports the totals once every file is done.
+    pub fn finish(&self) {
+        match self {
+            Self::Bars(bars) => bars.total.finish(),
+            Self::Json(log) => log.summary(),
+        }
+    }
+}
+
+/// Builds one progress reporter per `(filename, size)` tuple: bars
+/// sharing one [`Bars`] display, or JSON reporters sharing one
+/// [`JsonLog`].
+pub fn build_progress<'a, I>(
+    mode: ProgressMode,
+    interval: Duration,
+    iter: I,
+) -> (Progress, Vec<Arc<dyn FileProgress>>)
+where
+    I: IntoIterator<Item = (&'a str, u64)>,
+{
+    if mode.resolve() == ProgressMode::Json {
+        let log = Arc::new(JsonLog::new(Box::new(io::stderr()), interval));
+        let reporters = iter
+            .into_iter()
+            .map(|(filename, size)| {
+                Arc::new(JsonProgress::new(&log, filename, size)) as Arc<dyn FileProgress>
+            })
+            .collect();
+        return (Progress::Json(log), reporters);
+    }
+
+    let bars = Arc::new(Bars::new(MultiProgress::new(), iter, fitting_bars));
+    let reporters = (0..bars.files.len())
+        .map(|idx| Arc::new(IndicatifProgress::new(&bars, idx)) as Arc<dyn FileProgress>)
+        .collect();
+    (Progress::Bars(bars), reporters)
+}
+
+/// How many file bars fit on the terminal below the total and above the
+/// summary line.
+fn fitting_bars() -> usize {
+    console::Term::stderr()
+        .size_checked()
+        .map_or(usize::MAX, |(rows, _)| usize::from(rows).saturating_sub(3))
+        .max(1)
+}
+
+/// The bars of one download: a total across every file on top, a bar
+/// for each file being fetched, and a summary line counting the files
+/// that are done, already cached, or waiting for room on the terminal.
+pub struct Bars {
+    multi: MultiProgress,
+    total: ProgressBar,
+    summary: ProgressBar,
+    files: Vec<ProgressBar>,
+    fetched: Arc<Fetched>,
+    /// How many file bars are shown at once, read again whenever one
+    /// is, so a resized terminal is followed.
+    limit: fn() -> usize,
+    state: Mutex<BarsState>,
+}
+
+#[derive(Default)]
+struct BarsState {
+    shown: Vec<Shown>,
+    /// Started files without a bar, in the order they started.
+    waiting: VecDeque<usize>,
+    visible: usize,
+    done: usize,
+    cached: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum Shown {
+    #[default]
+    Pending,
+    Visible,
+    Waiting,
+    /// In the cache already; counted, never drawn.
+    Cached,
+    Done,
+}
+
+/// Bytes fetched over the network, as opposed to found on disk, which
+/// is what the total's throughput and ETA are worked out from.
+#[derive(Default)]
+struct Fetched {
+    bytes: AtomicU64,
+    since: OnceLock<Instant>,
+}
+
+impl Fetched {
+    fn add(&self, bytes: u64) {
+        self.since.get_or_init(Instant::now);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn per_sec(&self) -> f64 {
+        self.since.get().map_or(0.0, |since| {
+            throughput(self.bytes.load(Ordering::Relaxed), since.elapsed())
+        })
+    }
+}
+
+impl Bars {
+    fn new<'a>(
+        multi: MultiProgress,
+        iter: impl IntoIterator<Item = (&'a str, u64)>,
+        limit: fn() -> usize,
+    ) -> Self {
+        let files: Vec<ProgressBar> = iter
+            .into_iter()
+            .map(|(filename, size)| {
+                // Drawn only once added to `multi`.
+                let bar = ProgressBar::with_draw_target(Some(size), ProgressDrawTarget::hidden());
+                bar.set_style(download_style());
+                bar.set_message(filename.to_string());
+                bar
+            })
+            .collect();
+        let fetched = Arc::new(Fetched::default());
+        let total = multi.add(ProgressBar::new(
+            files.iter().filter_map(ProgressBar::length).sum(),
+        ));
+        total.set_style(total_style(&fetched));
+        let summary = multi.add(ProgressBar::new(0));
+        summary.set_style(message_style());
+
+        let bars = Self {
+            multi,
+            total,
+            summary,
+            state: Mutex::new(BarsState {
+                shown: vec![Shown::Pending; files.len()],
+                ..BarsState::default()
+            }),
+            files,
+            fetched,
+            limit,
+        };
+        bars.update_summary(&bars.state());
+        bars
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, BarsState> {
+        self.state.lock().expect("progress bars poisoned")
+    }
+
+    /// Gives a started file a bar, or a place in line for one.
+    fn show(&self, idx: usize) {
+        let mut state = self.state();
+        if state.shown[idx] != Shown::Pending {
+            return;
+        }
+        if state.visible < (self.limit)() {
+            self.reveal(&mut state, idx);
+        } else {
+            state.shown[idx] = Shown::Waiting;
+            state.waiting.push_back(idx);
+        }
+        self.update_summary(&state);
+    }
+
+    fn reveal(&self, state: &mut BarsState, idx: usize) {
+        self.multi
+            .insert_before(&self.summary, self.files[idx].clone());
+        state.shown[idx] = Shown::Visible;
+        state.visible += 1;
+    }
+
+    /// Folds a file into the summary line, handing its bar's room to the
+    /// next file waiting for one.
+    fn done(&self, idx: usize) {
+        let mut state = self.state();
+        match state.shown[idx] {
+            Shown::Done => return,
+            Shown::Visible => {
+                self.multi.remove(&self.files[idx]);
+                state.visible -= 1;
+            }
+            Shown::Waiting => state.waiting.retain(|&waiting| waiting != idx),
+            Shown::Cached => state.cached += 1,
+            Shown::Pending => {}
+        }
+        state.shown[idx] = Shown::Done;
+        state.done += 1;
+        while state.visible < (self.limit)()
+            && let Some(next) = state.waiting.pop_front()
+        {
+            self.reveal(&mut state, next);
+        }
+        self.update_summary(&state);
+    }
+
+    fn update_summary(&self, state: &BarsState) {
+        let mut summary = format!("{} of {} files done", state.done, self.files.len());
+        if state.cached > 0 {
+            summary.push_str(&format!(", {} already cached", state.cached));
+        }
+        if !state.waiting.is_empty() {
+            summary.push_str(&format!(", {} more downloading", state.waiting.len()));
+        }
+        self.summary.set_message(summary);
+    }
+}
+
+/// Reports one file to its bar in a [`Bars`] display and to the total.
 pub struct IndicatifProgress {
-    bar: ProgressBar,
+    bars: Arc<Bars>,
+    idx: usize,
 }
 
 impl IndicatifProgress {
-    pub fn new(bar: ProgressBar) -> Self {
-        Self { bar }
+    pub fn new(bars: &Arc<Bars>, idx: usize) -> Self {
+        Self {
+            bars: Arc::clone(bars),
+            idx,
+        }
+    }
+
+    fn bar(&self) -> &ProgressBar {
+        &self.bars.files[self.idx]
     }
 }
 
 impl FileProgress for IndicatifProgress {
     fn start(&self, initial_position: u64) {
-        self.bar.set_style(download_style());
-        self.bar.set_position(initial_position);
+        // A retry starts again from what is on disk, which may be less
+        // than was counted before it failed.
+        let previous = self.bar().position();
+        self.bar().set_position(initial_position);
+        if initial_position >= previous {
+            self.bars.total.inc(initial_position - previous);
+        } else {
+            self.bars.total.dec(previous - initial_position);
+        }
+        self.bars.show(self.idx);
     }
 
     fn inc(&self, delta: u64) {
-        self.bar.inc(delta);
+        self.bar().inc(delta);
+        self.bars.total.inc(delta);
+        self.bars.fetched.add(delta);
     }
 
     fn println(&self, msg: &str) {
-        self.bar.println(msg);
+        // Progress is best-effort: a closed stderr must not fail the download.
+        let _ = self.bars.multi.println(msg);
+    }
+
+    fn event(&self, event: &ProgressEvent) {
+        match event {
+            ProgressEvent::Cached => {
+                let mut state = self.bars.state();
+                if state.shown[self.idx] == Shown::Pending {
+                    state.shown[self.idx] = Shown::Cached;
+                }
+            }
+            ProgressEvent::Retrying { .. } | ProgressEvent::MirrorFallback { .. } => {
+                self.println(&event.to_string());
+            }
+            _ => {}
+        }
     }
 
     fn finish(&self) {
-        self.bar.finish();
+        self.bar().finish();
+        self.bars.done(self.idx);
     }
 }
 
-/// Builds one progress reporter per `(filename, size)` tuple, attached
-/// to a shared [`MultiProgress`] so bars render together in the terminal.
-pub fn build_progress<'a, I>(iter: I) -> (MultiProgress, Vec<Arc<dyn FileProgress>>)
-where
-    I: IntoIterator<Item = (&'a str, u64)>,
-{
-    let multi = MultiProgress::new();
-    let reporters = iter
-        .into_iter()
-        .map(|(filename, size)| {
-            let bar = multi.add(ProgressBar::new(size));
-            bar.set_style(pending_style());
-            bar.set_message(filename.to_string());
-            Arc::new(IndicatifProgress::new(bar)) as Arc<dyn FileProgress>
-        })
-        .collect();
-    (multi, reporters)
+/// Where JSON progress events go, and the totals the closing `summary`
+/// event reports.
+pub struct JsonLog {
+    interval: Duration,
+    out: Mutex<Box<dyn Write + Send>>,
+    started: Instant,
+    totals: Mutex<Totals>,
 }
 
-fn pending_style() -> ProgressStyle {
+#[derive(Default)]
+struct Totals {
+    bytes: u64,
+    downloaded: u64,
+    files: usize,
+    retries: u64,
+    reused: u64,
+}
+
+impl JsonLog {
+    pub fn new(out: Box<dyn Write + Send>, interval: Duration) -> Self {
+        Self {
+            interval,
+            out: Mutex::new(out),
+            started: Instant::now(),
+            totals: Mutex::default(),
+        }
+    }
+
+    fn emit(&self, event: &str, mut fields: Value) {
+        fields["event"] = json!(event);
+        let mut out = self.out.lock().expect("progress output poisoned");
+        // Progress is best-effort: a closed stderr must not fail the download.
+        let _ = writeln!(out, "{fields}");
+    }
+
+    fn summary(&self) {
+        let totals = self.totals.lock().expect("progress totals poisoned");
+        let elapsed = self.started.elapsed();
+        self.emit(
+            "summary",
+            json!({
+                "files": totals.files,
+                "bytes": totals.bytes,
+                "downloaded": totals.downloaded,
+                "reused": totals.reused,
+                "retries": totals.retries,
+                "elapsed_secs": elapsed.as_secs_f64(),
+                "bytes_per_second": throughput(totals.downloaded, elapsed),
+            }),
+        );
+    }
+}
+
+/// Reports one file as JSON lines: `start`, a `progress` tick at most
+/// once per interval, one line per [`ProgressEvent`], and `finish`.
+pub struct JsonProgress {
+    file: String,
+    log: Arc<JsonLog>,
+    size: u64,
+    state: Mutex<FileState>,
+}
+
+struct FileState {
+    last_tick: Instant,
+    position: u64,
+    reused: u64,
+    /// Set by the first `start`; a retry starts again from where it is.
+    started: Option<Instant>,
+}
+
+impl JsonProgress {
+    pub fn new(log: &Arc<JsonLog>, file: &str, size: u64) -> Self {
+        let now = Instant::now();
+        Self {
+            file: file.to_string(),
+            log: Arc::clone(log),
+            size,
+            state: Mutex::new(FileState {
+                last_tick: now,
+                position: 0,
+                reused: 0,
+                started: None,
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, FileState> {
+        self.state.lock().expect("progress state poisoned")
+    }
+}
+
+impl FileProgress for JsonProgress {
+    fn start(&self, initial_position: u64) {
+        let mut state = self.state();
+        state.position = initial_position;
+        if state.started.is_some() {
+            return;
+        }
+        state.started = Some(Instant::now());
+        state.reused = initial_position;
+        drop(state);
+        self.log.emit(
+            "start",
+            json!({ "file": self.file, "size": self.size, "position": initial_position }),
+        );
+    }
+
+    fn inc(&self, delta: u64) {
+        let mut state = self.state();
+        state.position += delta;
+        if state.last_tick.elapsed() < self.log.interval {
+            return;
+        }
+        state.last_tick = Instant::now();
+        let position = state.position;
+        drop(state);
+        self.log.emit(
+            "progress",
+            json!({ "file": self.file, "size": self.size, "position": position }),
+        );
+    }
+
+    fn println(&self, msg: &str) {
+        self.log
+            .emit("message", json!({ "file": self.file, "message": msg }));
+    }
+
+    fn event(&self, event: &ProgressEvent) {
+        let file = &self.file;
+        let (name, fields) = match event {
+            ProgressEvent::Resolving => ("resolving", json!({ "file": file })),
+            ProgressEvent::ChunkStarted { idx, range } => (
+                "chunk_started",
+                json!({ "file": file, "idx": idx, "start": range.start, "end": range.end }),
+            ),
+            ProgressEvent::Retrying {
+                attempt,
+                delay,
+                error,
+            } => {
+                self.log
+                    .totals
+                    .lock()
+                    .expect("progress totals poisoned")
+                    .retries += 1;
+                (
+                    "retry",
+                    json!({
+                        "file": file,
+                        "attempt": attempt,
+                        "delay_secs": delay.as_secs_f64(),
+                        "error": error,
+                    }),
+                )
+            }
+            ProgressEvent::RateLimited { until } => {
+                let until = until
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default();
+                (
+                    "rate_limited",
+                    json!({ "file": file, "until": until.as_secs_f64() }),
+                )
+            }
+            ProgressEvent::MirrorFallback { from, to, reason } => (
+                "mirror_fallback",
+                json!({ "file": file, "from": from, "to": to, "reason": reason }),
+            ),
+            ProgressEvent::Verifying => ("verifying", json!({ "file": file })),
+            ProgressEvent::Finalizing => ("finalizing", json!({ "file": file })),
+            ProgressEvent::Cached => ("cached", json!({ "file": file })),
+            // Events added to paca later are left out until named here.
+            _ => return,
+        };
+        self.log.emit(name, fields);
+    }
+
+    fn finish(&self) {
+        let state = self.state();
+        let downloaded = state.position.saturating_sub(state.reused);
+        let elapsed = state
+            .started
+            .map_or(Duration::ZERO, |started| started.elapsed());
+        let reused = state.reused;
+        drop(state);
+
+        let mut totals = self.log.totals.lock().expect("progress totals poisoned");
+        totals.files += 1;
+        totals.bytes += self.size;
+        totals.downloaded += downloaded;
+        totals.reused += reused;
+        drop(totals);
+
+        self.log.emit(
+            "finish",
+            json!({
+                "file": self.file,
+                "size": self.size,
+                "downloaded": downloaded,
+                "reused": reused,
+                "elapsed_secs": elapsed.as_secs_f64(),
+                "bytes_per_second": throughput(downloaded, elapsed),
+            }),
+        );
+    }
+}
+
+fn throughput(bytes: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 { bytes as f64 / secs } else { 0.0 }
+}
+
+fn message_style() -> ProgressStyle {
     ProgressStyle::default_bar().template("{msg}").unwrap()
 }
 
 fn download_style() -> ProgressStyle {
     ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec})")
+        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}) {msg}")
+        .unwrap()
+        .progress_chars("#>-")
+}
+
+/// The total bar's style. Throughput and ETA count only the bytes
+/// `fetched`, so cached and resumed files don't inflate them.
+fn total_style(fetched: &Arc<Fetched>) -> ProgressStyle {
+    let rate = Arc::clone(fetched);
+    let eta = Arc::clone(fetched);
+    ProgressStyle::default_bar()
+        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.green/blue}] {bytes}/{total_bytes} ({rate}, {remaining}) total")
         .unwrap()
+        .with_key("rate", move |_: &ProgressState, w: &mut dyn std::fmt::Write| {
+            let _ = write!(w, "{}/s", HumanBytes(rate.per_sec() as u64));
+        })
+        .with_key("remaining", move |state: &ProgressState, w: &mut dyn std::fmt::Write| {
+            let left = state.len().unwrap_or(0).saturating_sub(state.pos());
+            let _ = match eta.per_sec() {
+                per_sec if per_sec > 0.0 => write!(
+                    w,
+                    "{} left",
+                    HumanDuration(Duration::from_secs_f64(left as f64 / per_sec))
+                ),
+                _ => write!(w, "waiting"),
+            };
+        })
         .progress_chars("#>-")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collects what a [`JsonLog`] writes.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn events(&self) -> Vec<Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    fn hidden_bars(files: &[(&str, u64)], limit: fn() -> usize) -> Arc<Bars> {
+        let multi = MultiProgress::with_draw_target(ProgressDrawTarget::hidden());
+        Arc::new(Bars::new(multi, files.iter().copied(), limit))
+    }
+
+    #[test]
+    fn bars_show_what_fits_and_summarize_the_rest() {
+        let bars = hidden_bars(&[("a.gguf", 10), ("b.gguf", 20), ("c.gguf", 30)], || 1);
+        let [a, b, c] = [0, 1, 2].map(|idx| IndicatifProgress::new(&bars, idx));
+
+        c.event(&ProgressEvent::Cached);
+        c.start(30);
+        c.finish();
+        a.start(0);
+        b.start(5);
+        a.inc(10);
+
+        assert_eq!(bars.total.length(), Some(60));
+        assert_eq!(bars.total.position(), 45);
+        assert_eq!(bars.fetched.bytes.load(Ordering::Relaxed), 10);
+        assert_eq!(
+            bars.state().shown,
+            [Shown::Visible, Shown::Waiting, Shown::Done]
+        );
+        assert_eq!(
+            bars.summary.message(),
+            "1 of 3 files done, 1 already cached, 1 more downloading"
+        );
+
+        a.finish();
+        assert_eq!(
+            bars.state().shown,
+            [Shown::Done, Shown::Visible, Shown::Done]
+        );
+        assert_eq!(
+            bars.summary.message(),
+            "2 of 3 files done, 1 already cached"
+        );
+    }
+
+    #[test]
+    fn a_retry_from_less_on_disk_takes_it_back_off_the_total() {
+        let bars = hidden_bars(&[("a.gguf", 100)], || 1);
+        let a = IndicatifProgress::new(&bars, 0);
+
+        a.start(40);
+        a.inc(30);
+        a.start(0);
+
+        assert_eq!(bars.total.position(), 0);
+    }
+
+    #[test]
+    fn json_progress_reports_each_file_and_a_summary() {
+        let buffer = Buffer::default();
+        let log = Arc::new(JsonLog::new(Box::new(buffer.clone()), Duration::ZERO));
+        let cached = JsonProgress::new(&log, "cached.gguf", 10);
+        let fetched = JsonProgress::new(&log, "fetched.gguf", 100);
+
+        cached.start(10);
+        cached.finish();
+        fetched.start(40);
+        fetched.inc(30);
+        fetched.event(&ProgressEvent::Retrying {
+            attempt: 1,
+            delay: Duration::from_secs(1),
+            error: String::from("connection reset"),
+        });
+        fetched.start(70);
+        fetched.inc(30);
+        fetched.finish();
+        Progress::Json(Arc::clone(&log)).finish();
+
+        let events = buffer.events();
+        let kinds: Vec<&str> = events
+            .iter()
+            .map(|e| e["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "start", "finish", "start", "progress", "retry", "progress", "finish", "summary"
+            ]
+        );
+        assert_eq!(events[4]["attempt"], 1);
+        assert_eq!(events[4]["error"], "connection reset");
+        assert_eq!(events[1]["reused"], 10);
+        assert_eq!(events[1]["downloaded"], 0);
+        assert_eq!(events[5]["position"], 100);
+        assert_eq!(events[6]["downloaded"], 60);
+
+        let summary = &events[7];
+        assert_eq!(summary["files"], 2);
+        assert_eq!(summary["bytes"], 110);
+        assert_eq!(summary["downloaded"], 60);
+        assert_eq!(summary["reused"], 50);
+        assert_eq!(summary["retries"], 1);
+    }
+
+    #[test]
+    fn json_progress_ticks_at_most_once_per_interval() {
+        let buffer = Buffer::default();
+        let log = Arc::new(JsonLog::new(
+            Box::new(buffer.clone()),
+            Duration::from_secs(3600),
+        ));
+        let progress = JsonProgress::new(&log, "model.gguf", 100);
+
+        progress.start(0);
+        for _ in 0..10 {
+            progress.inc(10);
+        }
+        progress.finish();
+
+        let kinds: Vec<Value> = buffer
+            .events()
+            .into_iter()
+            .map(|e| e["event"].clone())
+            .collect();
+        assert_eq!(kinds, [json!("start"), json!("finish")]);
+    }
+
+    #[test]
+    fn progress_interval_must_be_positive() {
+        assert_eq!(parse_interval("0.5"), Ok(Duration::from_millis(500)));
+        assert!(parse_interval("0").is_err());
+        assert!(parse_interval("-1").is_err());
+        assert!(parse_interval("soon").is_err());
+    }
+}
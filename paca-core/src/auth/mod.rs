@@ -0,0 +1,335 @@
+//! HuggingFace access tokens: where they come from, and where `paca login`
+//! puts them.
+//!
+//! The lookup mirrors `huggingface_hub` so that a machine already logged in
+//! with the Python tooling works with paca unchanged: `HF_TOKEN` first,
+//! then the file named by `HF_TOKEN_PATH`, then `$HF_HOME/token`.
+
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Profile;
+use crate::env;
+use crate::error::PacaError;
+use crate::http::HttpOptions;
+use crate::registry::endpoint::{model_endpoint, model_endpoints_with};
+use crate::registry::token_headers;
+
+/// The identity a token belongs to, as reported by `/api/whoami-v2`.
+/// Never carries the token itself.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct WhoAmI {
+    /// The account name
+    pub name: String,
+    /// The display name given to the token when it was created
+    pub token_name: Option<String>,
+    /// The token's permission level (`read`, `write`, `fineGrained`)
+    pub token_role: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhoAmIResponse {
+    name: String,
+    auth: Option<WhoAmIAuth>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhoAmIAuth {
+    #[serde(rename = "accessToken")]
+    access_token: Option<WhoAmIAccessToken>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhoAmIAccessToken {
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+    role: Option<String>,
+}
+
+impl From<WhoAmIResponse> for WhoAmI {
+    fn from(response: WhoAmIResponse) -> Self {
+        let access_token = response.auth.and_then(|auth| auth.access_token);
+        let (token_name, token_role) =
+            access_token.map_or((None, None), |token| (token.display_name, token.role));
+
+        Self {
+            name: response.name,
+            token_name,
+            token_role,
+        }
+    }
+}
+
+/// Resolves the token to send with registry requests, if any.
+///
+/// An unreadable token file is an error rather than "no token": silently
+/// falling back to anonymous access would surface later as a confusing
+/// 401 on a gated repo.
+pub fn resolve_token() -> Result<Option<String>, PacaError> {
+    resolve_token_for(model_endpoint())
+}
+
+/// Resolves the token for requests to `endpoint`: `HF_TOKEN`, else the
+/// token file.
+pub fn resolve_token_for(endpoint: &str) -> Result<Option<String>, PacaError> {
+    resolve_token_with(endpoint, &Profile::default())
+}
+
+/// [`resolve_token_for`], with `profile`'s token for the endpoint's host
+/// between `HF_TOKEN` and the token file.
+pub fn resolve_token_with(endpoint: &str, profile: &Profile) -> Result<Option<String>, PacaError> {
+    if let Some(token) = std::env::var("HF_TOKEN").ok().and_then(non_blank) {
+        return Ok(Some(token));
+    }
+    if let Some(token) = profile.token_for(endpoint)? {
+        return Ok(Some(token));
+    }
+
+    match fs::read_to_string(token_path()?) {
+        Ok(contents) => Ok(non_blank(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(PacaError::TokenFile(e)),
+    }
+}
+
+fn non_blank(token: String) -> Option<String> {
+    let token = token.trim();
+    (!token.is_empty()).then(|| token.to_string())
+}
+
+/// Where the token file lives. See [`crate::env::token_path`].
+pub fn token_path() -> Result<PathBuf, PacaError> {
+    env::token_path()
+}
+
+/// Stores `token` in the token file, readable by its owner only.
+/// Returns the path written.
+pub fn login(token: &str) -> Result<PathBuf, PacaError> {
+    let token = non_blank(token.to_string()).ok_or(PacaError::NotLoggedIn)?;
+    let path = token_path()?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(PacaError::TokenFile)?;
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .write(true)
+        .mode(0o600)
+        .open(&path)
+        .map_err(PacaError::TokenFile)?;
+    // `mode` only applies on creation; an existing file keeps whatever
+    // permissions it had, so tighten them before the token lands in it.
+    file.set_permissions(fs::Permissions::from_mode(0o600))
+        .map_err(PacaError::TokenFile)?;
+    file.write_all(token.as_bytes())
+        .map_err(PacaError::TokenFile)?;
+
+    Ok(path)
+}
+
+/// Deletes the token file. Returns whether there was one to delete.
+pub fn logout() -> Result<bool, PacaError> {
+    match fs::remove_file(token_path()?) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(PacaError::TokenFile(e)),
+    }
+}
+
+/// Asks the registry who the resolved token belongs to.
+pub async fn whoami() -> Result<WhoAmI, PacaError> {
+    whoami_with(&Profile::default(), &HttpOptions::from_env()?).await
+}
+
+/// [`whoami`] at the hub `profile` names, with the token it resolves,
+/// connecting as `http` says.
+pub async fn whoami_with(profile: &Profile, http: &HttpOptions) -> Result<WhoAmI, PacaError> {
+    env::ensure_online()?;
+    let endpoints = model_endpoints_with(&profile.endpoints);
+    let endpoint = endpoints.last().expect("the hub is always an endpoint");
+    let token = resolve_token_with(endpoint, profile)?.ok_or(PacaError::NotLoggedIn)?;
+
+    let client = http.client(token_headers(Some(&token))?)?;
+    fetch_whoami(&client, endpoint).await
+}
+
+async fn fetch_whoami(client: &Client, endpoint: &str) -> Result<WhoAmI, PacaError> {
+    let response = client
+        .get(format!("{endpoint}/api/whoami-v2"))
+        .send()
+        .await?;
+
+    if response.status() == StatusCode::UNAUTHORIZED {
+        return Err(PacaError::Unauthorized);
+    }
+
+    let parsed: WhoAmIResponse = response.error_for_status()?.json().await?;
+    Ok(parsed.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn resolve_token_prefers_hf_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("token");
+        fs::write(&file, "from-file").unwrap();
+
+        temp_env::with_vars(
+            [
+                ("HF_TOKEN", Some("from-env")),
+                ("HF_TOKEN_PATH", Some(file.to_str().unwrap())),
+            ],
+            || {
+                assert_eq!(resolve_token().unwrap().as_deref(), Some("from-env"));
+            },
+        );
+    }
+
+    #[test]
+    fn resolve_token_reads_hf_token_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("custom-token");
+        fs::write(&file, "from-file\n").unwrap();
+
+        temp_env::with_vars(
+            [
+                ("HF_TOKEN", None),
+                ("HF_TOKEN_PATH", Some(file.to_str().unwrap())),
+            ],
+            || {
+                assert_eq!(resolve_token().unwrap().as_deref(), Some("from-file"));
+            },
+        );
+    }
+
+    #[test]
+    fn resolve_token_reads_token_in_hf_home() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("token"), "from-home").unwrap();
+
+        temp_env::with_vars(
+            [
+                ("HF_TOKEN", None),
+                ("HF_TOKEN_PATH", None),
+                ("HF_HOME", Some(dir.path().to_str().unwrap())),
+            ],
+            || {
+                assert_eq!(resolve_token().unwrap().as_deref(), Some("from-home"));
+            },
+        );
+    }
+
+    #[test]
+    fn resolve_token_returns_none_without_a_token() {
+        let dir = tempfile::tempdir().unwrap();
+
+        temp_env::with_vars(
+            [
+                ("HF_TOKEN", None),
+                ("HF_TOKEN_PATH", None),
+                ("HF_HOME", Some(dir.path().to_str().unwrap())),
+            ],
+            || {
+                assert_eq!(resolve_token().unwrap(), None);
+            },
+        );
+    }
+
+    #[test]
+    fn login_writes_an_owner_only_token_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("nested").join("token");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "old").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+
+        temp_env::with_vars(
+            [
+                ("HF_TOKEN", None),
+                ("HF_TOKEN_PATH", Some(file.to_str().unwrap())),
+            ],
+            || {
+                assert_eq!(login("hf_secret\n").unwrap(), file);
+                assert_eq!(resolve_token().unwrap().as_deref(), Some("hf_secret"));
+            },
+        );
+
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn logout_removes_the_token_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("token");
+        fs::write(&file, "hf_secret").unwrap();
+
+        temp_env::with_vars([("HF_TOKEN_PATH", Some(file.to_str().unwrap()))], || {
+            assert!(logout().unwrap());
+            assert!(!logout().unwrap());
+        });
+        assert!(!file.exists());
+    }
+
+    #[tokio::test]
+    async fn fetch_whoami_parses_the_account_and_token_details() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/whoami-v2"))
+            .and(header("authorization", "Bearer hf_secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"name":"alice","type":"user","auth":{"accessToken":{"displayName":"laptop","role":"read"}}}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let client = Client::builder()
+            .default_headers(
+                [(
+                    reqwest::header::AUTHORIZATION,
+                    "Bearer hf_secret".parse().unwrap(),
+                )]
+                .into_iter()
+                .collect(),
+            )
+            .build()
+            .unwrap();
+        let who = fetch_whoami(&client, &server.uri()).await.unwrap();
+
+        assert_eq!(
+            who,
+            WhoAmI {
+                name: "alice".to_string(),
+                token_name: Some("laptop".to_string()),
+                token_role: Some("read".to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_whoami_reports_a_rejected_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let result = fetch_whoami(&Client::new(), &server.uri()).await;
+        assert!(
+            matches!(result, Err(PacaError::Unauthorized)),
+            "got {result:?}"
+        );
+    }
+}
@@ -2,10 +2,14 @@ use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use serde::Serialize;
+
 use crate::cache::HubLayout;
+use crate::download::is_chunk_suffix;
 use crate::error::PacaError;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CleanReason {
     BrokenSymlink,
     OrphanedBlob,
@@ -13,19 +17,22 @@ pub enum CleanReason {
     PartialBlob,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct RemovedFile {
     pub path: PathBuf,
     pub reason: CleanReason,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct CleanResult {
     pub removed_files: Vec<RemovedFile>,
 }
 
 pub fn clean_cache(hub_dir: Option<PathBuf>) -> Result<CleanResult, PacaError> {
-    let hub = HubLayout::open(hub_dir)?;
+    clean_hub(&HubLayout::open(hub_dir)?)
+}
+
+pub(crate) fn clean_hub(hub: &HubLayout) -> Result<CleanResult, PacaError> {
     let mut removed_files = Vec::new();
 
     for entry in fs::read_dir(hub.root()).map_err(PacaError::CacheDir)? {
@@ -105,16 +112,14 @@ fn clean_model_dir(
 }
 
 /// Recognizes both sequential (`<hash>.partial`) and parallel
-/// (`<hash>.partial.<idx>`) download artifacts. The numeric suffix check
-/// avoids false positives like `<hash>.partialfoo`.
+/// (`<hash>.partial.<idx>-of-<count>`) download artifacts. The suffix
+/// check avoids false positives like `<hash>.partialfoo`.
 fn is_partial_blob_filename(name: &str) -> bool {
     if name.ends_with(".partial") {
         return true;
     }
-    if let Some((_, suffix)) = name.rsplit_once(".partial.") {
-        return !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit());
-    }
-    false
+    name.rsplit_once(".partial.")
+        .is_some_and(|(_, suffix)| is_chunk_suffix(suffix))
 }
 
 /// Records every blob hash reachable from a snapshot tree, deleting any
@@ -210,6 +215,23 @@ mod tests {
     use super::*;
     use crate::test_support::{setup_model_dir, write_blob, write_ref, write_snapshot_symlink};
 
+    #[test]
+    fn clean_result_serializes_reasons_in_snake_case() {
+        let result = CleanResult {
+            removed_files: vec![RemovedFile {
+                path: PathBuf::from("/hub/blobs/abc.partial"),
+                reason: CleanReason::PartialBlob,
+            }],
+        };
+
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            serde_json::json!({
+                "removed_files": [{ "path": "/hub/blobs/abc.partial", "reason": "partial_blob" }]
+            })
+        );
+    }
+
     #[test]
     fn clean_cache_empty_dir_removes_nothing() {
         let dir = tempfile::tempdir().unwrap();
@@ -390,6 +412,11 @@ mod tests {
             )
             .unwrap();
         }
+        fs::write(
+            model_dir.join("blobs/aborted_hash.partial.0-of-2"),
+            vec![0u8; 16],
+        )
+        .unwrap();
 
         let result = clean_cache(Some(dir.path().to_path_buf())).unwrap();
 
@@ -398,7 +425,7 @@ mod tests {
             .iter()
             .filter(|r| r.reason == CleanReason::PartialBlob)
             .collect();
-        assert_eq!(partial_reasons.len(), 4);
+        assert_eq!(partial_reasons.len(), 5);
 
         for idx in 0..4 {
             assert!(
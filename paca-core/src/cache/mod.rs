@@ -4,18 +4,20 @@ pub mod remove;
 use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use reqwest::Client;
+use serde::Serialize;
 
-use crate::error::PacaError;
-use crate::model::ModelRef;
-use crate::path::join_child;
-use crate::registry::endpoint::model_endpoint;
-use crate::registry::manifest::{GgufFile, fetch_manifest};
-use crate::registry::{build_resolve_client, default_headers, fetch_resolve_info};
+use crate::download::is_commit_hash;
+use crate::env;
+use crate::error::{ExitStatus, PacaError};
+use crate::model::{DEFAULT_REVISION, ModelRef, tag_for_path};
+use crate::path::{join_child, join_within};
+use crate::registry::manifest::GgufFile;
+use crate::registry::{Origin, Registry};
 
 /// Information about a model with an outdated commit
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct OutdatedModelInfo {
     pub model_ref: ModelRef,
     pub filename: String,
@@ -25,20 +27,49 @@ pub struct OutdatedModelInfo {
 /// A repo whose freshness could not be determined. Reported rather than
 /// swallowed: answering "up to date" for a repo we failed to reach would
 /// present a stale model as current.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct UnreachableRepo {
     pub reason: String,
     pub repo: String,
 }
 
+/// A repo imported from a store that records no registry, so there is
+/// nothing to check it against.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct UncheckedRepo {
+    /// The store it was imported from.
+    pub origin: String,
+    pub repo: String,
+}
+
 /// The outcome of an outdated check: what is stale, and what could not be
 /// checked at all.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize)]
 pub struct OutdatedReport {
     pub outdated: Vec<OutdatedModelInfo>,
+    /// Repos left unchecked because `HF_HUB_OFFLINE` is set.
+    pub skipped_offline: Vec<String>,
+    /// Repos left unchecked because no registry is known to hold them.
+    pub unchecked: Vec<UncheckedRepo>,
     pub unreachable: Vec<UnreachableRepo>,
 }
 
+impl OutdatedReport {
+    /// Stale models come first, since they are what a caller acts on;
+    /// repos left unchecked mean "up to date" cannot be claimed.
+    pub fn exit_status(&self) -> ExitStatus {
+        if !self.outdated.is_empty() {
+            ExitStatus::Outdated
+        } else if !self.unreachable.is_empty() {
+            ExitStatus::Unreachable
+        } else if !self.skipped_offline.is_empty() {
+            ExitStatus::Offline
+        } else {
+            ExitStatus::Success
+        }
+    }
+}
+
 /// A repo's manifest plus whether the local commit has fallen behind.
 #[derive(Clone, Debug)]
 struct RepoStatus {
@@ -47,12 +78,23 @@ struct RepoStatus {
 }
 
 /// A downloaded model tag with the on-disk size of all its files combined.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct ModelEntry {
     pub model_ref: ModelRef,
     pub size: u64,
 }
 
+/// The files llama.cpp loads for an installed tag.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct LocalModel {
+    /// The file to pass as `-m`: the first shard when the tag is sharded
+    pub model: PathBuf,
+    /// The projector to pass as `--mmproj`, if the repo has one
+    pub mmproj: Option<PathBuf>,
+    /// Every shard, in order; just [`LocalModel::model`] when unsharded
+    pub shards: Vec<PathBuf>,
+}
+
 /// The HuggingFace Hub cache root, ensured to exist on disk.
 ///
 /// Constructed from an optional user override via [`HubLayout::open`]; all
@@ -66,12 +108,12 @@ pub(crate) struct HubLayout {
 impl HubLayout {
     /// Opens the hub directory, creating it if missing.
     ///
-    /// `override_path` replaces the default `~/.cache/huggingface/hub`
-    /// location when set.
+    /// `override_path` replaces the hub cache resolved from the
+    /// HuggingFace environment variables when set.
     pub(crate) fn open(override_path: Option<PathBuf>) -> Result<Self, PacaError> {
         let root = match override_path {
             Some(root) => root,
-            None => default_hub_dir()?,
+            None => env::hub_cache()?,
         };
         fs::create_dir_all(&root).map_err(PacaError::CacheDir)?;
         Ok(Self { root })
@@ -128,9 +170,22 @@ impl ModelPaths {
     }
 
     pub(crate) fn save_ref(&self, commit_hash: &str) -> Result<(), PacaError> {
-        let dir = self.refs();
-        fs::create_dir_all(&dir).map_err(PacaError::CacheDir)?;
-        fs::write(dir.join("main"), commit_hash).map_err(PacaError::FileWrite)?;
+        self.save_named_ref(DEFAULT_REVISION, commit_hash)
+    }
+
+    /// Records `commit_hash` under `refs/<revision>`, as the hub cache does
+    /// for branches and tags. Revisions like `refs/pr/1` nest, so this
+    /// takes the containment rule for nested paths.
+    pub(crate) fn save_named_ref(
+        &self,
+        revision: &str,
+        commit_hash: &str,
+    ) -> Result<(), PacaError> {
+        let path = join_within(&self.refs(), revision)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(PacaError::CacheDir)?;
+        }
+        fs::write(path, commit_hash).map_err(PacaError::FileWrite)?;
         Ok(())
     }
 
@@ -138,19 +193,72 @@ impl ModelPaths {
         fs::read_to_string(self.ref_main()).ok()
     }
 
+    /// The commit `refs/<revision>` names, if the revision was downloaded.
+    pub(crate) fn read_named_ref(&self, revision: &str) -> Option<String> {
+        let path = join_within(&self.refs(), revision).ok()?;
+        fs::read_to_string(path).ok()
+    }
+
     pub(crate) fn blob_exists(&self, blob_hash: &str) -> bool {
         self.blob(blob_hash).is_ok_and(|path| path.exists())
     }
-}
 
-fn default_hub_dir() -> Result<PathBuf, PacaError> {
-    let home = dirs::home_dir().ok_or_else(|| {
-        PacaError::CacheDir(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "Could not determine home directory",
-        ))
-    })?;
-    Ok(home.join(".cache").join("huggingface").join("hub"))
+    pub(crate) fn origin(&self) -> PathBuf {
+        self.dir().join("origin")
+    }
+
+    /// The registry the repo came from. Repos cached before origins were
+    /// recorded came from HuggingFace; an empty one has none yet.
+    pub(crate) fn read_origin(&self) -> Result<Option<Origin>, PacaError> {
+        match fs::read_to_string(self.origin()) {
+            Ok(line) => line
+                .parse()
+                .map(Some)
+                .map_err(|reason| PacaError::UnknownOrigin {
+                    repo: self.repo_name(),
+                    reason,
+                }),
+            Err(_) if self.read_ref().is_some() => Ok(Some(Origin::HuggingFace)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Fails unless the repo may take files from `origin`, so that repos
+    /// sharing a name across registries do not share a snapshot.
+    pub(crate) fn check_origin(&self, origin: &Origin) -> Result<(), PacaError> {
+        match self.read_origin()? {
+            Some(recorded) if !recorded.admits(origin) => Err(PacaError::OriginConflict {
+                repo: self.repo_name(),
+                recorded: recorded.to_string(),
+                origin: origin.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records `origin` for the repo, before its ref so a new repo is not
+    /// taken for one cached before origins were. An import joining a repo
+    /// from a registry leaves that registry recorded.
+    pub(crate) fn save_origin(&self, origin: &Origin) -> Result<(), PacaError> {
+        if matches!(origin, Origin::Import(_))
+            && self
+                .read_origin()?
+                .is_some_and(|recorded| !matches!(recorded, Origin::Import(_)))
+        {
+            return Ok(());
+        }
+        fs::create_dir_all(self.dir()).map_err(PacaError::CacheDir)?;
+        fs::write(self.origin(), format!("{origin}\n")).map_err(PacaError::FileWrite)
+    }
+
+    /// `owner/model`, as the directory name spells it.
+    fn repo_name(&self) -> String {
+        self.dir
+            .file_name()
+            .and_then(|name| parse_model_dir_name(&name.to_string_lossy()))
+            .map(|(owner, model)| format!("{owner}/{model}"))
+            .unwrap_or_else(|| self.dir.display().to_string())
+    }
 }
 
 pub(crate) fn model_dir_name(model_ref: &ModelRef) -> String {
@@ -159,7 +267,10 @@ pub(crate) fn model_dir_name(model_ref: &ModelRef) -> String {
 
 /// Lists all downloaded models from the hub directory.
 pub fn list_models(hub_dir: Option<PathBuf>) -> Result<Vec<ModelEntry>, PacaError> {
-    let hub = HubLayout::open(hub_dir)?;
+    list_installed(&HubLayout::open(hub_dir)?)
+}
+
+pub(crate) fn list_installed(hub: &HubLayout) -> Result<Vec<ModelEntry>, PacaError> {
     let mut entries = Vec::new();
 
     for entry in fs::read_dir(hub.root()).map_err(PacaError::CacheDir)? {
@@ -203,8 +314,10 @@ fn collect_model_tags(
     for (tag, size) in sizes_by_tag {
         entries.push(ModelEntry {
             model_ref: ModelRef {
+                file: None,
                 model: model.clone(),
                 owner: owner.clone(),
+                revision: None,
                 tag,
             },
             size,
@@ -239,18 +352,13 @@ fn sum_gguf_sizes_by_tag(
             continue;
         };
 
-        let name = entry.file_name().to_string_lossy().into_owned();
         let relative = path
             .strip_prefix(base)
             .unwrap_or(&path)
             .to_string_lossy()
             .into_owned();
 
-        let tag = if let Some((subdir, _)) = relative.split_once('/') {
-            subdir.to_string()
-        } else if let Some(derived) = derive_tag(&name, model) {
-            derived
-        } else {
+        let Some(tag) = tag_for_path(&relative, model) else {
             continue;
         };
 
@@ -259,32 +367,209 @@ fn sum_gguf_sizes_by_tag(
     Ok(())
 }
 
+/// The repo is installed but the tag is not: name the installed tags
+/// nearest to what was asked for.
+pub(crate) fn tag_not_found(hub: &HubLayout, model_ref: &ModelRef) -> Result<PacaError, PacaError> {
+    let installed: Vec<String> = list_installed(hub)?
+        .into_iter()
+        .filter(|entry| {
+            entry.model_ref.owner == model_ref.owner && entry.model_ref.model == model_ref.model
+        })
+        .map(|entry| entry.model_ref.tag)
+        .collect();
+
+    Ok(PacaError::TagNotFound {
+        model_ref: model_ref.to_string(),
+        suggestions: closest_tags(&model_ref.tag, &installed),
+    })
+}
+
+/// Finds the files of an installed tag in the snapshot its revision
+/// names, `refs/main` by default, so scripts need not know the cache
+/// layout. A reference naming a single file resolves to just that file.
+///
+/// Fails with [`PacaError::ModelNotInstalled`] when the tag is not in the
+/// cache, or any of its files is missing or a dangling symlink.
+pub fn resolve_local_model(
+    model_ref: &ModelRef,
+    hub_dir: Option<PathBuf>,
+) -> Result<LocalModel, PacaError> {
+    let hub = HubLayout::open(hub_dir)?;
+    let not_installed = || PacaError::ModelNotInstalled(model_ref.to_string());
+    let files = match installed_tag_files(&hub, model_ref) {
+        Err(PacaError::TagNotFound { .. }) => return Err(not_installed()),
+        result => result?,
+    };
+
+    let shards = match &model_ref.file {
+        Some(file) => vec![file.clone()],
+        None => files.model,
+    };
+    let model = shards
+        .iter()
+        .find(|file| file.contains("-00001-of-"))
+        .unwrap_or(&shards[0])
+        .clone();
+
+    // `exists` follows the snapshot symlink, so a deleted blob counts too.
+    let resolve = |relative: &str| {
+        let path = join_within(&files.snapshot, relative)?;
+        if path.exists() {
+            Ok(path)
+        } else {
+            Err(not_installed())
+        }
+    };
+
+    Ok(LocalModel {
+        model: resolve(&model)?,
+        mmproj: files.mmproj.as_deref().map(resolve).transpose()?,
+        shards: shards
+            .iter()
+            .map(|file| resolve(file))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// The GGUF files of an installed tag, relative to its snapshot.
+pub(crate) struct TagFiles {
+    pub(crate) snapshot: PathBuf,
+    /// Every model file of the tag, shards in order.
+    pub(crate) model: Vec<String>,
+    pub(crate) mmproj: Option<String>,
+}
+
+/// Finds the files of `model_ref`'s tag in the snapshot `refs/main` names.
+/// The projector is the tag's own, else an untagged one at the snapshot
+/// root, where HuggingFace repos usually keep it.
+pub(crate) fn installed_tag_files(
+    hub: &HubLayout,
+    model_ref: &ModelRef,
+) -> Result<TagFiles, PacaError> {
+    let paths = hub.model(model_ref)?;
+    let not_installed = || PacaError::ModelNotInstalled(model_ref.to_string());
+    // A commit hash names its snapshot directly; branches and tags go
+    // through their ref, as downloads record them.
+    let revision = model_ref.revision();
+    let commit = if is_commit_hash(revision) {
+        revision.to_string()
+    } else {
+        paths
+            .read_named_ref(revision)
+            .map(|commit| commit.trim().to_string())
+            .ok_or_else(not_installed)?
+    };
+    let snapshot = paths.snapshot(&commit)?;
+    if !snapshot.is_dir() {
+        return Err(not_installed());
+    }
+
+    let mut relatives = Vec::new();
+    collect_gguf_paths(&snapshot, &snapshot, &mut relatives)?;
+    relatives.sort();
+
+    let (mut model, mut tag_mmproj, mut root_mmproj) = (Vec::new(), None, None);
+    for relative in relatives {
+        let projector = is_mmproj(&relative);
+        match tag_for_path(&relative, &model_ref.model) {
+            Some(tag) if tag.eq_ignore_ascii_case(&model_ref.tag) => {
+                if projector {
+                    tag_mmproj.get_or_insert(relative);
+                } else {
+                    model.push(relative);
+                }
+            }
+            None if projector && !relative.contains('/') => {
+                root_mmproj.get_or_insert(relative);
+            }
+            _ => {}
+        }
+    }
+
+    if model.is_empty() {
+        return Err(tag_not_found(hub, model_ref)?);
+    }
+    Ok(TagFiles {
+        snapshot,
+        model,
+        mmproj: tag_mmproj.or(root_mmproj),
+    })
+}
+
+pub(crate) fn is_mmproj(relative: &str) -> bool {
+    relative
+        .rsplit('/')
+        .next()
+        .is_some_and(|name| name.to_ascii_lowercase().starts_with("mmproj"))
+}
+
+pub(crate) fn collect_gguf_paths(
+    base: &Path,
+    dir: &Path,
+    paths: &mut Vec<String>,
+) -> Result<(), PacaError> {
+    for entry in fs::read_dir(dir).map_err(PacaError::CacheDir)? {
+        let path = entry.map_err(PacaError::CacheDir)?.path();
+        if path.is_dir() {
+            collect_gguf_paths(base, &path, paths)?;
+        } else if is_gguf(&path) {
+            let relative = path.strip_prefix(base).unwrap_or(&path);
+            paths.push(relative.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn is_gguf(path: impl AsRef<Path>) -> bool {
     path.as_ref()
         .extension()
         .is_some_and(|ext| ext.eq_ignore_ascii_case("gguf"))
 }
 
-/// Derives a quantization tag from a GGUF filename using the model name.
-///
-/// Strips the model base name prefix (model name without `-GGUF`), the
-/// `.gguf` suffix, and any shard suffix (e.g. `-00001-of-00002`).
-pub(crate) fn derive_tag(filename: &str, model: &str) -> Option<String> {
-    let stem = filename.strip_suffix(".gguf")?;
-    let model_base = model.strip_suffix("-GGUF")?;
-    let remainder = stem.strip_prefix(model_base)?.strip_prefix('-')?;
+/// The number of tags offered when a requested one does not exist.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The available tags closest to `tag`, nearest first, for "did you mean"
+/// errors. Case is ignored, so a mis-cased tag ranks as an exact match.
+pub(crate) fn closest_tags<'a, I>(tag: &str, available: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let wanted = tag.to_ascii_lowercase();
+    let mut scored: Vec<(usize, &String)> = available
+        .into_iter()
+        .map(|candidate| {
+            (
+                edit_distance(&wanted, &candidate.to_ascii_lowercase()),
+                candidate,
+            )
+        })
+        .collect();
+
+    scored.sort();
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
 
-    if remainder.is_empty() {
-        return None;
-    }
+/// Levenshtein distance over chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
 
-    let tag = if let Some((before, _)) = remainder.rsplit_once("-of-") {
-        before.rsplit_once('-').map_or(before, |(t, _)| t)
-    } else {
-        remainder
-    };
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
 
-    (!tag.is_empty()).then(|| tag.to_string())
+    previous[b.len()]
 }
 
 fn parse_model_dir_name(dir_name: &str) -> Option<(String, String)> {
@@ -296,44 +581,69 @@ fn parse_model_dir_name(dir_name: &str) -> Option<(String, String)> {
 /// Checks which downloaded models have outdated files by comparing commit
 /// hashes.
 ///
+/// Each repo is checked against the registry it was downloaded from:
+/// `registry` for its own, one built from the environment for the rest.
+/// Imported repos with no registry to ask are reported as unchecked.
+///
 /// Groups models by repo so that only one resolve-info HEAD request is
 /// made per repo, regardless of how many tags are installed.
-pub async fn check_outdated_models(hub_dir: Option<PathBuf>) -> Result<OutdatedReport, PacaError> {
-    let hub = HubLayout::open(hub_dir)?;
+pub async fn check_outdated_models(
+    registry: &dyn Registry,
+    hub_dir: Option<PathBuf>,
+) -> Result<OutdatedReport, PacaError> {
+    check_outdated(registry, &HubLayout::open(hub_dir)?).await
+}
+
+pub(crate) async fn check_outdated(
+    registry: &dyn Registry,
+    hub: &HubLayout,
+) -> Result<OutdatedReport, PacaError> {
+    if env::is_offline() {
+        return Ok(offline_report(&list_installed(hub)?));
+    }
 
-    let client = Client::builder()
-        .default_headers(default_headers()?)
-        .build()?;
-    let head_client = build_resolve_client()?;
-    let endpoint = model_endpoint();
     let mut report = OutdatedReport::default();
 
-    let models = list_models(Some(hub.root().to_path_buf()))?;
+    let models = list_installed(hub)?;
 
     // Cached per repo so that N installed tags cost one round trip, and so
     // that an unreachable repo is reported once rather than N times.
-    let mut checked: HashMap<String, Result<RepoStatus, String>> = HashMap::new();
+    let mut checked: HashMap<String, Option<RepoStatus>> = HashMap::new();
+    // Registries other than `registry`, built once per origin.
+    let mut others: HashMap<Origin, Arc<dyn Registry>> = HashMap::new();
 
     for entry in &models {
         let model_ref = &entry.model_ref;
         let repo = model_ref.repo();
 
         if !checked.contains_key(&repo) {
-            let status = fetch_repo_status(&client, &head_client, endpoint, &hub, model_ref)
-                .await
-                .map_err(|error| error.to_string());
-
-            if let Err(reason) = &status {
+            let result = match hub.model(model_ref)?.read_origin() {
+                Ok(Some(Origin::Import(source))) => {
+                    report.unchecked.push(UncheckedRepo {
+                        origin: source,
+                        repo: repo.clone(),
+                    });
+                    checked.insert(repo.clone(), None);
+                    continue;
+                }
+                Ok(origin) => {
+                    let origin = origin.unwrap_or(Origin::HuggingFace);
+                    check_repo(registry, &mut others, &origin, hub, model_ref).await
+                }
+                Err(error) => Err(error),
+            };
+
+            if let Err(error) = &result {
                 report.unreachable.push(UnreachableRepo {
-                    reason: reason.clone(),
+                    reason: error.to_string(),
                     repo: repo.clone(),
                 });
             }
 
-            checked.insert(repo.clone(), status);
+            checked.insert(repo.clone(), result.ok());
         }
 
-        let Some(Ok(status)) = checked.get(&repo) else {
+        let Some(Some(status)) = checked.get(&repo) else {
             continue;
         };
         if !status.is_outdated {
@@ -358,23 +668,56 @@ pub async fn check_outdated_models(hub_dir: Option<PathBuf>) -> Result<OutdatedR
     }
 
     report.outdated.sort_by_key(|a| a.model_ref.to_string());
+    report.unchecked.sort_by(|a, b| a.repo.cmp(&b.repo));
     report.unreachable.sort_by(|a, b| a.repo.cmp(&b.repo));
 
     Ok(report)
 }
 
+/// Fetches a repo's status from the registry it came from: `registry`
+/// when that is the one, else one built for `origin` and kept in `others`.
+async fn check_repo(
+    registry: &dyn Registry,
+    others: &mut HashMap<Origin, Arc<dyn Registry>>,
+    origin: &Origin,
+    hub: &HubLayout,
+    model_ref: &ModelRef,
+) -> Result<RepoStatus, PacaError> {
+    if *origin == registry.origin() {
+        return fetch_repo_status(registry, hub, model_ref).await;
+    }
+    if !others.contains_key(origin) {
+        let Some(other) = origin.registry()? else {
+            unreachable!("imports are reported before a registry is needed");
+        };
+        others.insert(origin.clone(), other);
+    }
+    fetch_repo_status(others[origin].as_ref(), hub, model_ref).await
+}
+
+/// Lists every installed repo as skipped, once each, rather than failing:
+/// offline mode is a deliberate choice, not an error.
+fn offline_report(models: &[ModelEntry]) -> OutdatedReport {
+    let mut skipped_offline: Vec<String> = models.iter().map(|e| e.model_ref.repo()).collect();
+    skipped_offline.sort();
+    skipped_offline.dedup();
+
+    OutdatedReport {
+        skipped_offline,
+        ..OutdatedReport::default()
+    }
+}
+
 /// Fetches a repo's manifest and compares its head commit against the
 /// local ref. Returns the manifest alongside the verdict so the caller
 /// need not refetch it, and propagates every failure so an unreachable
 /// repo is never mistaken for a current one.
 async fn fetch_repo_status(
-    client: &Client,
-    head_client: &Client,
-    endpoint: &str,
+    registry: &dyn Registry,
     hub: &HubLayout,
     model_ref: &ModelRef,
 ) -> Result<RepoStatus, PacaError> {
-    let manifest = fetch_manifest(client, endpoint, model_ref).await?;
+    let manifest = registry.manifest(model_ref).await?;
     let Some(first_file) = manifest.gguf_files.first() else {
         return Ok(RepoStatus {
             gguf_files: manifest.gguf_files,
@@ -382,18 +725,15 @@ async fn fetch_repo_status(
         });
     };
 
-    let url = format!(
-        "{}/{}/resolve/main/{}",
-        endpoint,
-        model_ref.repo(),
-        first_file.filename
-    );
-
-    let info = fetch_resolve_info(head_client, &url).await?;
-    let local_commit = hub.model(model_ref)?.read_ref();
+    let info = registry.resolve(model_ref, &first_file.filename).await?;
+    let paths = hub.model(model_ref)?;
+    let local_commit = paths.read_ref();
 
+    // Registries without commits (OCI) keep one snapshot per repo, so a
+    // retagged file only shows as a blob the cache does not hold.
     Ok(RepoStatus {
-        is_outdated: local_commit.as_deref().map(str::trim) != Some(&info.commit_hash),
+        is_outdated: local_commit.as_deref().map(str::trim) != Some(&info.commit_hash)
+            || !paths.blob_exists(&info.blob_hash),
         gguf_files: manifest.gguf_files,
     })
 }
@@ -401,8 +741,9 @@ async fn fetch_repo_status(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::registry::{HuggingFace, Oci};
     use crate::test_support::{setup_model_dir, write_blob, write_ref, write_snapshot_symlink};
-    use wiremock::matchers::method;
+    use wiremock::matchers::{method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     /// A repo whose manifest loads but whose resolve HEAD fails. Returning
@@ -430,8 +771,8 @@ mod tests {
             .mount(&server)
             .await;
 
-        let client = Client::new();
-        let result = fetch_repo_status(&client, &client, &server.uri(), &hub, &mr).await;
+        let registry = HuggingFace::new(server.uri()).unwrap();
+        let result = fetch_repo_status(&registry, &hub, &mr).await;
 
         assert!(result.is_err(), "expected an error, got {result:?}");
     }
@@ -463,10 +804,8 @@ mod tests {
             .mount(&server)
             .await;
 
-        let client = Client::new();
-        let status = fetch_repo_status(&client, &client, &server.uri(), &hub, &mr)
-            .await
-            .unwrap();
+        let registry = HuggingFace::new(server.uri()).unwrap();
+        let status = fetch_repo_status(&registry, &hub, &mr).await.unwrap();
 
         assert!(status.is_outdated);
         assert_eq!(status.gguf_files.len(), 1);
@@ -478,10 +817,9 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let hub = HubLayout::open(Some(dir.path().to_path_buf())).unwrap();
         let mr = model_ref("owner/model-GGUF:Q4");
-        write_ref(
-            &setup_model_dir(dir.path(), "owner", "model-GGUF"),
-            "commit1",
-        );
+        let model_dir = setup_model_dir(dir.path(), "owner", "model-GGUF");
+        write_ref(&model_dir, "commit1");
+        write_blob(&model_dir, "blobhash");
 
         let server = MockServer::start().await;
         Mock::given(method("GET"))
@@ -500,27 +838,190 @@ mod tests {
             .mount(&server)
             .await;
 
-        let client = Client::new();
-        let status = fetch_repo_status(&client, &client, &server.uri(), &hub, &mr)
-            .await
-            .unwrap();
+        let registry = HuggingFace::new(server.uri()).unwrap();
+        let status = fetch_repo_status(&registry, &hub, &mr).await.unwrap();
 
         assert!(!status.is_outdated);
     }
 
+    #[tokio::test]
+    async fn fetch_repo_status_reports_a_missing_blob_as_outdated() {
+        let dir = tempfile::tempdir().unwrap();
+        let hub = HubLayout::open(Some(dir.path().to_path_buf())).unwrap();
+        let mr = model_ref("owner/model-GGUF:Q4");
+        let model_dir = setup_model_dir(dir.path(), "owner", "model-GGUF");
+        write_ref(&model_dir, "commit1");
+        write_blob(&model_dir, "oldblob");
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"ggufFile":{"rfilename":"model-Q4.gguf","size":100}}"#),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-repo-commit", "commit1")
+                    .insert_header("etag", "\"newblob\""),
+            )
+            .mount(&server)
+            .await;
+
+        let registry = HuggingFace::new(server.uri()).unwrap();
+        let status = fetch_repo_status(&registry, &hub, &mr).await.unwrap();
+
+        assert!(status.is_outdated);
+    }
+
     fn model_ref(s: &str) -> ModelRef {
         s.parse().unwrap()
     }
 
-    #[test]
-    fn default_hub_dir_ends_in_huggingface_hub() {
-        assert!(
-            default_hub_dir()
-                .unwrap()
-                .ends_with(".cache/huggingface/hub")
+    #[tokio::test]
+    async fn check_outdated_models_skips_every_repo_when_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        for tag in ["Q4", "Q8"] {
+            let model_dir = setup_model_dir(dir.path(), "owner", "model-GGUF");
+            write_blob(&model_dir, &format!("hash_{tag}"));
+            write_ref(&model_dir, "commit1");
+            write_snapshot_symlink(
+                &model_dir,
+                "commit1",
+                &format!("model-{tag}.gguf"),
+                &format!("hash_{tag}"),
+            );
+        }
+
+        let report = temp_env::async_with_vars(
+            [("HF_HUB_OFFLINE", Some("1"))],
+            check_outdated_models(
+                &HuggingFace::new("http://unused.invalid").unwrap(),
+                Some(dir.path().to_path_buf()),
+            ),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.skipped_offline, vec!["owner/model-GGUF".to_string()]);
+        assert!(report.outdated.is_empty());
+        assert!(report.unreachable.is_empty());
+        assert_eq!(report.exit_status(), ExitStatus::Offline);
+    }
+
+    #[tokio::test]
+    async fn check_outdated_models_asks_the_registry_each_repo_came_from() {
+        let dir = tempfile::tempdir().unwrap();
+        let oci = MockServer::start().await;
+        let blob = "a".repeat(64);
+        Mock::given(method("GET"))
+            .and(path("/v2/library/gemma3/manifests/4b"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "layers": [{
+                    "mediaType": "application/vnd.ollama.image.model",
+                    "digest": format!("sha256:{}", "b".repeat(64)),
+                    "size": 4,
+                }]
+            })))
+            .mount(&oci)
+            .await;
+        let commit = Oci::new(oci.uri())
+            .unwrap()
+            .commit_hash(&model_ref("library/gemma3:4b"));
+        let pulled = setup_model_dir(dir.path(), "library", "gemma3");
+        write_blob(&pulled, &blob);
+        write_ref(&pulled, &commit);
+        write_snapshot_symlink(&pulled, &commit, "4b/gemma3-4b.gguf", &blob);
+        fs::write(pulled.join("origin"), format!("oci {}\n", oci.uri())).unwrap();
+        let imported = setup_model_dir(dir.path(), "owner", "model-GGUF");
+        write_blob(&imported, "hash_q4");
+        write_ref(&imported, "commit1");
+        write_snapshot_symlink(&imported, "commit1", "model-Q4.gguf", "hash_q4");
+        fs::write(imported.join("origin"), "import lmstudio\n").unwrap();
+        // Answers nothing: neither repo came from it.
+        let hub = MockServer::start().await;
+
+        let report = temp_env::async_with_vars(
+            [("HF_HUB_OFFLINE", None::<&str>)],
+            check_outdated_models(
+                &HuggingFace::new(hub.uri()).unwrap(),
+                Some(dir.path().to_path_buf()),
+            ),
+        )
+        .await
+        .unwrap();
+
+        assert!(report.unreachable.is_empty(), "{:?}", report.unreachable);
+        let outdated: Vec<String> = report
+            .outdated
+            .iter()
+            .map(|info| info.model_ref.to_string())
+            .collect();
+        assert_eq!(outdated, ["library/gemma3:4b"]);
+        assert_eq!(
+            report.unchecked,
+            [UncheckedRepo {
+                origin: "lmstudio".to_string(),
+                repo: "owner/model-GGUF".to_string(),
+            }]
         );
     }
 
+    #[test]
+    fn check_origin_keeps_registries_with_the_same_repo_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let hub = HubLayout::open(Some(dir.path().to_path_buf())).unwrap();
+        let paths = hub.model(&model_ref("library/gemma3:4b")).unwrap();
+        let oci = Origin::Oci("https://registry.ollama.ai".to_string());
+        assert_eq!(paths.read_origin().unwrap(), None);
+
+        // Cached before origins were recorded, so from HuggingFace.
+        write_ref(&setup_model_dir(dir.path(), "library", "gemma3"), "commit1");
+        assert_eq!(paths.read_origin().unwrap(), Some(Origin::HuggingFace));
+        assert!(matches!(
+            paths.check_origin(&oci),
+            Err(PacaError::OriginConflict { .. })
+        ));
+
+        // An import joining the repo leaves its registry recorded.
+        let lmstudio = Origin::Import("lmstudio".to_string());
+        paths.check_origin(&lmstudio).unwrap();
+        paths.save_origin(&lmstudio).unwrap();
+        assert_eq!(paths.read_origin().unwrap(), Some(Origin::HuggingFace));
+    }
+
+    #[test]
+    fn outdated_report_exit_status_puts_stale_models_first() {
+        let unreachable = UnreachableRepo {
+            reason: "timed out".to_string(),
+            repo: "owner/gone".to_string(),
+        };
+        let mut report = OutdatedReport::default();
+        assert_eq!(report.exit_status(), ExitStatus::Success);
+
+        report.unreachable.push(unreachable);
+        assert_eq!(report.exit_status(), ExitStatus::Unreachable);
+
+        report.outdated.push(OutdatedModelInfo {
+            model_ref: "owner/model:Q4_K_M".parse().unwrap(),
+            filename: "model-Q4_K_M.gguf".to_string(),
+            file_path: PathBuf::from("/hub/model-Q4_K_M.gguf"),
+        });
+        assert_eq!(report.exit_status(), ExitStatus::Outdated);
+    }
+
+    #[test]
+    fn hub_layout_defaults_to_hf_hub_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("from-env");
+        temp_env::with_var("HF_HUB_CACHE", Some(root.to_str().unwrap()), || {
+            let hub = HubLayout::open(None).unwrap();
+            assert_eq!(hub.root(), root);
+        });
+    }
+
     #[test]
     fn hub_layout_creates_override_root() {
         let dir = tempfile::tempdir().unwrap();
@@ -565,6 +1066,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn save_named_ref_nests_pull_request_refs() {
+        let dir = tempfile::tempdir().unwrap();
+        let hub = HubLayout::open(Some(dir.path().to_path_buf())).unwrap();
+        let paths = hub.model(&model_ref("owner/model-GGUF:Q4")).unwrap();
+
+        paths.save_named_ref("refs/pr/1", "abc123commit").unwrap();
+        assert_eq!(
+            fs::read_to_string(paths.refs().join("refs/pr/1")).unwrap(),
+            "abc123commit"
+        );
+        assert!(matches!(
+            paths.save_named_ref("../../evil", "abc"),
+            Err(PacaError::UnsafePath(_))
+        ));
+    }
+
     #[test]
     fn read_ref_returns_none_when_no_ref_file() {
         let dir = tempfile::tempdir().unwrap();
@@ -582,8 +1100,10 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let hub = HubLayout::open(Some(dir.path().to_path_buf())).unwrap();
         let mr = ModelRef {
+            file: None,
             model: "../../victim".to_string(),
             owner: "owner".to_string(),
+            revision: None,
             tag: "Q4".to_string(),
         };
 
@@ -657,48 +1177,29 @@ mod tests {
     }
 
     #[test]
-    fn derive_tag_strips_model_base_and_gguf_suffix() {
-        assert_eq!(
-            derive_tag(
-                "Phi-4-mini-reasoning-BF16.gguf",
-                "Phi-4-mini-reasoning-GGUF"
-            ),
-            Some("BF16".to_string())
-        );
-    }
-
-    #[test]
-    fn derive_tag_handles_quantization_with_underscores() {
-        assert_eq!(
-            derive_tag("Qwen3-1.7B-Q4_K_M.gguf", "Qwen3-1.7B-GGUF"),
-            Some("Q4_K_M".to_string())
-        );
-    }
-
-    #[test]
-    fn derive_tag_handles_ud_prefix_quantization() {
-        assert_eq!(
-            derive_tag("Qwen3.5-0.8B-UD-Q2_K_XL.gguf", "Qwen3.5-0.8B-GGUF"),
-            Some("UD-Q2_K_XL".to_string())
-        );
-    }
+    fn closest_tags_ranks_near_misses_first() {
+        let available: Vec<String> = ["BF16", "Q4_K_M", "Q4_K_S", "Q8_0"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
 
-    #[test]
-    fn derive_tag_strips_shard_suffix() {
         assert_eq!(
-            derive_tag("Model-BF16-00001-of-00002.gguf", "Model-GGUF"),
-            Some("BF16".to_string())
+            closest_tags("Q4_KM", &available),
+            vec!["Q4_K_M", "Q4_K_S", "Q8_0"]
         );
+        assert_eq!(closest_tags("q8_0", &available)[0], "Q8_0");
     }
 
     #[test]
-    fn derive_tag_returns_none_for_non_matching_prefix() {
-        assert_eq!(derive_tag("mmproj-BF16.gguf", "Qwen3.5-0.8B-GGUF"), None);
+    fn closest_tags_is_empty_without_candidates() {
+        assert!(closest_tags("Q4", &Vec::new()).is_empty());
     }
 
     #[test]
-    fn derive_tag_returns_none_for_non_gguf_file() {
-        assert_eq!(derive_tag("readme.txt", "Model-GGUF"), None);
+    fn edit_distance_counts_single_edits() {
+        assert_eq!(edit_distance("q4_km", "q4_k_m"), 1);
+        assert_eq!(edit_distance("abc", "abc"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
     }
 
     #[test]
@@ -739,6 +1240,105 @@ mod tests {
         assert_eq!(result[0].model_ref.tag, "Q4");
     }
 
+    #[test]
+    fn resolve_local_model_finds_the_first_shard_and_projector() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_dir = setup_model_dir(dir.path(), "owner", "model-GGUF");
+        write_ref(&model_dir, "commit1");
+        for (file, hash) in [
+            ("Q8_0/model-Q8_0-00002-of-00002.gguf", "shard2"),
+            ("Q8_0/model-Q8_0-00001-of-00002.gguf", "shard1"),
+            ("model-Q4_K_M.gguf", "q4"),
+            ("mmproj-F16.gguf", "mmproj"),
+        ] {
+            write_blob(&model_dir, hash);
+            write_snapshot_symlink(&model_dir, "commit1", file, hash);
+        }
+
+        let local = resolve_local_model(
+            &model_ref("owner/model-GGUF:q8_0"),
+            Some(dir.path().to_path_buf()),
+        )
+        .unwrap();
+
+        let snapshot = model_dir.join("snapshots/commit1");
+        assert_eq!(
+            local,
+            LocalModel {
+                model: snapshot.join("Q8_0/model-Q8_0-00001-of-00002.gguf"),
+                mmproj: Some(snapshot.join("mmproj-F16.gguf")),
+                shards: vec![
+                    snapshot.join("Q8_0/model-Q8_0-00001-of-00002.gguf"),
+                    snapshot.join("Q8_0/model-Q8_0-00002-of-00002.gguf"),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn resolve_local_model_follows_the_revision() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_dir = setup_model_dir(dir.path(), "owner", "model-GGUF");
+        let pinned = "0123456789abcdef0123456789abcdef01234567";
+        write_ref(&model_dir, "commit1");
+        fs::write(model_dir.join("refs/v1"), "commit2").unwrap();
+        for (commit, hash) in [("commit1", "main"), ("commit2", "v1"), (pinned, "pinned")] {
+            write_blob(&model_dir, hash);
+            write_snapshot_symlink(&model_dir, commit, "model-Q4.gguf", hash);
+        }
+
+        for (reference, commit) in [
+            ("owner/model-GGUF:Q4", "commit1"),
+            ("owner/model-GGUF@v1:Q4", "commit2"),
+            (&format!("owner/model-GGUF@{pinned}:Q4"), pinned),
+        ] {
+            let local =
+                resolve_local_model(&model_ref(reference), Some(dir.path().to_path_buf())).unwrap();
+            assert_eq!(
+                local.model,
+                model_dir
+                    .join("snapshots")
+                    .join(commit)
+                    .join("model-Q4.gguf"),
+                "{reference}"
+            );
+        }
+        let missing = resolve_local_model(
+            &model_ref("owner/model-GGUF@v2:Q4"),
+            Some(dir.path().to_path_buf()),
+        );
+        assert!(matches!(missing, Err(PacaError::ModelNotInstalled(_))));
+    }
+
+    #[test]
+    fn resolve_local_model_rejects_a_dangling_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_dir = setup_model_dir(dir.path(), "owner", "model-GGUF");
+        write_ref(&model_dir, "commit1");
+        write_snapshot_symlink(&model_dir, "commit1", "model-Q4.gguf", "missing_hash");
+
+        let result = resolve_local_model(
+            &model_ref("owner/model-GGUF:Q4"),
+            Some(dir.path().to_path_buf()),
+        );
+
+        assert!(matches!(result, Err(PacaError::ModelNotInstalled(_))));
+    }
+
+    #[test]
+    fn resolve_local_model_rejects_a_missing_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_dir = setup_model_dir(dir.path(), "owner", "model-GGUF");
+        write_blob(&model_dir, "q4");
+        write_ref(&model_dir, "commit1");
+        write_snapshot_symlink(&model_dir, "commit1", "model-Q4.gguf", "q4");
+
+        for reference in ["owner/model-GGUF:Q8", "owner/other-GGUF:Q4"] {
+            let result = resolve_local_model(&model_ref(reference), Some(dir.path().to_path_buf()));
+            assert!(matches!(result, Err(PacaError::ModelNotInstalled(_))));
+        }
+    }
+
     #[test]
     fn list_models_discovers_multiple_tags_from_snapshots() {
         let dir = tempfile::tempdir().unwrap();
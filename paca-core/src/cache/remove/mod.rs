@@ -3,9 +3,13 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use crate::cache::{HubLayout, ModelPaths, derive_tag, is_gguf};
+use serde::Serialize;
+
+use crate::cache::{HubLayout, ModelPaths, is_gguf, tag_not_found};
+use crate::config::Profile;
 use crate::error::{ModelRefError, PacaError};
 use crate::model::ModelRef;
+use crate::model::derive_tag;
 use crate::path::join_child;
 
 /// What to remove from the cache.
@@ -20,35 +24,51 @@ pub enum RemoveTarget {
 impl FromStr for RemoveTarget {
     type Err = ModelRefError;
 
+    /// Accepts every form [`ModelRef`] does; one that names no tag (or
+    /// file) targets the whole repository.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.contains(':') {
-            return Ok(Self::Tag(s.parse()?));
-        }
-
-        let (owner, model) = s.split_once('/').ok_or(ModelRefError::MissingOwner)?;
-        if owner.is_empty() || model.is_empty() {
-            return Err(ModelRefError::MissingOwner);
+        match s.parse() {
+            Ok(model_ref) => Ok(Self::Tag(model_ref)),
+            Err(ModelRefError::MissingTag) => {
+                let ModelRef { owner, model, .. } = ModelRef::parse_with_default_tag(s)?;
+                Ok(Self::Repo { owner, model })
+            }
+            Err(e) => Err(e),
         }
-
-        Ok(Self::Repo {
-            owner: owner.to_string(),
-            model: model.to_string(),
-        })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct RemoveResult {
     pub removed_files: Vec<PathBuf>,
 }
 
 pub fn remove_model(target: &str, hub_dir: Option<PathBuf>) -> Result<RemoveResult, PacaError> {
-    let target: RemoveTarget = target.parse()?;
-    let hub = HubLayout::open(hub_dir)?;
+    remove_model_with(target, hub_dir, &Profile::default())
+}
 
-    match target {
+/// [`remove_model`], refusing targets `profile` protects.
+pub fn remove_model_with(
+    target: &str,
+    hub_dir: Option<PathBuf>,
+    profile: &Profile,
+) -> Result<RemoveResult, PacaError> {
+    remove_from(&HubLayout::open(hub_dir)?, target, profile)
+}
+
+pub(crate) fn remove_from(
+    hub: &HubLayout,
+    target: &str,
+    profile: &Profile,
+) -> Result<RemoveResult, PacaError> {
+    let parsed: RemoveTarget = target.parse()?;
+    if profile.protects(&parsed) {
+        return Err(PacaError::Protected(target.to_string()));
+    }
+
+    match parsed {
         RemoveTarget::Repo { owner, model } => remove_repo(hub.root(), &owner, &model),
-        RemoveTarget::Tag(model_ref) => remove_tag(&hub, &model_ref),
+        RemoveTarget::Tag(model_ref) => remove_tag(hub, &model_ref),
     }
 }
 
@@ -83,7 +103,7 @@ fn remove_tag(hub: &HubLayout, model_ref: &ModelRef) -> Result<RemoveResult, Pac
 
     let tag_entries = find_tag_paths(&snapshot_dir, &model_ref.model, &model_ref.tag)?;
     if tag_entries.is_empty() {
-        return Err(PacaError::ModelNotInstalled(model_ref.to_string()));
+        return Err(tag_not_found(hub, model_ref)?);
     }
 
     let mut removed_files = Vec::new();
@@ -136,6 +156,8 @@ fn prune_orphaned_blobs(
 /// files nested under a `{tag}/` subdirectory), the subdirectory itself is
 /// returned once so callers can remove the entire shard tree with a single
 /// call.
+///
+/// Tags match case-insensitively, as `derive_tag` does.
 fn find_tag_paths(snapshot_dir: &Path, model: &str, tag: &str) -> Result<Vec<PathBuf>, PacaError> {
     let mut entries: Vec<PathBuf> = Vec::new();
     collect_tag_paths(snapshot_dir, snapshot_dir, model, tag, &mut entries)?;
@@ -170,13 +192,14 @@ fn collect_tag_paths(
             .into_owned();
 
         if let Some((subdir, _)) = relative.split_once('/') {
-            if subdir == tag {
+            if subdir.eq_ignore_ascii_case(tag) {
                 let subdir_path = base.join(subdir);
                 if !entries.contains(&subdir_path) {
                     entries.push(subdir_path);
                 }
             }
-        } else if derive_tag(&name, model).as_deref() == Some(tag) {
+        } else if derive_tag(&name, model).is_some_and(|derived| derived.eq_ignore_ascii_case(tag))
+        {
             entries.push(path);
         }
     }
@@ -271,6 +294,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parses_target_from_a_repo_url() {
+        let target: RemoveTarget = "https://huggingface.co/owner/model-GGUF".parse().unwrap();
+        assert_eq!(
+            target,
+            RemoveTarget::Repo {
+                owner: "owner".to_string(),
+                model: "model-GGUF".to_string(),
+            }
+        );
+    }
+
     #[test]
     fn parses_target_without_owner_errors() {
         let result: Result<RemoveTarget, _> = "model-only".parse();
@@ -298,7 +333,10 @@ mod tests {
         let result = remove_model("owner/Model/../../victim", Some(hub));
 
         assert!(
-            matches!(result, Err(PacaError::UnsafePath(_))),
+            matches!(
+                result,
+                Err(PacaError::ModelRef(ModelRefError::InvalidPath(_)))
+            ),
             "got {result:?}"
         );
         assert!(victim.join("important.txt").exists());
@@ -323,7 +361,30 @@ mod tests {
             "owner/model-GGUF:NONEXISTENT",
             Some(dir.path().to_path_buf()),
         );
-        assert!(matches!(result, Err(PacaError::ModelNotInstalled(_))));
+        match result {
+            Err(PacaError::TagNotFound { suggestions, .. }) => {
+                assert_eq!(suggestions, vec!["Q4".to_string()]);
+            }
+            other => panic!("expected TagNotFound, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn remove_tag_matches_tags_case_insensitively() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_dir = setup_model_dir(dir.path(), "owner", "model-GGUF");
+        write_blob(&model_dir, "hash_q4");
+        write_ref(&model_dir, "commit1");
+        write_snapshot_symlink(&model_dir, "commit1", "model-Q4_K_M.gguf", "hash_q4");
+
+        let result =
+            remove_model("owner/model-GGUF:q4_k_m", Some(dir.path().to_path_buf())).unwrap();
+
+        assert!(
+            result
+                .removed_files
+                .contains(&model_dir.join("snapshots/commit1/model-Q4_K_M.gguf"))
+        );
     }
 
     #[test]
@@ -480,6 +541,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn remove_model_with_refuses_what_the_profile_protects() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_dir = setup_model_dir(dir.path(), "owner", "model-GGUF");
+        write_blob(&model_dir, "hash_q4");
+        write_ref(&model_dir, "commit1");
+        write_snapshot_symlink(&model_dir, "commit1", "model-Q4.gguf", "hash_q4");
+        let profile = Profile {
+            protected: vec!["owner/model-GGUF:Q4".to_string()],
+            ..Profile::default()
+        };
+
+        let hub_dir = Some(dir.path().to_path_buf());
+        let result = remove_model_with("owner/model-GGUF", hub_dir, &profile);
+
+        assert!(matches!(result, Err(PacaError::Protected(_))));
+        assert!(model_dir.join("blobs/hash_q4").exists());
+    }
+
     #[test]
     fn remove_repo_leaves_sibling_repos_untouched() {
         let dir = tempfile::tempdir().unwrap();
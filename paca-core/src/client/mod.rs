@@ -0,0 +1,530 @@
+//! [`Paca`], one configured handle on a hub cache and the registry it
+//! downloads from.
+//!
+//! The free functions elsewhere in the crate read their configuration
+//! from the environment on every call. A program that needs two caches,
+//! or two endpoints, builds a `Paca` for each instead; both routes share
+//! one implementation.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::cache::clean::{CleanResult, clean_hub};
+use crate::cache::remove::{RemoveResult, remove_from};
+use crate::cache::{HubLayout, ModelEntry, OutdatedReport, check_outdated, list_installed};
+use crate::config::Profile;
+use crate::download::{Download, DownloadedFile, ModelManifest, fetch_manifest, install_model};
+use crate::env;
+use crate::error::PacaError;
+use crate::http::HttpOptions;
+use crate::progress::{FileProgress, NoProgress, ProgressFactory};
+use crate::registry::endpoint::model_endpoints_with;
+use crate::registry::huggingface::Credentials;
+use crate::registry::{HuggingFace, Registry};
+
+/// A hub cache and the registry it downloads from, configured once.
+///
+/// ```no_run
+/// # async fn example() -> Result<(), paca::error::PacaError> {
+/// let paca = paca::Paca::builder()
+///     .hub_dir("/srv/models/hub")
+///     .endpoint("http://models.lan:8080")
+///     .build()?;
+/// paca.download("unsloth/GLM-4.7-GGUF:BF16").await?;
+/// for entry in paca.list()? {
+///     println!("{}", entry.model_ref);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Paca {
+    http: HttpOptions,
+    hub: HubLayout,
+    profile: Profile,
+    progress: Arc<dyn ProgressFactory>,
+    registry: Arc<dyn Registry>,
+}
+
+/// Where the HuggingFace token comes from.
+#[derive(Clone, Debug)]
+enum Token {
+    /// Resolved when the handle is built, as the free functions do, with
+    /// the profile's tokens below `HF_TOKEN`.
+    Environment,
+    /// Given to the builder; `None` is anonymous.
+    Given(Option<String>),
+}
+
+impl Paca {
+    pub fn builder() -> PacaBuilder {
+        PacaBuilder::default()
+    }
+
+    /// The hub cache directory.
+    pub fn hub_dir(&self) -> &Path {
+        self.hub.root()
+    }
+
+    /// The registry downloads and update checks go to: the one given to
+    /// the builder, else HuggingFace at the configured endpoints.
+    pub fn registry(&self) -> Arc<dyn Registry> {
+        Arc::clone(&self.registry)
+    }
+
+    /// Lists the models in the cache. See [`crate::cache::list_models`].
+    pub fn list(&self) -> Result<Vec<ModelEntry>, PacaError> {
+        list_installed(&self.hub)
+    }
+
+    /// Fetches the manifest for `model` without downloading it. See
+    /// [`crate::download::fetch_manifest`].
+    pub async fn manifest(&self, model: &str) -> Result<ModelManifest, PacaError> {
+        fetch_manifest(self.registry.as_ref(), model).await
+    }
+
+    /// Downloads `model` into the cache, reporting through the
+    /// builder's progress factory.
+    pub async fn download(&self, model: &str) -> Result<Vec<DownloadedFile>, PacaError> {
+        let manifest = fetch_manifest(self.registry.as_ref(), model).await?;
+        self.install(manifest).await
+    }
+
+    /// Downloads the files of a manifest from [`Paca::manifest`].
+    pub async fn download_manifest(
+        &self,
+        manifest: ModelManifest,
+    ) -> Result<Vec<DownloadedFile>, PacaError> {
+        self.install(manifest).await
+    }
+
+    /// Starts downloading the files of a manifest in the background,
+    /// reporting through the returned [`Download`] rather than the
+    /// builder's progress factory.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn start_download(&self, manifest: ModelManifest) -> Result<Download, PacaError> {
+        Ok(Download::start(
+            self.registry(),
+            manifest,
+            self.hub.clone(),
+            self.http.clone(),
+        ))
+    }
+
+    /// [`Paca::download_manifest`], reporting through `progress`: one
+    /// reporter per file, as for [`crate::download::download_model`].
+    pub async fn download_manifest_with(
+        &self,
+        manifest: ModelManifest,
+        progress: Vec<Arc<dyn FileProgress>>,
+    ) -> Result<Vec<DownloadedFile>, PacaError> {
+        install_model(self.registry(), manifest, &self.hub, progress, &self.http).await
+    }
+
+    async fn install(&self, manifest: ModelManifest) -> Result<Vec<DownloadedFile>, PacaError> {
+        let progress = manifest
+            .files()
+            .map(|(filename, size)| self.progress.file_progress(filename, size))
+            .collect();
+        self.download_manifest_with(manifest, progress).await
+    }
+
+    /// A handle on `registry` and the cache at `hub_dir`, else the
+    /// environment's, for the free functions to delegate to.
+    pub(crate) fn for_registry(
+        registry: Arc<dyn Registry>,
+        hub_dir: Option<PathBuf>,
+    ) -> Result<Self, PacaError> {
+        let builder = Self::builder().registry(registry);
+        match hub_dir {
+            Some(hub_dir) => builder.hub_dir(hub_dir),
+            None => builder,
+        }
+        .build()
+    }
+
+    /// Checks the cached models for newer commits upstream. See
+    /// [`crate::cache::check_outdated_models`].
+    pub async fn outdated(&self) -> Result<OutdatedReport, PacaError> {
+        check_outdated(self.registry.as_ref(), &self.hub).await
+    }
+
+    /// Removes partial downloads and orphaned files. See
+    /// [`crate::cache::clean::clean_cache`].
+    pub fn clean(&self) -> Result<CleanResult, PacaError> {
+        clean_hub(&self.hub)
+    }
+
+    /// Removes a repo or one of its tags, unless the profile protects it.
+    /// See [`crate::cache::remove::remove_model`].
+    pub fn remove(&self, target: &str) -> Result<RemoveResult, PacaError> {
+        remove_from(&self.hub, target, &self.profile)
+    }
+}
+
+/// Configures a [`Paca`]. Anything left unset comes from the
+/// environment, as it does for the free functions.
+#[derive(Clone)]
+pub struct PacaBuilder {
+    endpoints: Vec<String>,
+    http: Option<HttpOptions>,
+    hub_dir: Option<PathBuf>,
+    profile: Option<Profile>,
+    progress: Arc<dyn ProgressFactory>,
+    registry: Option<Arc<dyn Registry>>,
+    token: Token,
+}
+
+impl Default for PacaBuilder {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            http: None,
+            hub_dir: None,
+            profile: None,
+            progress: Arc::new(NoProgress),
+            registry: None,
+            token: Token::Environment,
+        }
+    }
+}
+
+impl PacaBuilder {
+    /// The hub cache directory, instead of `HF_HUB_CACHE` and friends.
+    #[must_use]
+    pub fn hub_dir(mut self, hub_dir: impl Into<PathBuf>) -> Self {
+        self.hub_dir = Some(hub_dir.into());
+        self
+    }
+
+    /// The HuggingFace endpoint, instead of `PACA_MIRRORS`,
+    /// `MODEL_ENDPOINT` and `HF_ENDPOINT`.
+    #[must_use]
+    pub fn endpoint(self, endpoint: impl Into<String>) -> Self {
+        self.endpoints([endpoint])
+    }
+
+    /// HuggingFace endpoints to try in order, each falling back to the
+    /// next; see [`HuggingFace::with_fallbacks`].
+    #[must_use]
+    pub fn endpoints(mut self, endpoints: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.endpoints = endpoints.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The HuggingFace token, instead of `HF_TOKEN` and the token file;
+    /// `None` downloads anonymously.
+    #[must_use]
+    pub fn token(mut self, token: Option<impl Into<String>>) -> Self {
+        self.token = Token::Given(token.map(Into::into));
+        self
+    }
+
+    /// A registry other than HuggingFace, such as
+    /// [`crate::registry::ModelScope`]. Endpoints and token then go unused.
+    #[must_use]
+    pub fn registry(mut self, registry: Arc<dyn Registry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Timeouts, proxy, certificates and other connection settings,
+    /// instead of [`HttpOptions::from_env`].
+    #[must_use]
+    pub fn http(mut self, http: HttpOptions) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    /// A config profile filling in what the builder leaves unset: its
+    /// hub dir, endpoints, tokens, concurrency and rate limit below the
+    /// environment variables, as for the CLI, and the models
+    /// [`Paca::remove`] refuses to delete.
+    #[must_use]
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Where download progress goes; nowhere by default.
+    #[must_use]
+    pub fn progress(mut self, progress: impl ProgressFactory + 'static) -> Self {
+        self.progress = Arc::new(progress);
+        self
+    }
+
+    pub fn build(self) -> Result<Paca, PacaError> {
+        let http = match (self.http, &self.profile) {
+            (Some(http), _) => http,
+            (None, Some(profile)) => HttpOptions::from_env_with(profile)?,
+            (None, None) => HttpOptions::from_env()?,
+        };
+        let profile = self.profile.unwrap_or_default();
+        let hub_dir = match self.hub_dir {
+            Some(hub_dir) => hub_dir,
+            None => env::hub_cache_with(profile.hub_dir.as_deref())?,
+        };
+        let registry = match self.registry {
+            Some(registry) => registry,
+            None => huggingface(self.endpoints, &self.token, &profile, &http)?,
+        };
+        Ok(Paca {
+            http,
+            hub: HubLayout::open(Some(hub_dir))?,
+            profile,
+            progress: self.progress,
+            registry,
+        })
+    }
+}
+
+/// HuggingFace at `endpoints`, or the profile's and environment's when
+/// none are given, each falling back to the next.
+fn huggingface(
+    endpoints: Vec<String>,
+    token: &Token,
+    profile: &Profile,
+    http: &HttpOptions,
+) -> Result<Arc<dyn Registry>, PacaError> {
+    let endpoints = if endpoints.is_empty() {
+        model_endpoints_with(&profile.endpoints)
+    } else {
+        endpoints
+    };
+    let (first, rest) = endpoints
+        .split_first()
+        .expect("the hub is always an endpoint");
+    let credentials = match token {
+        Token::Environment => Credentials::Resolved(profile.clone()),
+        Token::Given(token) => Credentials::Given(token.clone()),
+    };
+    let registry = HuggingFace::with_http(first, credentials, http)?.with_fallbacks(rest)?;
+    Ok(Arc::new(registry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::FileProgress;
+    use std::fs;
+    use std::sync::Mutex;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// A hub serving `owner/model-GGUF:Q4` as a single file of `body`.
+    async fn hub_with(body: &'static [u8], blob: &str) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/owner/model-GGUF/manifests/Q4"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ggufFile": {"rfilename": "model-Q4.gguf", "size": body.len()}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/owner/model-GGUF/resolve/main/model-Q4.gguf"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-repo-commit", "commit1")
+                    .insert_header("etag", format!("\"{blob}\"").as_str())
+                    .insert_header("x-linked-size", body.len().to_string().as_str()),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/owner/model-GGUF/resolve/main/model-Q4.gguf"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn two_clients_keep_their_own_hub_and_endpoint() {
+        temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            let first_hub = hub_with(b"first", "blob1").await;
+            let second_hub = hub_with(b"second", "blob2").await;
+            let first_dir = tempfile::tempdir().unwrap();
+            let second_dir = tempfile::tempdir().unwrap();
+            let first = Paca::builder()
+                .hub_dir(first_dir.path())
+                .endpoint(first_hub.uri())
+                .build()
+                .unwrap();
+            let second = Paca::builder()
+                .hub_dir(second_dir.path())
+                .endpoint(second_hub.uri())
+                .build()
+                .unwrap();
+
+            let first_files = first.download("owner/model-GGUF:Q4").await.unwrap();
+            let second_files = second.download("owner/model-GGUF:Q4").await.unwrap();
+
+            assert_eq!(fs::read(&first_files[0].path).unwrap(), b"first");
+            assert_eq!(fs::read(&second_files[0].path).unwrap(), b"second");
+            assert!(first_files[0].path.starts_with(first_dir.path()));
+            assert!(second_files[0].path.starts_with(second_dir.path()));
+            assert_eq!(first.list().unwrap().len(), 1);
+
+            first.remove("owner/model-GGUF").unwrap();
+            assert!(first.list().unwrap().is_empty());
+            assert_eq!(second.list().unwrap().len(), 1);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn downloads_with_the_given_token() {
+        temp_env::async_with_vars(
+            [
+                ("HF_HUB_OFFLINE", None::<&str>),
+                ("HF_TOKEN", Some("from-env")),
+            ],
+            async {
+                let server = MockServer::start().await;
+                Mock::given(method("GET"))
+                    .and(header("Authorization", "Bearer given"))
+                    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                        "ggufFile": {"rfilename": "model-Q4.gguf", "size": 4}
+                    })))
+                    .expect(1)
+                    .mount(&server)
+                    .await;
+                let dir = tempfile::tempdir().unwrap();
+                let paca = Paca::builder()
+                    .hub_dir(dir.path())
+                    .endpoint(server.uri())
+                    .token(Some("given"))
+                    .build()
+                    .unwrap();
+
+                let manifest = paca.manifest("owner/model-GGUF:Q4").await.unwrap();
+
+                assert_eq!(manifest.files().count(), 1);
+            },
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn the_profile_fills_what_the_builder_leaves_unset() {
+        temp_env::async_with_vars(
+            [
+                ("HF_HUB_OFFLINE", None::<&str>),
+                ("HF_TOKEN", None),
+                ("HF_HUB_CACHE", None),
+                ("HUGGINGFACE_HUB_CACHE", None),
+                ("HF_HOME", None),
+                ("PACA_MIRRORS", None),
+                ("MODEL_ENDPOINT", None),
+                ("HF_ENDPOINT", None),
+            ],
+            async {
+                let server = MockServer::start().await;
+                Mock::given(method("GET"))
+                    .and(header("Authorization", "Bearer from-profile"))
+                    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                        "ggufFile": {"rfilename": "model-Q4.gguf", "size": 4}
+                    })))
+                    .expect(1)
+                    .mount(&server)
+                    .await;
+                let dir = tempfile::tempdir().unwrap();
+                let profile = Profile {
+                    hub_dir: Some(dir.path().to_path_buf()),
+                    endpoints: vec![server.uri()],
+                    protected: vec!["owner/model-GGUF".to_string()],
+                    tokens: [("127.0.0.1".to_string(), "from-profile".to_string())].into(),
+                    ..Profile::default()
+                };
+                let paca = Paca::builder().profile(profile).build().unwrap();
+
+                paca.manifest("owner/model-GGUF:Q4").await.unwrap();
+
+                assert_eq!(paca.hub_dir(), dir.path());
+                assert!(matches!(
+                    paca.remove("owner/model-GGUF"),
+                    Err(PacaError::Protected(_))
+                ));
+            },
+        )
+        .await;
+    }
+
+    #[test]
+    fn builds_its_registry_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let paca = Paca::builder()
+            .hub_dir(dir.path())
+            .endpoint("http://127.0.0.1:9")
+            .token(None::<String>)
+            .build()
+            .unwrap();
+
+        assert!(Arc::ptr_eq(&paca.registry(), &paca.registry()));
+    }
+
+    #[tokio::test]
+    async fn reports_progress_through_the_factory() {
+        struct Recorded(Arc<Mutex<Vec<u64>>>);
+
+        impl FileProgress for Recorded {
+            fn start(&self, _: u64) {}
+            fn inc(&self, delta: u64) {
+                self.0.lock().unwrap().push(delta);
+            }
+            fn println(&self, _: &str) {}
+            fn finish(&self) {}
+        }
+
+        temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            let server = hub_with(b"gguf", "blob1").await;
+            let dir = tempfile::tempdir().unwrap();
+            let files = Arc::new(Mutex::new(Vec::new()));
+            let deltas = Arc::new(Mutex::new(Vec::new()));
+            let paca = Paca::builder()
+                .hub_dir(dir.path())
+                .endpoint(server.uri())
+                .progress({
+                    let files = Arc::clone(&files);
+                    let deltas = Arc::clone(&deltas);
+                    move |filename: &str, size: u64| {
+                        files.lock().unwrap().push((filename.to_string(), size));
+                        Arc::new(Recorded(Arc::clone(&deltas))) as Arc<dyn FileProgress>
+                    }
+                })
+                .build()
+                .unwrap();
+
+            paca.download("owner/model-GGUF:Q4").await.unwrap();
+
+            assert_eq!(*files.lock().unwrap(), [("model-Q4.gguf".to_string(), 4)]);
+            assert_eq!(deltas.lock().unwrap().iter().sum::<u64>(), 4);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn start_download_uses_the_clients_hub() {
+        use futures_util::StreamExt;
+
+        temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            let server = hub_with(b"gguf", "blob1").await;
+            let dir = tempfile::tempdir().unwrap();
+            let paca = Paca::builder()
+                .hub_dir(dir.path())
+                .endpoint(server.uri())
+                .build()
+                .unwrap();
+
+            let manifest = paca.manifest("owner/model-GGUF:Q4").await.unwrap();
+            let mut download = paca.start_download(manifest).unwrap();
+            while download.next().await.is_some() {}
+            let files = download.finish().await.unwrap();
+
+            assert!(files[0].path.starts_with(dir.path()));
+            assert_eq!(fs::read(&files[0].path).unwrap(), b"gguf");
+        })
+        .await;
+    }
+}
@@ -0,0 +1,591 @@
+//! The config file, `$XDG_CONFIG_HOME/paca/config.toml`: named profiles
+//! of persistent defaults.
+//!
+//! A profile only fills gaps. Command-line flags and environment variables
+//! still win, and nothing is read unless a program loads the file and
+//! hands a profile to [`crate::PacaBuilder::profile`] or the `_with`
+//! variants of the free functions, so library callers see no change
+//! unless they opt in.
+//!
+//! ```toml
+//! profile = "default"
+//!
+//! [profiles.default]
+//! hub-dir = "/srv/models/hub"
+//! endpoints = ["http://models.lan:8080", "https://huggingface.co"]
+//! concurrency = 8
+//! rate-limit = 52428800
+//! protected = ["unsloth/GLM-4.7-GGUF:BF16"]
+//!
+//! [profiles.default.tokens]
+//! "huggingface.co" = "hf_..."
+//!
+//! [profiles.default.token-commands]
+//! "hf.internal" = "pass show hf-internal"
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::cache::remove::RemoveTarget;
+use crate::error::PacaError;
+use crate::http::parse_rate;
+
+/// The profile used when none is named.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// The whole config file.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// The profile to use when neither `--profile` nor `PACA_PROFILE`
+    /// names one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// One named set of defaults.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    /// The hub cache directory, below `HF_HUB_CACHE` and `HF_HOME`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hub_dir: Option<PathBuf>,
+    /// Endpoints to download from in order, the hub last, below
+    /// `PACA_MIRRORS`, `MODEL_ENDPOINT` and `HF_ENDPOINT`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub endpoints: Vec<String>,
+    /// Connections a large file is downloaded over.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<usize>,
+    /// Download bandwidth cap, in bytes per second.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<u64>,
+    /// Repositories or tags that `rm` refuses to delete.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub protected: Vec<String>,
+    /// Tokens by endpoint host, below `HF_TOKEN` and above the token file.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", deserialize_with = "hosts")]
+    pub tokens: BTreeMap<String, String>,
+    /// Commands printing a host's token, for hosts without one in
+    /// [`Profile::tokens`], so the token can stay in a password manager.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", deserialize_with = "hosts")]
+    pub token_commands: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// Reads the config at `path`; a missing file is an empty config.
+    pub fn load(path: &Path) -> Result<Self, PacaError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => contents.parse(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(PacaError::ConfigFile(e)),
+        }
+    }
+
+    /// Writes the config to `path`, readable by its owner only since it
+    /// may hold tokens.
+    pub fn save(&self, path: &Path) -> Result<(), PacaError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(PacaError::ConfigFile)?;
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .mode(0o600)
+            .open(path)
+            .map_err(PacaError::ConfigFile)?;
+        file.set_permissions(fs::Permissions::from_mode(0o600))
+            .map_err(PacaError::ConfigFile)?;
+        file.write_all(self.to_string().as_bytes())
+            .map_err(PacaError::ConfigFile)
+    }
+
+    /// The profile to use: `requested`, else the file's `profile`, else
+    /// [`DEFAULT_PROFILE`].
+    pub fn profile_name<'a>(&'a self, requested: Option<&'a str>) -> &'a str {
+        requested
+            .or(self.profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE)
+    }
+
+    /// The named profile, empty when the file has none by that name.
+    pub fn profile(&self, name: &str) -> Profile {
+        self.profiles.get(name).cloned().unwrap_or_default()
+    }
+
+    /// The named profile, created if missing, for `paca config set`.
+    pub fn profile_mut(&mut self, name: &str) -> &mut Profile {
+        self.profiles.entry(name.to_string()).or_default()
+    }
+}
+
+impl FromStr for Config {
+    type Err = PacaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s).map_err(|e| PacaError::InvalidConfig {
+            // Spans are byte offsets; people read lines.
+            line: e
+                .span()
+                .map_or(1, |span| s[..span.start].matches('\n').count() + 1),
+            reason: e.message().to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&toml::to_string(self).map_err(|_| std::fmt::Error)?)
+    }
+}
+
+impl Profile {
+    /// The value of `key` as `paca config get` prints it, or `None` when
+    /// unset. Lists are comma-separated.
+    pub fn get(&self, key: &str) -> Result<Option<String>, PacaError> {
+        Ok(match parse_key(key)? {
+            Key::HubDir => self.hub_dir.as_ref().map(|dir| dir.display().to_string()),
+            Key::Endpoints => joined(&self.endpoints),
+            Key::Token(host) => self.tokens.get(&host).cloned(),
+            Key::TokenCommand(host) => self.token_commands.get(&host).cloned(),
+            Key::Concurrency => self.concurrency.map(|n| n.to_string()),
+            Key::RateLimit => self.rate_limit.map(|n| n.to_string()),
+            Key::Protected => joined(&self.protected),
+        })
+    }
+
+    /// Sets `key` from a `paca config set` argument. Lists are
+    /// comma-separated; `rate-limit` takes a `K`, `M` or `G` suffix.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), PacaError> {
+        let bad = |reason: &str| PacaError::InvalidConfigValue {
+            key: key.to_string(),
+            reason: reason.to_string(),
+        };
+        match parse_key(key)? {
+            Key::HubDir => self.hub_dir = Some(PathBuf::from(value)),
+            Key::Endpoints => self.endpoints = split(value),
+            Key::Token(host) => {
+                self.tokens.insert(host, value.trim().to_string());
+            }
+            Key::TokenCommand(host) => {
+                self.token_commands.insert(host, value.to_string());
+            }
+            Key::Concurrency => {
+                let concurrency = value
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| bad("expected a positive whole number"))?;
+                self.concurrency = Some(concurrency);
+            }
+            Key::RateLimit => self.rate_limit = Some(parse_rate(value)?),
+            Key::Protected => self.protected = split(value),
+        }
+        Ok(())
+    }
+
+    /// Checks the values a hand-edited file may get wrong. Run when the
+    /// profile is used rather than when the file is read, so that
+    /// `paca config set` can still repair it.
+    pub fn validate(&self) -> Result<(), PacaError> {
+        if self.concurrency == Some(0) {
+            return Err(PacaError::InvalidConfigValue {
+                key: "concurrency".to_string(),
+                reason: "expected a positive whole number".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Clears `key`.
+    pub fn unset(&mut self, key: &str) -> Result<(), PacaError> {
+        match parse_key(key)? {
+            Key::HubDir => self.hub_dir = None,
+            Key::Endpoints => self.endpoints.clear(),
+            Key::Token(host) => {
+                self.tokens.remove(&host);
+            }
+            Key::TokenCommand(host) => {
+                self.token_commands.remove(&host);
+            }
+            Key::Concurrency => self.concurrency = None,
+            Key::RateLimit => self.rate_limit = None,
+            Key::Protected => self.protected.clear(),
+        }
+        Ok(())
+    }
+
+    /// Every key that is set, with its value as [`Profile::get`] gives it.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let mut keys: Vec<String> = ["hub-dir", "endpoints", "concurrency", "rate-limit"]
+            .map(String::from)
+            .into();
+        keys.extend(self.tokens.keys().map(|host| format!("token.{host}")));
+        keys.extend(
+            self.token_commands
+                .keys()
+                .map(|host| format!("token-command.{host}")),
+        );
+        keys.push("protected".to_string());
+
+        keys.into_iter()
+            .filter_map(|key| {
+                let value = self.get(&key).ok()??;
+                Some((key, value))
+            })
+            .collect()
+    }
+
+    /// The token for `endpoint`'s host: the one stored for it, else what
+    /// its command prints. Hosts are matched with their port first, then
+    /// without.
+    pub fn token_for(&self, endpoint: &str) -> Result<Option<String>, PacaError> {
+        let authority = endpoint
+            .split_once("://")
+            .map_or(endpoint, |(_, rest)| rest)
+            .split(['/', '?', '#'])
+            .next()
+            .unwrap_or_default()
+            .rsplit('@')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let host = authority
+            .rsplit_once(':')
+            .filter(|(_, port)| port.bytes().all(|b| b.is_ascii_digit()))
+            .map_or(authority.as_str(), |(host, _)| host);
+
+        for name in [authority.as_str(), host] {
+            if let Some(token) = self.tokens.get(name) {
+                return Ok(Some(token.clone()));
+            }
+            if let Some(command) = self.token_commands.get(name) {
+                return run_token_command(name, command).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether removing `target` would delete something in
+    /// [`Profile::protected`]: the entry itself, a tag of a protected
+    /// repository, or a repository holding a protected tag.
+    pub fn protects(&self, target: &RemoveTarget) -> bool {
+        let same_repo = |owner: &str, model: &str, other: &RemoveTarget| {
+            let (other_owner, other_model) = match other {
+                RemoveTarget::Repo { owner, model } => (owner, model),
+                RemoveTarget::Tag(model_ref) => (&model_ref.owner, &model_ref.model),
+            };
+            owner.eq_ignore_ascii_case(other_owner) && model.eq_ignore_ascii_case(other_model)
+        };
+
+        self.protected
+            .iter()
+            .filter_map(|entry| entry.parse::<RemoveTarget>().ok())
+            .any(|entry| match (&entry, target) {
+                (RemoveTarget::Repo { owner, model }, _) => same_repo(owner, model, target),
+                (RemoveTarget::Tag(protected), RemoveTarget::Repo { .. }) => {
+                    same_repo(&protected.owner, &protected.model, target)
+                }
+                (RemoveTarget::Tag(protected), RemoveTarget::Tag(model_ref)) => {
+                    same_repo(&protected.owner, &protected.model, target)
+                        && protected.tag.eq_ignore_ascii_case(&model_ref.tag)
+                }
+            })
+    }
+}
+
+/// A key `paca config` accepts. Hosts are lowercased, as they are
+/// compared.
+enum Key {
+    HubDir,
+    Endpoints,
+    Token(String),
+    TokenCommand(String),
+    Concurrency,
+    RateLimit,
+    Protected,
+}
+
+fn parse_key(key: &str) -> Result<Key, PacaError> {
+    let host = |host: &str| !host.is_empty();
+    Ok(match key {
+        "hub-dir" => Key::HubDir,
+        "endpoints" => Key::Endpoints,
+        "concurrency" => Key::Concurrency,
+        "rate-limit" => Key::RateLimit,
+        "protected" => Key::Protected,
+        _ => match key.split_once('.') {
+            Some(("token", name)) if host(name) => Key::Token(name.to_ascii_lowercase()),
+            Some(("token-command", name)) if host(name) => {
+                Key::TokenCommand(name.to_ascii_lowercase())
+            }
+            _ => return Err(PacaError::UnknownConfigKey(key.to_string())),
+        },
+    })
+}
+
+fn run_token_command(host: &str, command: &str) -> Result<String, PacaError> {
+    let failed = |reason: String| PacaError::TokenCommand {
+        host: host.to_string(),
+        reason,
+    };
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .map_err(|e| failed(e.to_string()))?;
+    if !output.status.success() {
+        return Err(failed(output.status.to_string()));
+    }
+
+    let token =
+        String::from_utf8(output.stdout).map_err(|_| failed("output is not UTF-8".into()))?;
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(failed("printed nothing".to_string()));
+    }
+    Ok(token.to_string())
+}
+
+fn split(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn joined(items: &[String]) -> Option<String> {
+    (!items.is_empty()).then(|| items.join(","))
+}
+
+/// Reads a table keyed by host, lowercased as hosts are compared.
+fn hosts<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error> {
+    let map = BTreeMap::<String, String>::deserialize(deserializer)?;
+    Ok(map
+        .into_iter()
+        .map(|(host, value)| (host.to_ascii_lowercase(), value))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"profile = "work"
+
+[profiles.default]
+hub-dir = "/srv/models/hub"
+endpoints = ["http://models.lan:8080", "https://huggingface.co"]
+concurrency = 8
+rate-limit = 52428800
+protected = ["unsloth/GLM-4.7-GGUF:BF16"]
+
+[profiles.default.tokens]
+"huggingface.co" = "hf_public"
+
+[profiles.default.token-commands]
+"hf.internal" = "printf ' hf_from_command\n'"
+
+[profiles.work]
+hub-dir = "/work/hub"
+"#;
+
+    #[test]
+    fn parses_profiles() {
+        let config: Config = SAMPLE.parse().unwrap();
+        assert_eq!(config.profile.as_deref(), Some("work"));
+        assert_eq!(config.profile_name(None), "work");
+        assert_eq!(config.profile_name(Some("default")), "default");
+
+        let profile = config.profile("default");
+        assert_eq!(profile.hub_dir, Some(PathBuf::from("/srv/models/hub")));
+        assert_eq!(profile.endpoints.len(), 2);
+        assert_eq!(profile.concurrency, Some(8));
+        assert_eq!(profile.rate_limit, Some(50 * 1024 * 1024));
+        assert_eq!(profile.tokens["huggingface.co"], "hf_public");
+        assert_eq!(
+            config.profile("work").hub_dir,
+            Some(PathBuf::from("/work/hub"))
+        );
+        assert_eq!(config.profile("missing"), Profile::default());
+    }
+
+    #[test]
+    fn round_trips_through_its_text() {
+        let config: Config = SAMPLE.parse().unwrap();
+        let reparsed: Config = config.to_string().parse().unwrap();
+        assert_eq!(reparsed, config);
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_tables_with_their_line() {
+        for (text, line) in [
+            ("[profiles.default]\nhub_dir = \"/x\"\n", 2),
+            ("[profile.default]\n", 1),
+            ("[profiles.default]\nconcurrency = \"8\"\n", 2),
+            ("\n\n[profiles.default\n", 3),
+        ] {
+            let err = text.parse::<Config>().unwrap_err();
+            assert!(
+                matches!(err, PacaError::InvalidConfig { line: l, .. } if l == line),
+                "{text:?}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn reads_any_toml_a_person_might_write() {
+        let config: Config = r#"
+[profiles.default]
+hub-dir = '/srv/models/hub'   # a literal string
+endpoints = [
+    "http://models.lan:8080",
+    "https://huggingface.co",
+]
+tokens = { "HuggingFace.co" = "hf_public" }
+"#
+        .parse()
+        .unwrap();
+
+        let profile = config.profile("default");
+        assert_eq!(profile.hub_dir, Some(PathBuf::from("/srv/models/hub")));
+        assert_eq!(profile.endpoints.len(), 2);
+        assert_eq!(profile.tokens["huggingface.co"], "hf_public");
+    }
+
+    #[test]
+    fn validate_rejects_what_the_file_cannot() {
+        let config: Config = "[profiles.default]\nconcurrency = 0\n".parse().unwrap();
+        assert!(matches!(
+            config.profile("default").validate(),
+            Err(PacaError::InvalidConfigValue { .. })
+        ));
+        assert!(Profile::default().validate().is_ok());
+    }
+
+    #[test]
+    fn load_treats_a_missing_file_as_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::load(&dir.path().join("config.toml")).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn save_writes_an_owner_only_file_that_loads_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("paca").join("config.toml");
+        let mut config = Config::default();
+        config
+            .profile_mut("default")
+            .set("token.huggingface.co", "hf_secret")
+            .unwrap();
+
+        config.save(&path).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(Config::load(&path).unwrap(), config);
+    }
+
+    #[test]
+    fn set_get_and_unset_each_key() {
+        let mut profile = Profile::default();
+        for (key, value, stored) in [
+            ("hub-dir", "/models", "/models"),
+            ("endpoints", "http://a, http://b", "http://a,http://b"),
+            ("token.hf.internal:8443", " hf_x ", "hf_x"),
+            ("token-command.huggingface.co", "pass hf", "pass hf"),
+            ("concurrency", "6", "6"),
+            ("rate-limit", "10M", "10485760"),
+            ("protected", "a/b, c/d:Q4", "a/b,c/d:Q4"),
+        ] {
+            profile.set(key, value).unwrap();
+            assert_eq!(profile.get(key).unwrap().as_deref(), Some(stored), "{key}");
+        }
+        assert_eq!(profile.entries().len(), 7);
+
+        for key in ["hub-dir", "token.hf.internal:8443", "rate-limit"] {
+            profile.unset(key).unwrap();
+            assert_eq!(profile.get(key).unwrap(), None, "{key}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bad_values() {
+        let mut profile = Profile::default();
+        assert!(matches!(
+            profile.set("hub_dir", "/x"),
+            Err(PacaError::UnknownConfigKey(_))
+        ));
+        assert!(matches!(
+            profile.get("token."),
+            Err(PacaError::UnknownConfigKey(_))
+        ));
+        assert!(matches!(
+            profile.set("concurrency", "0"),
+            Err(PacaError::InvalidConfigValue { .. })
+        ));
+    }
+
+    #[test]
+    fn token_for_matches_the_endpoint_host() {
+        let config: Config = SAMPLE.parse().unwrap();
+        let profile = config.profile("default");
+
+        assert_eq!(
+            profile
+                .token_for("https://huggingface.co")
+                .unwrap()
+                .as_deref(),
+            Some("hf_public")
+        );
+        assert_eq!(
+            profile
+                .token_for("https://HF.internal:8443/api")
+                .unwrap()
+                .as_deref(),
+            Some("hf_from_command")
+        );
+        assert_eq!(profile.token_for("http://models.lan:8080").unwrap(), None);
+    }
+
+    #[test]
+    fn a_failing_token_command_is_an_error() {
+        let mut profile = Profile::default();
+        profile.set("token-command.hub.lan", "exit 3").unwrap();
+        assert!(matches!(
+            profile.token_for("http://hub.lan"),
+            Err(PacaError::TokenCommand { .. })
+        ));
+    }
+
+    #[test]
+    fn protects_repos_and_tags() {
+        let mut profile = Profile::default();
+        profile
+            .set("protected", "unsloth/GLM-4.7-GGUF:BF16, owner/kept-GGUF")
+            .unwrap();
+        let protects = |target: &str| profile.protects(&target.parse().unwrap());
+
+        assert!(protects("unsloth/GLM-4.7-GGUF:bf16"));
+        assert!(protects("unsloth/GLM-4.7-GGUF"));
+        assert!(!protects("unsloth/GLM-4.7-GGUF:Q4_K_M"));
+        assert!(protects("owner/kept-GGUF:Q8_0"));
+        assert!(!protects("owner/other-GGUF"));
+    }
+}
@@ -1,22 +1,32 @@
 use std::fs::{self, File};
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
 use reqwest::Client;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 
-use crate::cache::{HubLayout, ModelPaths};
+use crate::cache::{HubLayout, ModelPaths, closest_tags};
+use crate::client::Paca;
+use crate::env;
 use crate::error::PacaError;
-use crate::model::ModelRef;
+use crate::http::{HttpOptions, Throttle};
+use crate::llama;
+use crate::model::tag_for_path;
+use crate::model::{DEFAULT_TAG, ModelRef};
 use crate::path::join_within;
-use crate::progress::FileProgress;
-use crate::registry::default_headers;
-use crate::registry::endpoint::model_endpoint;
-use crate::registry::manifest::{GgufFile, fetch_manifest as fetch_registry_manifest};
-use crate::registry::{ResolveInfo, build_resolve_client, fetch_resolve_info};
+use crate::progress::{FileProgress, ProgressEvent};
+use crate::registry::manifest::{GgufFile, Manifest};
+use crate::registry::{Registry, ResolveInfo, is_unavailable};
 use crate::sysinfo::check_disk_space;
 
+mod stream;
+
+pub use stream::{Download, DownloadEvent, start_download};
+
 /// A prepared download manifest: the parsed model ref plus the GGUF
 /// files that will be fetched. Returned by [`fetch_manifest`] so callers
 /// can create one progress reporter per file before invoking
@@ -24,6 +34,10 @@ use crate::sysinfo::check_disk_space;
 pub struct ModelManifest {
     model_ref: ModelRef,
     files: Vec<GgufFile>,
+    tag_defaulted: bool,
+    primary: Option<String>,
+    response: Option<serde_json::Value>,
+    llama_cache: Option<PathBuf>,
 }
 
 impl ModelManifest {
@@ -32,114 +46,370 @@ impl ModelManifest {
     pub fn files(&self) -> impl ExactSizeIterator<Item = (&str, u64)> + '_ {
         self.files.iter().map(|f| (f.filename.as_str(), f.size))
     }
+
+    /// The reference being downloaded. When the request named no tag,
+    /// this carries the tag the registry chose.
+    pub fn model_ref(&self) -> &ModelRef {
+        &self.model_ref
+    }
+
+    /// Whether the tag was chosen by the registry rather than requested.
+    pub fn tag_defaulted(&self) -> bool {
+        self.tag_defaulted
+    }
+
+    /// Also links the files into llama.cpp's cache at `llama_cache` once
+    /// they are installed, with the registry's manifest beside them, so
+    /// `llama-server -hf <ref> --offline` finds them. See [`crate::llama`].
+    #[must_use]
+    pub fn with_llama_cache(mut self, llama_cache: PathBuf) -> Self {
+        self.llama_cache = Some(llama_cache);
+        self
+    }
 }
 
-/// Fetches the model manifest from HuggingFace without starting the download.
-pub async fn fetch_manifest(model: &str) -> Result<ModelManifest, PacaError> {
-    let model_ref: ModelRef = model.parse()?;
-    let client = build_download_client(DEFAULT_CONNECT_TIMEOUT, DEFAULT_READ_TIMEOUT)?;
-    let manifest = fetch_registry_manifest(&client, model_endpoint(), &model_ref).await?;
+/// Fetches the model manifest from `registry` without starting the download.
+///
+/// `model` may omit its tag, in which case the registry's default
+/// quantization is used and recorded in [`ModelManifest::model_ref`].
+pub async fn fetch_manifest(
+    registry: &dyn Registry,
+    model: &str,
+) -> Result<ModelManifest, PacaError> {
+    env::ensure_online()?;
+    let mut model_ref = ModelRef::parse_with_default_tag(model)?;
+
+    if let Some(file) = &model_ref.file {
+        let file = fetch_single_file(registry, &model_ref, file).await?;
+        return Ok(ModelManifest {
+            files: vec![file],
+            model_ref,
+            tag_defaulted: false,
+            primary: None,
+            response: None,
+            llama_cache: None,
+        });
+    }
+
+    let tag_defaulted = model_ref.tag == DEFAULT_TAG;
+    let manifest = fetch_tag_manifest(registry, &mut model_ref).await?;
+
+    // Registries that match tags loosely still name the file they picked;
+    // report the tag under its canonical spelling.
+    if let Some(tag) = chosen_tag(&manifest, &model_ref)
+        && (tag_defaulted || tag.eq_ignore_ascii_case(&model_ref.tag))
+    {
+        model_ref.tag = tag;
+    }
+
     Ok(ModelManifest {
         files: manifest.gguf_files,
         model_ref,
+        tag_defaulted,
+        primary: manifest.primary,
+        response: manifest.response,
+        llama_cache: None,
+    })
+}
+
+/// Fetches the manifest for the reference's tag. When the registry does not
+/// know the tag, its tag list decides: a tag differing only in case is
+/// retried under its canonical spelling (and `model_ref` updated to match),
+/// anything else fails with the closest tags the repo does offer.
+async fn fetch_tag_manifest(
+    registry: &dyn Registry,
+    model_ref: &mut ModelRef,
+) -> Result<Manifest, PacaError> {
+    let error = match registry.manifest(model_ref).await {
+        Ok(manifest) => return Ok(manifest),
+        Err(e) if is_unknown_tag(&e) => e,
+        Err(e) => return Err(e),
+    };
+
+    // If the tree is unreachable too (no such repo, gated), the manifest
+    // error is the more accurate one to report.
+    let Ok(available) = registry.tags(model_ref).await else {
+        return Err(error);
+    };
+
+    match available
+        .iter()
+        .find(|tag| tag.eq_ignore_ascii_case(&model_ref.tag))
+    {
+        Some(tag) if *tag != model_ref.tag => {
+            model_ref.tag.clone_from(tag);
+            registry.manifest(model_ref).await
+        }
+        _ => Err(PacaError::TagNotFound {
+            model_ref: model_ref.to_string(),
+            suggestions: closest_tags(&model_ref.tag, &available),
+        }),
+    }
+}
+
+/// Whether a manifest failure means "no such tag" rather than a broken
+/// transfer. The registry answers an unknown tag with 400 or 404.
+fn is_unknown_tag(error: &PacaError) -> bool {
+    match error {
+        PacaError::NoFiles => true,
+        PacaError::ManifestFetch(e) => e.status().is_some_and(|status| {
+            status == reqwest::StatusCode::BAD_REQUEST || status == reqwest::StatusCode::NOT_FOUND
+        }),
+        _ => false,
+    }
+}
+
+/// Sizes a file named directly by the reference. There is no manifest to
+/// consult, so resolving it is the only source of its length.
+async fn fetch_single_file(
+    registry: &dyn Registry,
+    model_ref: &ModelRef,
+    filename: &str,
+) -> Result<GgufFile, PacaError> {
+    let size = registry
+        .resolve(model_ref, filename)
+        .await?
+        .size
+        .ok_or_else(|| PacaError::MissingFileSize(registry.file_url(model_ref, filename)))?;
+
+    Ok(GgufFile {
+        filename: filename.to_string(),
+        size,
     })
 }
 
-/// Downloads a GGUF model from HuggingFace into the HF Hub cache format.
+/// The tag the registry resolved [`DEFAULT_TAG`] to, read back from the
+/// file it picked using the same rules `list` applies to the cache.
+fn chosen_tag(manifest: &Manifest, model_ref: &ModelRef) -> Option<String> {
+    tag_for_path(manifest.primary.as_deref()?, &model_ref.model)
+}
+
+/// A file [`download_model`] placed in the cache.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct DownloadedFile {
+    /// The file's path in the snapshot.
+    pub path: PathBuf,
+    /// The endpoint or mirror its blob was fetched from; `None` when the
+    /// blob was already cached.
+    pub source: Option<String>,
+}
+
+/// Downloads a GGUF model from `registry` into the HF Hub cache format.
 ///
 /// `progress` must contain one reporter per file in `manifest`, in the
-/// same order as [`ModelManifest::files`].
+/// same order as [`ModelManifest::files`]; otherwise nothing is fetched
+/// and [`PacaError::ReporterCount`] is returned. [`start_download`]
+/// makes the reporters itself.
 pub async fn download_model(
+    registry: Arc<dyn Registry>,
     manifest: ModelManifest,
     hub_dir: Option<PathBuf>,
     progress: Vec<Arc<dyn FileProgress>>,
-) -> Result<Vec<PathBuf>, PacaError> {
-    let ModelManifest { files, model_ref } = manifest;
-    assert_eq!(
-        files.len(),
-        progress.len(),
-        "progress reporter count must match manifest file count"
-    );
-
-    let client = build_download_client(DEFAULT_CONNECT_TIMEOUT, DEFAULT_READ_TIMEOUT)?;
-    let hub = HubLayout::open(hub_dir)?;
-    let endpoint = model_endpoint();
-    let head_client = build_resolve_client()?;
+) -> Result<Vec<DownloadedFile>, PacaError> {
+    Paca::for_registry(registry, hub_dir)?
+        .download_manifest_with(manifest, progress)
+        .await
+}
+
+pub(crate) async fn install_model(
+    registry: Arc<dyn Registry>,
+    manifest: ModelManifest,
+    hub: &HubLayout,
+    progress: Vec<Arc<dyn FileProgress>>,
+    http: &HttpOptions,
+) -> Result<Vec<DownloadedFile>, PacaError> {
+    env::ensure_online()?;
+    let ModelManifest {
+        files,
+        model_ref,
+        tag_defaulted,
+        primary,
+        response,
+        llama_cache,
+    } = manifest;
+    let filenames: Vec<String> = files.iter().map(|f| f.filename.clone()).collect();
+    if files.len() != progress.len() {
+        return Err(PacaError::ReporterCount {
+            expected: files.len(),
+            actual: progress.len(),
+        });
+    }
 
+    let origin = registry.origin();
+    hub.model(&model_ref)?.check_origin(&origin)?;
     let blobs = hub.model(&model_ref)?.blobs();
     fs::create_dir_all(&blobs).map_err(PacaError::CacheDir)?;
 
     // Resolving every file up front is what makes the disk-space check
     // honest: only once the blob hashes are known can already-cached files
     // be excluded from the requirement.
-    let resolved = resolve_files(&head_client, endpoint, &model_ref, files, progress).await?;
-    check_disk_space(&blobs, bytes_to_download(&hub, &model_ref, &resolved)?)?;
+    let fetch = Fetch::new(http);
+    let resolved = resolve_files(&registry, &model_ref, files, progress, &fetch).await?;
+    check_disk_space(&blobs, bytes_to_download(hub, &model_ref, &resolved)?)?;
 
-    let mut set: tokio::task::JoinSet<Result<(PathBuf, String), PacaError>> =
+    let mut set: tokio::task::JoinSet<Result<(DownloadedFile, String), PacaError>> =
         tokio::task::JoinSet::new();
 
     for file in resolved {
-        let client = client.clone();
         let model_ref = model_ref.clone();
         let hub = hub.clone();
 
-        set.spawn(async move { install_file(&client, &hub, &model_ref, file).await });
+        set.spawn(async move { install_file(&hub, &model_ref, file).await });
     }
 
-    let mut paths = Vec::new();
+    let mut downloaded = Vec::new();
     let mut commit_hash = None;
 
     while let Some(result) = set.join_next().await {
-        let (path, hash) = result.expect("download task panicked")?;
-        paths.push(path);
+        let (file, hash) = result.expect("download task panicked")?;
+        downloaded.push(file);
         if commit_hash.is_none() {
             commit_hash = Some(hash);
         }
     }
 
     if let Some(commit) = &commit_hash {
-        hub.model(&model_ref)?.save_ref(commit)?;
+        let paths = hub.model(&model_ref)?;
+        paths.save_origin(&origin)?;
+        match &model_ref.revision {
+            None => paths.save_ref(commit)?,
+            // The hub cache keeps refs for branches and tags only; a
+            // commit hash already names its snapshot.
+            Some(revision) if !is_commit_hash(revision) => {
+                paths.save_named_ref(revision, commit)?;
+            }
+            Some(_) => {}
+        }
+
+        if let Some(llama_cache) = &llama_cache {
+            // llama.cpp names a file reference's files directly; only a
+            // tag goes through a manifest.
+            let (manifest, tags) = if model_ref.file.is_some() {
+                (None, Vec::new())
+            } else {
+                let manifest = response
+                    .unwrap_or_else(|| llama::synthesized_manifest(&filenames, primary.as_deref()));
+                let mut tags = vec![model_ref.tag.as_str()];
+                if tag_defaulted {
+                    tags.push(DEFAULT_TAG);
+                }
+                (Some(manifest), tags)
+            };
+            llama::write_cache(
+                llama_cache,
+                &model_ref,
+                &paths.snapshot(commit)?,
+                &filenames,
+                manifest.as_ref(),
+                &tags,
+            )?;
+        }
     }
 
-    Ok(paths)
+    Ok(downloaded)
+}
+
+pub(crate) fn is_commit_hash(revision: &str) -> bool {
+    revision.len() == 40 && revision.bytes().all(|b| b.is_ascii_hexdigit())
 }
 
 /// A manifest file with its registry-resolved blob and commit hashes.
-struct ResolvedFile {
-    gguf_file: GgufFile,
-    progress: Arc<dyn FileProgress>,
-    resolve_info: ResolveInfo,
-    url: String,
+pub(crate) struct ResolvedFile {
+    pub(crate) gguf_file: GgufFile,
+    pub(crate) progress: Arc<dyn FileProgress>,
+    /// The registry that resolved it, asked before falling back to a
+    /// mirror whether that mirror serves the same blob.
+    pub(crate) registry: Arc<dyn Registry>,
+    pub(crate) resolve_info: ResolveInfo,
+    /// Where the blob may be fetched from, in the order to try.
+    pub(crate) urls: Vec<String>,
+    pub(crate) fetch: Fetch,
+}
+
+/// How a blob's bytes are fetched.
+#[derive(Clone, Debug)]
+pub(crate) struct Fetch {
+    /// Range requests a large blob is split into. One fetches it in
+    /// order, which anything reading the growing `.partial` file needs.
+    pub(crate) connections: usize,
+    /// Shared by every file of a download, so the cap holds overall.
+    pub(crate) throttle: Option<Arc<Throttle>>,
+    pub(crate) clients: Arc<Clients>,
+}
+
+impl Fetch {
+    pub(crate) fn new(http: &HttpOptions) -> Self {
+        Self {
+            connections: http.concurrency,
+            throttle: http.throttle(),
+            clients: Arc::new(Clients::new(http)),
+        }
+    }
 }
 
-/// Issues the resolve HEAD request for every file concurrently. Results
-/// come back in completion order, which matches how `download_model`
-/// already collects its paths.
+/// Download clients by the headers they send: mirrors may each hold
+/// credentials of their own, and each connection pool is built once.
+#[derive(Debug)]
+pub(crate) struct Clients {
+    http: HttpOptions,
+    built: Mutex<Vec<(HeaderMap, Client)>>,
+}
+
+impl Clients {
+    pub(crate) fn new(http: &HttpOptions) -> Self {
+        Self {
+            http: http.clone(),
+            built: Mutex::default(),
+        }
+    }
+
+    /// The client to download `url` with, carrying what `registry` sends
+    /// there.
+    pub(crate) fn for_url(&self, registry: &dyn Registry, url: &str) -> Result<Client, PacaError> {
+        let headers = registry.headers_for(url)?;
+        let mut built = self.built.lock().expect("client cache poisoned");
+        if let Some((_, client)) = built.iter().find(|(sent, _)| *sent == headers) {
+            return Ok(client.clone());
+        }
+        let client = self.http.download_client(headers.clone())?;
+        built.push((headers, client.clone()));
+        Ok(client)
+    }
+}
+
+impl Default for Fetch {
+    fn default() -> Self {
+        Self::new(&HttpOptions::default())
+    }
+}
+
+/// Resolves every file concurrently. Results come back in completion
+/// order, which matches how `download_model` already collects its paths.
 async fn resolve_files(
-    head_client: &Client,
-    endpoint: &str,
+    registry: &Arc<dyn Registry>,
     model_ref: &ModelRef,
     files: Vec<GgufFile>,
     progress: Vec<Arc<dyn FileProgress>>,
+    fetch: &Fetch,
 ) -> Result<Vec<ResolvedFile>, PacaError> {
     let mut set: tokio::task::JoinSet<Result<ResolvedFile, PacaError>> =
         tokio::task::JoinSet::new();
 
     for (gguf_file, bar) in files.into_iter().zip(progress) {
-        let head_client = head_client.clone();
-        let url = format!(
-            "{endpoint}/{}/resolve/main/{}",
-            model_ref.repo(),
-            gguf_file.filename
-        );
+        let registry = Arc::clone(registry);
+        let model_ref = model_ref.clone();
+        let fetch = fetch.clone();
 
         set.spawn(async move {
-            let resolve_info = fetch_resolve_info(&head_client, &url).await?;
+            bar.event(&ProgressEvent::Resolving);
+            let resolve_info = registry.resolve(&model_ref, &gguf_file.filename).await?;
             Ok(ResolvedFile {
+                urls: registry.blob_urls(&model_ref, &gguf_file.filename, &resolve_info),
                 gguf_file,
                 progress: bar,
+                registry,
                 resolve_info,
-                url,
+                fetch,
             })
         });
     }
@@ -177,50 +447,107 @@ fn bytes_to_download(
 
 /// Puts one file into the cache: fetches its blob unless a complete copy
 /// is already on disk, then links it into the snapshot tree. Returns the
-/// symlink path and the commit it belongs to.
-async fn install_file(
-    client: &Client,
+/// file and the commit it belongs to.
+pub(crate) async fn install_file(
     hub: &HubLayout,
     model_ref: &ModelRef,
     file: ResolvedFile,
-) -> Result<(PathBuf, String), PacaError> {
+) -> Result<(DownloadedFile, String), PacaError> {
     let ResolvedFile {
         gguf_file,
         progress,
+        registry,
         resolve_info,
-        url,
+        urls,
+        fetch,
     } = file;
 
     let paths = hub.model(model_ref)?;
     let blob_path = paths.blob(&resolve_info.blob_hash)?;
 
-    if paths.blob_exists(&resolve_info.blob_hash) {
-        let existing_size = fs::metadata(&blob_path).map_or(0, |m| m.len());
-
-        if blob_is_complete(existing_size, gguf_file.size) {
-            progress.start(gguf_file.size);
-            progress.finish();
-        } else {
+    let cached = paths.blob_exists(&resolve_info.blob_hash);
+    let source = if cached
+        && blob_is_complete(
+            fs::metadata(&blob_path).map_or(0, |m| m.len()),
+            gguf_file.size,
+        ) {
+        progress.event(&ProgressEvent::Cached);
+        progress.start(gguf_file.size);
+        progress.finish();
+        None
+    } else {
+        if cached {
             // A final blob whose size doesn't match is evidence of a
             // legacy (pre-atomic-rename) download or external tampering.
             // Delete and redownload through the .partial + rename path.
             fs::remove_file(&blob_path).map_err(PacaError::FileDelete)?;
-            download_to_blob(client, &url, &blob_path, gguf_file.size, &progress).await?;
         }
-    } else {
-        download_to_blob(client, &url, &blob_path, gguf_file.size, &progress).await?;
-    }
+        let mirrors = Mirrors {
+            registry: registry.as_ref(),
+            resolved: &resolve_info,
+            urls: &urls,
+        };
+        let source =
+            download_from_any(mirrors, &blob_path, gguf_file.size, &fetch, &progress).await?;
+        if resolve_info.sha256.is_some() {
+            progress.event(&ProgressEvent::Verifying);
+        }
+        verify_sha256(&blob_path, resolve_info.sha256.as_deref()).await?;
+        Some(source)
+    };
 
-    let symlink_path = create_snapshot_symlink(
+    let path = create_snapshot_symlink(
         &paths,
         &resolve_info.commit_hash,
         &gguf_file.filename,
         &resolve_info.blob_hash,
     )?;
-    Ok((symlink_path, resolve_info.commit_hash))
+    Ok((DownloadedFile { path, source }, resolve_info.commit_hash))
+}
+
+/// Checks a freshly downloaded blob against the digest its registry
+/// promised, deleting it on mismatch so the next run starts clean rather
+/// than trusting a corrupt file of the right size.
+async fn verify_sha256(blob_path: &Path, expected: Option<&str>) -> Result<(), PacaError> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+
+    let actual = sha256_file_async(blob_path)
+        .await
+        .map_err(PacaError::Download)?;
+
+    if actual.eq_ignore_ascii_case(expected) {
+        return Ok(());
+    }
+
+    fs::remove_file(blob_path).map_err(PacaError::FileDelete)?;
+    Err(PacaError::DigestMismatch {
+        actual,
+        expected: expected.to_string(),
+        path: blob_path.display().to_string(),
+    })
 }
 
-fn create_snapshot_symlink(
+/// The lowercase hex SHA-256 of a file's content, which is also the name
+/// the hub gives an LFS blob.
+pub(crate) fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// [`sha256_file`] on the blocking pool, so hashing a multi-gigabyte blob
+/// doesn't stall the runtime's other downloads or requests.
+pub(crate) async fn sha256_file_async(path: &Path) -> std::io::Result<String> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || sha256_file(&path))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+pub(crate) fn create_snapshot_symlink(
     paths: &ModelPaths,
     commit_hash: &str,
     filename: &str,
@@ -262,13 +589,14 @@ async fn download_with_resume(
     url: &str,
     path: &Path,
     resume_from: u64,
+    throttle: Option<&Throttle>,
     progress: &Arc<dyn FileProgress>,
 ) -> Result<(), PacaError> {
     let mut retries: u32 = 0;
     let mut bytes_on_disk = resume_from;
 
     loop {
-        match attempt_download(client, url, path, bytes_on_disk, progress).await {
+        match attempt_download(client, url, path, bytes_on_disk, throttle, progress).await {
             Ok(()) => return Ok(()),
             Err(e) if is_retryable(&e) => {
                 let new_size = fs::metadata(path).map_or(bytes_on_disk, |m| m.len());
@@ -285,10 +613,7 @@ async fn download_with_resume(
                 }
 
                 let delay = retry_delay(&e, retries);
-                progress.println(&format!(
-                    "Download error: {e}. Retrying in {}s (attempt {retries}/{MAX_RETRIES})...",
-                    delay.as_secs()
-                ));
+                report_retry(progress, &e, retries, delay);
                 tokio::time::sleep(delay).await;
             }
             Err(e) => return Err(e),
@@ -296,6 +621,26 @@ async fn download_with_resume(
     }
 }
 
+/// Tells `progress` that the attempt that failed with `error` will be
+/// made again after `delay`.
+fn report_retry(
+    progress: &Arc<dyn FileProgress>,
+    error: &PacaError,
+    attempt: u32,
+    delay: Duration,
+) {
+    if let PacaError::RateLimited(_) = error {
+        progress.event(&ProgressEvent::RateLimited {
+            until: SystemTime::now() + delay,
+        });
+    }
+    progress.event(&ProgressEvent::Retrying {
+        attempt,
+        delay,
+        error: error.to_string(),
+    });
+}
+
 fn is_retryable(error: &PacaError) -> bool {
     match error {
         PacaError::Download(_) | PacaError::RangeNotHonored(_) | PacaError::RateLimited(_) => true,
@@ -321,6 +666,7 @@ async fn attempt_download(
     url: &str,
     path: &Path,
     resume_from: u64,
+    throttle: Option<&Throttle>,
     progress: &Arc<dyn FileProgress>,
 ) -> Result<(), PacaError> {
     let mut request = client.get(url);
@@ -361,6 +707,9 @@ async fn attempt_download(
     {
         file.write_all(&chunk).map_err(PacaError::FileWrite)?;
         progress.inc(chunk.len() as u64);
+        if let Some(throttle) = throttle {
+            throttle.consume(chunk.len() as u64).await;
+        }
     }
 
     file.flush().map_err(PacaError::FileWrite)?;
@@ -381,22 +730,23 @@ fn parse_retry_after(response: &reqwest::Response) -> u64 {
 /// Minimum file size to use parallel chunk downloads (100 MB)
 const PARALLEL_THRESHOLD: u64 = 100 * 1024 * 1024;
 
-/// Number of concurrent connections per file
-const CHUNK_COUNT: usize = 4;
-
-/// Each chunk gets its own `<final>.partial.<idx>` file so resume can
-/// trust file length — never preallocate, or the size will lie.
+/// Each chunk gets its own `<final>.partial.<idx>-of-<count>` file so
+/// resume can trust file length — never preallocate, or the size will
+/// lie. The count is in the name because it follows the configured
+/// concurrency: chunks laid out for another count cover other ranges.
 async fn download_blob_parallel(
     client: &Client,
     url: &str,
     final_path: &Path,
     total_size: u64,
+    fetch: &Fetch,
     progress: &Arc<dyn FileProgress>,
 ) -> Result<(), PacaError> {
-    let chunks = chunk_ranges(total_size, CHUNK_COUNT);
+    let chunks = chunk_ranges(total_size, fetch.connections);
     let chunk_paths: Vec<PathBuf> = (0..chunks.len())
-        .map(|i| chunk_partial_path(final_path, i))
+        .map(|i| chunk_partial_path(final_path, i, chunks.len()))
         .collect();
+    remove_chunk_partials(final_path, &chunk_paths)?;
 
     // Chunk files are the source of truth in parallel mode; a leftover
     // merged partial from a prior crashed concat would shadow them.
@@ -405,27 +755,46 @@ async fn download_blob_parallel(
         fs::remove_file(&merged).map_err(PacaError::FileDelete)?;
     }
 
-    progress.start(0);
+    let mut resumed = Vec::with_capacity(chunks.len());
+    for (path, &(start, end)) in chunk_paths.iter().zip(&chunks) {
+        let existing = fs::metadata(path).map_or(0, |m| m.len());
+        if existing > end - start + 1 {
+            fs::remove_file(path).map_err(PacaError::FileDelete)?;
+            resumed.push(0);
+        } else {
+            resumed.push(existing);
+        }
+    }
+    // Started at what is already on disk, as a sequential resume is, so
+    // reporters can tell reused bytes from fetched ones.
+    progress.start(resumed.iter().sum());
 
     let mut set = tokio::task::JoinSet::new();
     for (i, &(start, end)) in chunks.iter().enumerate() {
         let chunk_size = end - start + 1;
-        let path = chunk_paths[i].clone();
-        let existing = fs::metadata(&path).map_or(0, |m| m.len());
-        let resume_from = if existing > chunk_size {
-            fs::remove_file(&path).map_err(PacaError::FileDelete)?;
-            0
-        } else {
-            existing
-        };
-        progress.inc(resume_from);
-        if resume_from >= chunk_size {
+        if resumed[i] >= chunk_size {
             continue;
         }
+        progress.event(&ProgressEvent::ChunkStarted {
+            idx: i,
+            range: start..end + 1,
+        });
+        let path = chunk_paths[i].clone();
         let client = client.clone();
         let url = url.to_string();
         let bar = Arc::clone(progress);
-        set.spawn(async move { download_chunk(&client, &url, &path, start, end, &bar).await });
+        let throttle = fetch.throttle.clone();
+        set.spawn(async move {
+            download_chunk(
+                &client,
+                &url,
+                &path,
+                (start, end),
+                throttle.as_deref(),
+                &bar,
+            )
+            .await
+        });
     }
 
     while let Some(result) = set.join_next().await {
@@ -433,6 +802,7 @@ async fn download_blob_parallel(
     }
 
     verify_chunk_sizes(&chunk_paths, &chunks)?;
+    progress.event(&ProgressEvent::Finalizing);
     concatenate_chunks(&merged, &chunk_paths)?;
     verify_file_size(&merged, total_size)?;
     fs::rename(&merged, final_path).map_err(PacaError::FileWrite)?;
@@ -445,8 +815,8 @@ async fn download_chunk(
     client: &Client,
     url: &str,
     chunk_path: &Path,
-    abs_start: u64,
-    abs_end: u64,
+    (abs_start, abs_end): (u64, u64),
+    throttle: Option<&Throttle>,
     progress: &Arc<dyn FileProgress>,
 ) -> Result<(), PacaError> {
     let chunk_size = abs_end - abs_start + 1;
@@ -458,8 +828,15 @@ async fn download_chunk(
             return Ok(());
         }
         let current_start = abs_start + last_size;
-        let result =
-            attempt_chunk_download(client, url, chunk_path, current_start, abs_end, progress).await;
+        let result = attempt_chunk_download(
+            client,
+            url,
+            chunk_path,
+            (current_start, abs_end),
+            throttle,
+            progress,
+        )
+        .await;
         let new_size = fs::metadata(chunk_path).map_or(last_size, |m| m.len());
 
         match result {
@@ -478,10 +855,11 @@ async fn download_chunk(
                     )));
                 }
                 let delay = Duration::from_secs(1u64 << retries.min(30));
-                progress.println(&format!(
-                    "Chunk ended early ({new_size}/{chunk_size} bytes). Retrying in {}s (attempt {retries}/{MAX_RETRIES})...",
-                    delay.as_secs()
-                ));
+                progress.event(&ProgressEvent::Retrying {
+                    attempt: retries,
+                    delay,
+                    error: format!("chunk ended early ({new_size}/{chunk_size} bytes)"),
+                });
                 tokio::time::sleep(delay).await;
             }
             Err(e) if is_retryable(&e) => {
@@ -494,10 +872,7 @@ async fn download_chunk(
                     return Err(e);
                 }
                 let delay = retry_delay(&e, retries);
-                progress.println(&format!(
-                    "Chunk download error: {e}. Retrying in {}s (attempt {retries}/{MAX_RETRIES})...",
-                    delay.as_secs()
-                ));
+                report_retry(progress, &e, retries, delay);
                 tokio::time::sleep(delay).await;
             }
             Err(e) => return Err(e),
@@ -511,8 +886,8 @@ async fn attempt_chunk_download(
     client: &Client,
     url: &str,
     chunk_path: &Path,
-    start: u64,
-    end: u64,
+    (start, end): (u64, u64),
+    throttle: Option<&Throttle>,
     progress: &Arc<dyn FileProgress>,
 ) -> Result<(), PacaError> {
     let response = client
@@ -547,6 +922,9 @@ async fn attempt_chunk_download(
     {
         file.write_all(&chunk).map_err(PacaError::FileWrite)?;
         progress.inc(chunk.len() as u64);
+        if let Some(throttle) = throttle {
+            throttle.consume(chunk.len() as u64).await;
+        }
     }
 
     file.flush().map_err(PacaError::FileWrite)?;
@@ -612,41 +990,120 @@ fn chunk_ranges(total_size: u64, count: usize) -> Vec<(u64, u64)> {
         .collect()
 }
 
-/// Connection establishment timeout for chunk downloads.
-const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
-
-/// Per-read timeout — fires if the server goes silent mid-response,
-/// which is the "hang" mode we've seen in the wild.
-const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(60);
-
-fn build_download_client(
-    connect_timeout: Duration,
-    read_timeout: Duration,
-) -> Result<Client, PacaError> {
-    Ok(Client::builder()
-        .connect_timeout(connect_timeout)
-        .default_headers(default_headers()?)
-        .read_timeout(read_timeout)
-        .tcp_keepalive(Duration::from_secs(15))
-        .build()?)
-}
-
 fn blob_is_complete(existing_size: u64, expected_size: u64) -> bool {
     existing_size == expected_size
 }
 
-fn partial_path(final_path: &Path) -> PathBuf {
+pub(crate) fn partial_path(final_path: &Path) -> PathBuf {
     let mut name = final_path.file_name().unwrap_or_default().to_os_string();
     name.push(".partial");
     final_path.with_file_name(name)
 }
 
-fn chunk_partial_path(final_path: &Path, idx: usize) -> PathBuf {
+fn chunk_partial_path(final_path: &Path, idx: usize, count: usize) -> PathBuf {
     let mut name = final_path.file_name().unwrap_or_default().to_os_string();
-    name.push(format!(".partial.{idx}"));
+    name.push(format!(".partial.{idx}-of-{count}"));
     final_path.with_file_name(name)
 }
 
+/// Whether `suffix`, what follows `.partial.` in a blob's name, marks a
+/// chunk partial: `<idx>-of-<count>`, or a bare `<idx>` from before the
+/// count was recorded.
+pub(crate) fn is_chunk_suffix(suffix: &str) -> bool {
+    let digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    match suffix.split_once("-of-") {
+        Some((idx, count)) => digits(idx) && digits(count),
+        None => digits(suffix),
+    }
+}
+
+/// Deletes the chunk partials of `final_path` other than `keep`: those of
+/// a run with another connection count, whose bytes sit at other offsets,
+/// or of a parallel run before a sequential one.
+fn remove_chunk_partials(final_path: &Path, keep: &[PathBuf]) -> Result<(), PacaError> {
+    let Some(dir) = final_path.parent() else {
+        return Ok(());
+    };
+    let mut prefix = final_path.file_name().unwrap_or_default().to_os_string();
+    prefix.push(".partial.");
+    let prefix = prefix.to_string_lossy().into_owned();
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    for entry in entries {
+        let path = entry.map_err(PacaError::CacheDir)?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let orphaned =
+            name.strip_prefix(&prefix).is_some_and(is_chunk_suffix) && !keep.contains(&path);
+        if orphaned {
+            fs::remove_file(&path).map_err(PacaError::FileDelete)?;
+        }
+    }
+    Ok(())
+}
+
+/// The URLs a blob may be fetched from, and what to check a fallback
+/// against before using it.
+struct Mirrors<'a> {
+    registry: &'a dyn Registry,
+    resolved: &'a ResolveInfo,
+    urls: &'a [String],
+}
+
+/// Downloads the blob from the first of the mirrors that serves it,
+/// returning that mirror's endpoint. A mirror that is unreachable, lacks
+/// the file or keeps failing hands over to the next, which resumes from
+/// the partial files it left once the registry confirms it serves the
+/// same blob.
+async fn download_from_any(
+    mirrors: Mirrors<'_>,
+    final_path: &Path,
+    total_size: u64,
+    fetch: &Fetch,
+    progress: &Arc<dyn FileProgress>,
+) -> Result<String, PacaError> {
+    let registry = mirrors.registry;
+    let mut last_error = None;
+    for (i, url) in mirrors.urls.iter().enumerate() {
+        let next = mirrors.urls.get(i + 1);
+        let fall_back = |reason: String| {
+            if let Some(next) = next {
+                progress.event(&ProgressEvent::MirrorFallback {
+                    from: registry.endpoint_of(url),
+                    to: registry.endpoint_of(next),
+                    reason,
+                });
+            }
+        };
+        if i > 0 {
+            match registry.serves_blob(url, mirrors.resolved).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    fall_back("serves another version".to_string());
+                    continue;
+                }
+                Err(e) if next.is_some() && (is_unavailable(&e) || is_retryable(&e)) => {
+                    fall_back(e.to_string());
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let client = fetch.clients.for_url(registry, url)?;
+        match download_to_blob(&client, url, final_path, total_size, fetch, progress).await {
+            Ok(()) => return Ok(registry.endpoint_of(url)),
+            Err(e) if next.is_some() && (is_unavailable(&e) || is_retryable(&e)) => {
+                fall_back(e.to_string());
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    // The first mirror is never skipped, so something was tried.
+    Err(last_error.expect("the first mirror is always tried"))
+}
+
 /// Writes `total_size` bytes from `url` to `final_path`. Crash-safe: an
 /// interrupted run leaves resumable `.partial*` files, never a
 /// misleadingly-sized final blob.
@@ -655,12 +1112,14 @@ async fn download_to_blob(
     url: &str,
     final_path: &Path,
     total_size: u64,
+    fetch: &Fetch,
     progress: &Arc<dyn FileProgress>,
 ) -> Result<(), PacaError> {
-    if total_size >= PARALLEL_THRESHOLD {
-        download_blob_parallel(client, url, final_path, total_size, progress).await
+    if fetch.connections > 1 && total_size >= PARALLEL_THRESHOLD {
+        download_blob_parallel(client, url, final_path, total_size, fetch, progress).await
     } else {
-        download_blob_sequential(client, url, final_path, total_size, progress).await
+        let throttle = fetch.throttle.as_deref();
+        download_blob_sequential(client, url, final_path, total_size, throttle, progress).await
     }
 }
 
@@ -669,9 +1128,11 @@ async fn download_blob_sequential(
     url: &str,
     final_path: &Path,
     total_size: u64,
+    throttle: Option<&Throttle>,
     progress: &Arc<dyn FileProgress>,
 ) -> Result<(), PacaError> {
     let partial = partial_path(final_path);
+    remove_chunk_partials(final_path, &[])?;
 
     let existing = fs::metadata(&partial).map_or(0, |m| m.len());
     let resume_from = if existing > total_size {
@@ -681,7 +1142,8 @@ async fn download_blob_sequential(
         existing
     };
 
-    download_with_resume(client, url, &partial, resume_from, progress).await?;
+    download_with_resume(client, url, &partial, resume_from, throttle, progress).await?;
+    progress.event(&ProgressEvent::Finalizing);
     verify_file_size(&partial, total_size)?;
     fs::rename(&partial, final_path).map_err(PacaError::FileWrite)?;
     Ok(())
@@ -698,6 +1160,9 @@ fn verify_file_size(path: &Path, expected: u64) -> Result<(), PacaError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http::DEFAULT_CONCURRENCY;
+    use crate::registry::{HttpDirectory, HuggingFace};
+    use reqwest::header::HeaderMap;
     use wiremock::matchers::method;
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
@@ -714,6 +1179,26 @@ mod tests {
         Arc::new(NoopProgress)
     }
 
+    /// Keeps the events a download reports.
+    #[derive(Default)]
+    struct EventLog(std::sync::Mutex<Vec<ProgressEvent>>);
+
+    impl FileProgress for EventLog {
+        fn start(&self, _: u64) {}
+        fn inc(&self, _: u64) {}
+        fn println(&self, _: &str) {}
+        fn finish(&self) {}
+
+        fn event(&self, event: &ProgressEvent) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    /// For tests that must fail before any request is made.
+    fn unused_registry() -> HuggingFace {
+        HuggingFace::new("http://unused.invalid").unwrap()
+    }
+
     #[tokio::test]
     async fn attempt_chunk_download_errs_when_server_returns_200_to_range_request() {
         let server = MockServer::start().await;
@@ -728,7 +1213,8 @@ mod tests {
         let path = dir.path().join("blob");
         let progress = noop_progress();
 
-        let result = attempt_chunk_download(&client, &server.uri(), &path, 0, 31, &progress).await;
+        let result =
+            attempt_chunk_download(&client, &server.uri(), &path, (0, 31), None, &progress).await;
 
         assert!(
             matches!(result, Err(PacaError::RangeNotHonored(200))),
@@ -750,7 +1236,7 @@ mod tests {
         let path = dir.path().join("blob.partial.0");
         let progress = noop_progress();
 
-        attempt_chunk_download(&client, &server.uri(), &path, 0, 31, &progress)
+        attempt_chunk_download(&client, &server.uri(), &path, (0, 31), None, &progress)
             .await
             .unwrap();
 
@@ -772,7 +1258,7 @@ mod tests {
         fs::write(&path, vec![1u8; 16]).unwrap();
         let progress = noop_progress();
 
-        attempt_chunk_download(&client, &server.uri(), &path, 16, 31, &progress)
+        attempt_chunk_download(&client, &server.uri(), &path, (16, 31), None, &progress)
             .await
             .unwrap();
 
@@ -788,14 +1274,44 @@ mod tests {
                 size,
             },
             progress: noop_progress(),
+            registry: Arc::new(HttpDirectory::new("http://example.test").unwrap()),
             resolve_info: ResolveInfo {
                 blob_hash: blob_hash.to_string(),
                 commit_hash: "commit1".to_string(),
+                endpoint: None,
+                sha256: None,
+                size: Some(size),
             },
-            url: format!("http://example.test/{filename}"),
+            urls: vec![format!("http://example.test/{filename}")],
+            fetch: Fetch::default(),
         }
     }
 
+    #[tokio::test]
+    async fn verify_sha256_accepts_a_matching_blob() {
+        let dir = tempfile::tempdir().unwrap();
+        let blob = dir.path().join("blob");
+        fs::write(&blob, b"gguf").unwrap();
+
+        let digest = format!("{:x}", Sha256::digest(b"gguf"));
+        verify_sha256(&blob, Some(&digest)).await.unwrap();
+        verify_sha256(&blob, None).await.unwrap();
+        assert!(blob.exists());
+    }
+
+    #[tokio::test]
+    async fn verify_sha256_deletes_a_corrupt_blob() {
+        let dir = tempfile::tempdir().unwrap();
+        let blob = dir.path().join("blob");
+        fs::write(&blob, b"gguf").unwrap();
+
+        let digest = format!("{:x}", Sha256::digest(b"other"));
+        let result = verify_sha256(&blob, Some(&digest)).await;
+
+        assert!(matches!(result, Err(PacaError::DigestMismatch { .. })));
+        assert!(!blob.exists());
+    }
+
     #[test]
     fn bytes_to_download_excludes_complete_blobs() {
         let dir = tempfile::tempdir().unwrap();
@@ -843,9 +1359,16 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("blob.partial");
 
-        attempt_download(&Client::new(), &server.uri(), &path, 0, &noop_progress())
-            .await
-            .unwrap_err()
+        attempt_download(
+            &Client::new(),
+            &server.uri(),
+            &path,
+            0,
+            None,
+            &noop_progress(),
+        )
+        .await
+        .unwrap_err()
     }
 
     #[tokio::test]
@@ -906,6 +1429,7 @@ mod tests {
             &server.uri(),
             &final_path,
             body.len() as u64,
+            &Fetch::default(),
             &progress,
         )
         .await
@@ -939,6 +1463,7 @@ mod tests {
             &server.uri(),
             &final_path,
             body.len() as u64,
+            &Fetch::default(),
             &progress,
         )
         .await
@@ -948,6 +1473,174 @@ mod tests {
         assert!(!partial_path(&final_path).exists());
     }
 
+    /// What the blobs in the `download_from_any` tests resolve to.
+    fn resolved_abc123() -> ResolveInfo {
+        ResolveInfo {
+            blob_hash: "abc123".to_string(),
+            commit_hash: "commit1".to_string(),
+            endpoint: None,
+            sha256: None,
+            size: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn download_from_any_skips_a_mirror_serving_another_version() {
+        use wiremock::matchers::path;
+
+        let file = "/owner/model-GGUF/resolve/commit1/model-Q4.gguf";
+        let missing = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&missing)
+            .await;
+        let stale = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path(file))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-repo-commit", "commit1")
+                    .insert_header("etag", "\"other\""),
+            )
+            .mount(&stale)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(206).set_body_bytes(b"WORLD".to_vec()))
+            .expect(0)
+            .mount(&stale)
+            .await;
+        let mirror = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path(file))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-repo-commit", "commit1")
+                    .insert_header("etag", "\"abc123\""),
+            )
+            .mount(&mirror)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(file))
+            .and(wiremock::matchers::header("Range", "bytes=6-"))
+            .respond_with(ResponseTemplate::new(206).set_body_bytes(b"world".to_vec()))
+            .mount(&mirror)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let final_path = dir.path().join("abc123");
+        fs::write(partial_path(&final_path), b"hello ").unwrap();
+        let registry = HuggingFace::new(missing.uri())
+            .unwrap()
+            .with_fallbacks([stale.uri(), mirror.uri()])
+            .unwrap();
+        let urls =
+            [missing.uri(), stale.uri(), mirror.uri()].map(|endpoint| format!("{endpoint}{file}"));
+        let mirrors = Mirrors {
+            registry: &registry,
+            resolved: &resolved_abc123(),
+            urls: &urls,
+        };
+        let events = Arc::new(EventLog::default());
+        let progress: Arc<dyn FileProgress> = events.clone();
+
+        let source = download_from_any(mirrors, &final_path, 11, &Fetch::default(), &progress)
+            .await
+            .unwrap();
+
+        assert_eq!(source, mirror.uri());
+        assert_eq!(fs::read(&final_path).unwrap(), b"hello world");
+        let fallbacks: Vec<(String, String)> = events
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                ProgressEvent::MirrorFallback { from, to, .. } => Some((from.clone(), to.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            fallbacks,
+            [(missing.uri(), stale.uri()), (stale.uri(), mirror.uri())]
+        );
+    }
+
+    #[tokio::test]
+    async fn download_from_any_resumes_on_the_next_mirror() {
+        let missing = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&missing)
+            .await;
+        let mirror = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(wiremock::matchers::header("Range", "bytes=6-"))
+            .respond_with(ResponseTemplate::new(206).set_body_bytes(b"world".to_vec()))
+            .mount(&mirror)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let final_path = dir.path().join("abc123");
+        fs::write(partial_path(&final_path), b"hello ").unwrap();
+        let urls = [missing.uri(), mirror.uri()];
+        // Plain HTTP directories have no mirrors to check.
+        let registry = HttpDirectory::new("http://example.test").unwrap();
+        let mirrors = Mirrors {
+            registry: &registry,
+            resolved: &resolved_abc123(),
+            urls: &urls,
+        };
+
+        let source = download_from_any(
+            mirrors,
+            &final_path,
+            11,
+            &Fetch::default(),
+            &noop_progress(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(source, mirror.uri());
+        assert_eq!(fs::read(&final_path).unwrap(), b"hello world");
+    }
+
+    #[tokio::test]
+    async fn download_from_any_stops_at_an_error_another_mirror_would_repeat() {
+        let forbidden = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&forbidden)
+            .await;
+        let mirror = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"gguf".to_vec()))
+            .expect(0)
+            .mount(&mirror)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let urls = [forbidden.uri(), mirror.uri()];
+        let registry = HttpDirectory::new("http://example.test").unwrap();
+        let mirrors = Mirrors {
+            registry: &registry,
+            resolved: &resolved_abc123(),
+            urls: &urls,
+        };
+        let result = download_from_any(
+            mirrors,
+            &dir.path().join("abc123"),
+            4,
+            &Fetch::default(),
+            &noop_progress(),
+        )
+        .await;
+
+        assert!(
+            matches!(result, Err(PacaError::Http(e)) if e.status() == Some(reqwest::StatusCode::FORBIDDEN))
+        );
+    }
+
     #[tokio::test]
     async fn attempt_chunk_download_times_out_when_server_stalls() {
         let server = MockServer::start().await;
@@ -960,14 +1653,19 @@ mod tests {
             .mount(&server)
             .await;
 
-        let client =
-            build_download_client(Duration::from_secs(1), Duration::from_millis(200)).unwrap();
+        let options = HttpOptions {
+            connect_timeout: Duration::from_secs(1),
+            read_timeout: Duration::from_millis(200),
+            ..HttpOptions::default()
+        };
+        let client = options.download_client(HeaderMap::new()).unwrap();
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("blob.partial.0");
         let progress = noop_progress();
 
         let started = std::time::Instant::now();
-        let result = attempt_chunk_download(&client, &server.uri(), &path, 0, 31, &progress).await;
+        let result =
+            attempt_chunk_download(&client, &server.uri(), &path, (0, 31), None, &progress).await;
         let elapsed = started.elapsed();
 
         assert!(result.is_err(), "expected error, got {result:?}");
@@ -1082,15 +1780,445 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn fetch_manifest_returns_error_for_missing_tag() {
-        let result = fetch_manifest("owner/model").await;
-        assert!(result.is_err());
+    async fn fetch_manifest_refuses_to_reach_the_network_when_offline() {
+        let result = temp_env::async_with_vars(
+            [("HF_HUB_OFFLINE", Some("1"))],
+            fetch_manifest(&unused_registry(), "owner/model-GGUF:Q4"),
+        )
+        .await;
+        assert!(matches!(result, Err(PacaError::Offline)));
     }
 
-    #[test]
-    fn create_snapshot_symlink_creates_symlink_for_root_file() {
-        let dir = tempfile::tempdir().unwrap();
-        let hub = HubLayout::open(Some(dir.path().to_path_buf())).unwrap();
+    #[tokio::test]
+    async fn fetch_manifest_returns_error_for_missing_owner() {
+        let result = fetch_manifest(&unused_registry(), "model:tag").await;
+        assert!(matches!(result, Err(PacaError::ModelRef(_))));
+    }
+
+    /// A plain HTTP directory serving one four-byte Q4_K_M file.
+    async fn http_dir_stand_in() -> MockServer {
+        use wiremock::matchers::path;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/owner/model-GGUF/"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"<a href="model-Q4_K_M.gguf">model-Q4_K_M.gguf</a>"#),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/owner/model-GGUF/model-Q4_K_M.gguf"))
+            .respond_with(ResponseTemplate::new(200).insert_header("content-length", "4"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/owner/model-GGUF/model-Q4_K_M.gguf"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"gguf".to_vec()))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn download_model_installs_files_from_another_registry() {
+        use crate::registry::HttpDirectory;
+
+        let server = http_dir_stand_in().await;
+        let dir = tempfile::tempdir().unwrap();
+        let registry: Arc<dyn Registry> = Arc::new(HttpDirectory::new(server.uri()).unwrap());
+
+        let files = temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            let manifest = fetch_manifest(registry.as_ref(), "owner/model-GGUF")
+                .await
+                .unwrap();
+            assert_eq!(manifest.model_ref().tag, "Q4_K_M");
+            download_model(
+                Arc::clone(&registry),
+                manifest,
+                Some(dir.path().to_path_buf()),
+                vec![noop_progress()],
+            )
+            .await
+            .unwrap()
+        })
+        .await;
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(fs::read(&files[0].path).unwrap(), b"gguf");
+        let model_dir = dir.path().join("models--owner--model-GGUF");
+        assert!(model_dir.join("refs/main").is_file());
+        assert_eq!(
+            fs::read_to_string(model_dir.join("origin")).unwrap(),
+            format!("http {}\n", server.uri())
+        );
+    }
+
+    #[tokio::test]
+    async fn download_model_refuses_a_repo_cached_from_another_registry() {
+        use crate::registry::HttpDirectory;
+
+        let server = http_dir_stand_in().await;
+        let dir = tempfile::tempdir().unwrap();
+        let model_dir = dir.path().join("models--owner--model-GGUF");
+        fs::create_dir_all(&model_dir).unwrap();
+        fs::write(model_dir.join("origin"), "huggingface\n").unwrap();
+        let registry: Arc<dyn Registry> = Arc::new(HttpDirectory::new(server.uri()).unwrap());
+
+        let result = temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            let manifest = fetch_manifest(registry.as_ref(), "owner/model-GGUF")
+                .await
+                .unwrap();
+            download_model(
+                Arc::clone(&registry),
+                manifest,
+                Some(dir.path().to_path_buf()),
+                vec![noop_progress()],
+            )
+            .await
+        })
+        .await;
+
+        assert!(matches!(result, Err(PacaError::OriginConflict { .. })));
+        assert!(!model_dir.join("blobs").exists());
+    }
+
+    #[tokio::test]
+    async fn download_model_rejects_a_reporter_count_mismatch() {
+        use crate::registry::HttpDirectory;
+
+        let server = http_dir_stand_in().await;
+        let dir = tempfile::tempdir().unwrap();
+        let registry: Arc<dyn Registry> = Arc::new(HttpDirectory::new(server.uri()).unwrap());
+
+        let result = temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            let manifest = fetch_manifest(registry.as_ref(), "owner/model-GGUF")
+                .await
+                .unwrap();
+            download_model(
+                Arc::clone(&registry),
+                manifest,
+                Some(dir.path().to_path_buf()),
+                vec![noop_progress(), noop_progress()],
+            )
+            .await
+        })
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(PacaError::ReporterCount {
+                expected: 1,
+                actual: 2
+            })
+        ));
+        assert!(!dir.path().join("models--owner--model-GGUF").exists());
+    }
+
+    #[tokio::test]
+    async fn download_model_links_the_files_into_the_llama_cache() {
+        use crate::registry::HttpDirectory;
+
+        let server = http_dir_stand_in().await;
+        let dir = tempfile::tempdir().unwrap();
+        let llama_cache = dir.path().join("llama.cpp");
+        let registry: Arc<dyn Registry> = Arc::new(HttpDirectory::new(server.uri()).unwrap());
+
+        temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            let manifest = fetch_manifest(registry.as_ref(), "owner/model-GGUF")
+                .await
+                .unwrap()
+                .with_llama_cache(llama_cache.clone());
+            download_model(
+                Arc::clone(&registry),
+                manifest,
+                Some(dir.path().join("hub")),
+                vec![noop_progress()],
+            )
+            .await
+            .unwrap()
+        })
+        .await;
+
+        assert_eq!(
+            fs::read(llama_cache.join("owner_model-GGUF_model-Q4_K_M.gguf")).unwrap(),
+            b"gguf"
+        );
+        // Asked for without a tag, so llama.cpp's `latest` resolves too.
+        for tag in ["Q4_K_M", "latest"] {
+            let manifest: serde_json::Value = serde_json::from_slice(
+                &fs::read(llama_cache.join(format!("manifest=owner_model-GGUF={tag}.json")))
+                    .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(manifest["ggufFile"]["rfilename"], "model-Q4_K_M.gguf");
+        }
+    }
+
+    #[tokio::test]
+    async fn download_model_installs_and_verifies_oci_layers() {
+        use crate::cache::list_models;
+        use crate::registry::Oci;
+        use wiremock::matchers::path;
+
+        let body = b"GGUF weights".to_vec();
+        let digest = format!("sha256:{:x}", Sha256::digest(&body));
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/library/tiny/manifests/1b"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                r#"{{"schemaVersion":2,"layers":[
+                    {{"mediaType":"application/vnd.ollama.image.model","digest":"{digest}","size":{}}},
+                    {{"mediaType":"application/vnd.ollama.image.license","digest":"{digest}","size":1}}
+                ]}}"#,
+                body.len()
+            )))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/library/tiny/blobs/{digest}")))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body.clone()))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let registry: Arc<dyn Registry> = Arc::new(Oci::new(server.uri()).unwrap());
+
+        let files = temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            let manifest = fetch_manifest(registry.as_ref(), "library/tiny:1b")
+                .await
+                .unwrap();
+            download_model(
+                Arc::clone(&registry),
+                manifest,
+                Some(dir.path().to_path_buf()),
+                vec![noop_progress()],
+            )
+            .await
+            .unwrap()
+        })
+        .await;
+
+        assert_eq!(files.len(), 1);
+        assert!(files[0].path.ends_with("1b/tiny-1b.gguf"));
+        assert_eq!(fs::read(&files[0].path).unwrap(), body);
+
+        let listed = list_models(Some(dir.path().to_path_buf())).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].model_ref.to_string(), "library/tiny:1b");
+    }
+
+    #[tokio::test]
+    async fn download_model_rejects_an_oci_layer_with_the_wrong_digest() {
+        use crate::registry::Oci;
+        use wiremock::matchers::path;
+
+        let digest = format!("sha256:{:x}", Sha256::digest(b"expected"));
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/library/tiny/manifests/1b"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                r#"{{"layers":[{{"mediaType":"application/vnd.ollama.image.model","digest":"{digest}","size":8}}]}}"#
+            )))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/library/tiny/blobs/{digest}")))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"tampered".to_vec()))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let registry: Arc<dyn Registry> = Arc::new(Oci::new(server.uri()).unwrap());
+
+        let result = temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            let manifest = fetch_manifest(registry.as_ref(), "library/tiny:1b")
+                .await
+                .unwrap();
+            download_model(
+                Arc::clone(&registry),
+                manifest,
+                Some(dir.path().to_path_buf()),
+                vec![noop_progress()],
+            )
+            .await
+        })
+        .await;
+
+        assert!(matches!(result, Err(PacaError::DigestMismatch { .. })));
+        let blobs = dir.path().join("models--library--tiny/blobs");
+        assert_eq!(fs::read_dir(blobs).unwrap().count(), 0);
+    }
+
+    const TAG_TREE: &str = r#"[
+        {"type":"file","path":"model-Q4_K_M.gguf","size":10},
+        {"type":"file","path":"model-Q4_K_S.gguf","size":10},
+        {"type":"file","path":"model-Q8_0.gguf","size":10}
+    ]"#;
+
+    #[tokio::test]
+    async fn fetch_tag_manifest_retries_a_miscased_tag_under_its_canonical_spelling() {
+        use wiremock::matchers::path;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/owner/model-GGUF/manifests/Q4_K_M"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"ggufFile":{"rfilename":"model-Q4_K_M.gguf","size":10}}"#),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v2/owner/model-GGUF/manifests/q4_k_m"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/models/owner/model-GGUF/tree/main"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(TAG_TREE))
+            .mount(&server)
+            .await;
+
+        let mut mr: ModelRef = "owner/model-GGUF:q4_k_m".parse().unwrap();
+        let manifest = fetch_tag_manifest(&HuggingFace::new(server.uri()).unwrap(), &mut mr)
+            .await
+            .unwrap();
+
+        assert_eq!(mr.tag, "Q4_K_M");
+        assert_eq!(manifest.gguf_files[0].filename, "model-Q4_K_M.gguf");
+    }
+
+    #[tokio::test]
+    async fn fetch_tag_manifest_suggests_the_closest_remote_tags() {
+        use wiremock::matchers::path;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/owner/model-GGUF/manifests/Q4_KM"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/models/owner/model-GGUF/tree/main"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(TAG_TREE))
+            .mount(&server)
+            .await;
+
+        let mut mr: ModelRef = "owner/model-GGUF:Q4_KM".parse().unwrap();
+        let result = fetch_tag_manifest(&HuggingFace::new(server.uri()).unwrap(), &mut mr).await;
+
+        match result {
+            Err(PacaError::TagNotFound { suggestions, .. }) => {
+                assert_eq!(suggestions, vec!["Q4_K_M", "Q4_K_S", "Q8_0"]);
+            }
+            other => panic!("expected TagNotFound, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_tag_manifest_keeps_the_manifest_error_for_a_missing_repo() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let mut mr: ModelRef = "owner/missing-GGUF:Q4".parse().unwrap();
+        let result = fetch_tag_manifest(&HuggingFace::new(server.uri()).unwrap(), &mut mr).await;
+
+        assert!(matches!(result, Err(PacaError::ManifestFetch(_))));
+    }
+
+    #[tokio::test]
+    async fn fetch_single_file_sizes_the_named_file_at_its_revision() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(wiremock::matchers::path(
+                "/owner/model-GGUF/resolve/v2/BF16/model-BF16-00001-of-00002.gguf",
+            ))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("x-repo-commit", "commit1")
+                    .insert_header("x-linked-etag", "\"hash1\"")
+                    .insert_header("x-linked-size", "2048"),
+            )
+            .mount(&server)
+            .await;
+
+        let mr: ModelRef = "owner/model-GGUF@v2/BF16/model-BF16-00001-of-00002.gguf"
+            .parse()
+            .unwrap();
+        let file = fetch_single_file(
+            &HuggingFace::new(server.uri()).unwrap(),
+            &mr,
+            mr.file.as_deref().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            file,
+            GgufFile {
+                filename: "BF16/model-BF16-00001-of-00002.gguf".to_string(),
+                size: 2048,
+            }
+        );
+    }
+
+    #[test]
+    fn is_commit_hash_recognises_full_shas_only() {
+        assert!(is_commit_hash("0123456789abcdef0123456789abcdef01234567"));
+        assert!(!is_commit_hash("v1.0"));
+        assert!(!is_commit_hash("0123456"));
+    }
+
+    fn manifest_with_primary(primary: &str) -> Manifest {
+        Manifest {
+            gguf_files: Vec::new(),
+            primary: Some(primary.to_string()),
+            response: None,
+        }
+    }
+
+    #[test]
+    fn chosen_tag_reads_the_tag_from_a_root_file() {
+        let mr: ModelRef = "owner/Qwen3-1.7B-GGUF:latest".parse().unwrap();
+        assert_eq!(
+            chosen_tag(&manifest_with_primary("Qwen3-1.7B-Q4_K_M.gguf"), &mr).as_deref(),
+            Some("Q4_K_M")
+        );
+    }
+
+    #[test]
+    fn chosen_tag_reads_the_tag_from_a_shard_directory() {
+        let mr: ModelRef = "owner/Model-GGUF:latest".parse().unwrap();
+        assert_eq!(
+            chosen_tag(
+                &manifest_with_primary("Q8_0/Model-Q8_0-00001-of-00003.gguf"),
+                &mr
+            )
+            .as_deref(),
+            Some("Q8_0")
+        );
+    }
+
+    #[test]
+    fn chosen_tag_is_none_without_a_primary_file() {
+        let mr: ModelRef = "owner/Model-GGUF:latest".parse().unwrap();
+        let manifest = Manifest {
+            gguf_files: Vec::new(),
+            primary: None,
+            response: None,
+        };
+        assert_eq!(chosen_tag(&manifest, &mr), None);
+    }
+
+    #[test]
+    fn create_snapshot_symlink_creates_symlink_for_root_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let hub = HubLayout::open(Some(dir.path().to_path_buf())).unwrap();
         let mr: ModelRef = "owner/model-GGUF:Q4".parse().unwrap();
         let paths = hub.model(&mr).unwrap();
         fs::create_dir_all(paths.blobs()).unwrap();
@@ -1165,12 +2293,12 @@ mod tests {
     fn chunk_partial_path_appends_index_suffix() {
         let blob = PathBuf::from("/tmp/blobs/abc123");
         assert_eq!(
-            chunk_partial_path(&blob, 0),
-            PathBuf::from("/tmp/blobs/abc123.partial.0")
+            chunk_partial_path(&blob, 0, 8),
+            PathBuf::from("/tmp/blobs/abc123.partial.0-of-8")
         );
         assert_eq!(
-            chunk_partial_path(&blob, 7),
-            PathBuf::from("/tmp/blobs/abc123.partial.7")
+            chunk_partial_path(&blob, 7, 8),
+            PathBuf::from("/tmp/blobs/abc123.partial.7-of-8")
         );
     }
 
@@ -1178,8 +2306,8 @@ mod tests {
     fn chunk_partial_path_preserves_dots_in_blob_name() {
         let blob = PathBuf::from("/tmp/blobs/abc.def");
         assert_eq!(
-            chunk_partial_path(&blob, 2),
-            PathBuf::from("/tmp/blobs/abc.def.partial.2")
+            chunk_partial_path(&blob, 2, 4),
+            PathBuf::from("/tmp/blobs/abc.def.partial.2-of-4")
         );
     }
 
@@ -1229,6 +2357,7 @@ mod tests {
             &server.uri(),
             &final_path,
             body.len() as u64,
+            &Fetch::default(),
             &progress,
         )
         .await
@@ -1236,8 +2365,8 @@ mod tests {
 
         assert!(final_path.exists(), "final blob should exist");
         assert_eq!(fs::read(&final_path).unwrap(), body);
-        for i in 0..CHUNK_COUNT {
-            let chunk = chunk_partial_path(&final_path, i);
+        for i in 0..DEFAULT_CONCURRENCY {
+            let chunk = chunk_partial_path(&final_path, i, DEFAULT_CONCURRENCY);
             assert!(!chunk.exists(), "chunk partial {chunk:?} should be removed");
         }
         assert!(
@@ -1246,10 +2375,74 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn download_blob_parallel_reports_chunks_then_finalizing() {
+        let body: Vec<u8> = (0..64u8).collect();
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(range_responder(body.clone()))
+            .mount(&server)
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let events = Arc::new(EventLog::default());
+        let progress: Arc<dyn FileProgress> = events.clone();
+
+        download_blob_parallel(
+            &Client::new(),
+            &server.uri(),
+            &dir.path().join("abc123"),
+            body.len() as u64,
+            &Fetch::default(),
+            &progress,
+        )
+        .await
+        .unwrap();
+
+        let events = events.0.lock().unwrap();
+        let expected: Vec<ProgressEvent> = chunk_ranges(body.len() as u64, DEFAULT_CONCURRENCY)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (start, end))| ProgressEvent::ChunkStarted {
+                idx,
+                range: start..end + 1,
+            })
+            .chain([ProgressEvent::Finalizing])
+            .collect();
+        assert_eq!(*events, expected);
+    }
+
+    #[test]
+    fn rate_limited_retries_report_when_they_resume() {
+        let events = Arc::new(EventLog::default());
+        let progress: Arc<dyn FileProgress> = events.clone();
+        let before = SystemTime::now();
+
+        report_retry(
+            &progress,
+            &PacaError::RateLimited(30),
+            1,
+            Duration::from_secs(30),
+        );
+
+        let events = events.0.lock().unwrap();
+        let [ProgressEvent::RateLimited { until }, retrying] = events.as_slice() else {
+            panic!("expected RateLimited then Retrying, got {events:?}");
+        };
+        assert!(*until >= before + Duration::from_secs(30));
+        assert_eq!(
+            *retrying,
+            ProgressEvent::Retrying {
+                attempt: 1,
+                delay: Duration::from_secs(30),
+                error: PacaError::RateLimited(30).to_string(),
+            }
+        );
+    }
+
     #[tokio::test]
     async fn download_blob_parallel_skips_complete_chunk_partial() {
         let body: Vec<u8> = (0..64u8).collect();
-        let chunks = chunk_ranges(body.len() as u64, CHUNK_COUNT);
+        let chunks = chunk_ranges(body.len() as u64, DEFAULT_CONCURRENCY);
         let (start0, end0) = chunks[0];
 
         let server = MockServer::start().await;
@@ -1263,7 +2456,7 @@ mod tests {
         let final_path = dir.path().join("abc123");
         let progress = noop_progress();
 
-        let chunk0_path = chunk_partial_path(&final_path, 0);
+        let chunk0_path = chunk_partial_path(&final_path, 0, DEFAULT_CONCURRENCY);
         fs::write(&chunk0_path, &body[start0 as usize..=end0 as usize]).unwrap();
 
         download_blob_parallel(
@@ -1271,6 +2464,7 @@ mod tests {
             &server.uri(),
             &final_path,
             body.len() as u64,
+            &Fetch::default(),
             &progress,
         )
         .await
@@ -1295,7 +2489,7 @@ mod tests {
     #[tokio::test]
     async fn download_blob_parallel_resumes_undersized_chunk_partial() {
         let body: Vec<u8> = (0..64u8).collect();
-        let chunks = chunk_ranges(body.len() as u64, CHUNK_COUNT);
+        let chunks = chunk_ranges(body.len() as u64, DEFAULT_CONCURRENCY);
         let (start0, end0) = chunks[0];
         let chunk0_size = (end0 - start0 + 1) as usize;
         let half = chunk0_size / 2;
@@ -1311,7 +2505,7 @@ mod tests {
         let final_path = dir.path().join("abc123");
         let progress = noop_progress();
 
-        let chunk0_path = chunk_partial_path(&final_path, 0);
+        let chunk0_path = chunk_partial_path(&final_path, 0, DEFAULT_CONCURRENCY);
         fs::write(&chunk0_path, &body[start0 as usize..start0 as usize + half]).unwrap();
 
         download_blob_parallel(
@@ -1319,6 +2513,7 @@ mod tests {
             &server.uri(),
             &final_path,
             body.len() as u64,
+            &Fetch::default(),
             &progress,
         )
         .await
@@ -1345,7 +2540,7 @@ mod tests {
     #[tokio::test]
     async fn download_blob_parallel_discards_oversized_chunk_partial() {
         let body: Vec<u8> = (0..64u8).collect();
-        let chunks = chunk_ranges(body.len() as u64, CHUNK_COUNT);
+        let chunks = chunk_ranges(body.len() as u64, DEFAULT_CONCURRENCY);
         let (start0, end0) = chunks[0];
         let chunk0_size = (end0 - start0 + 1) as usize;
 
@@ -1360,7 +2555,7 @@ mod tests {
         let final_path = dir.path().join("abc123");
         let progress = noop_progress();
 
-        let chunk0_path = chunk_partial_path(&final_path, 0);
+        let chunk0_path = chunk_partial_path(&final_path, 0, DEFAULT_CONCURRENCY);
         fs::write(&chunk0_path, vec![0xFFu8; chunk0_size * 2]).unwrap();
 
         download_blob_parallel(
@@ -1368,6 +2563,7 @@ mod tests {
             &server.uri(),
             &final_path,
             body.len() as u64,
+            &Fetch::default(),
             &progress,
         )
         .await
@@ -1421,6 +2617,7 @@ mod tests {
             &server.uri(),
             &final_path,
             body.len() as u64,
+            &Fetch::default(),
             &progress,
         )
         .await;
@@ -1432,12 +2629,12 @@ mod tests {
         assert!(!final_path.exists());
         assert!(!partial_path(&final_path).exists());
         assert!(
-            !chunk_partial_path(&final_path, 0).exists(),
+            !chunk_partial_path(&final_path, 0, DEFAULT_CONCURRENCY).exists(),
             "the overshot chunk should be discarded"
         );
-        for i in 1..CHUNK_COUNT {
+        for i in 1..DEFAULT_CONCURRENCY {
             assert!(
-                chunk_partial_path(&final_path, i).exists(),
+                chunk_partial_path(&final_path, i, DEFAULT_CONCURRENCY).exists(),
                 "chunk {i} is intact and must survive for resume"
             );
         }
@@ -1465,6 +2662,7 @@ mod tests {
             &server.uri(),
             &final_path,
             body.len() as u64,
+            &Fetch::default(),
             &progress,
         )
         .await
@@ -1473,4 +2671,96 @@ mod tests {
         assert_eq!(fs::read(&final_path).unwrap(), body);
         assert!(!partial_path(&final_path).exists());
     }
+
+    /// The chunk partials left in `dir`, whatever their layout.
+    fn chunk_partials(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.contains(".partial."))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn download_blob_parallel_discards_chunks_of_another_connection_count() {
+        let body: Vec<u8> = (0..64u8).collect();
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(range_responder(body.clone()))
+            .mount(&server)
+            .await;
+
+        let client = Client::new();
+        let dir = tempfile::tempdir().unwrap();
+        let final_path = dir.path().join("abc123");
+        let progress = noop_progress();
+
+        // Interrupted at four connections: chunk 1 is complete, chunk 2
+        // half done. At two connections those bytes belong elsewhere.
+        let four = chunk_ranges(body.len() as u64, 4);
+        let (start1, end1) = four[1];
+        let (start2, _) = four[2];
+        fs::write(
+            chunk_partial_path(&final_path, 1, 4),
+            &body[start1 as usize..=end1 as usize],
+        )
+        .unwrap();
+        fs::write(
+            chunk_partial_path(&final_path, 2, 4),
+            &body[start2 as usize..start2 as usize + 4],
+        )
+        .unwrap();
+        // And a chunk from before the count was in the name.
+        fs::write(dir.path().join("abc123.partial.1"), &body[..16]).unwrap();
+
+        download_blob_parallel(
+            &client,
+            &server.uri(),
+            &final_path,
+            body.len() as u64,
+            &Fetch {
+                connections: 2,
+                throttle: None,
+                ..Fetch::default()
+            },
+            &progress,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(fs::read(&final_path).unwrap(), body);
+        assert!(chunk_partials(dir.path()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn download_to_blob_removes_chunk_partials_when_sequential() {
+        let body: Vec<u8> = (0..64u8).collect();
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(range_responder(body.clone()))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let final_path = dir.path().join("abc123");
+        fs::write(chunk_partial_path(&final_path, 0, 4), &body[..16]).unwrap();
+
+        download_to_blob(
+            &Client::new(),
+            &server.uri(),
+            &final_path,
+            body.len() as u64,
+            &Fetch {
+                connections: 1,
+                throttle: None,
+                ..Fetch::default()
+            },
+            &noop_progress(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(fs::read(&final_path).unwrap(), body);
+        assert!(chunk_partials(dir.path()).is_empty());
+    }
 }
@@ -0,0 +1,356 @@
+//! [`Download`], a download that reports through a [`Stream`] of
+//! [`DownloadEvent`]s instead of caller-supplied [`FileProgress`]
+//! reporters.
+
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::task::{Context, Poll};
+
+use futures_util::Stream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use super::{DownloadedFile, ModelManifest, install_model};
+use crate::cache::HubLayout;
+use crate::client::Paca;
+use crate::error::PacaError;
+use crate::http::HttpOptions;
+use crate::progress::{FileProgress, ProgressEvent};
+use crate::registry::Registry;
+
+/// What one file of a [`Download`] did.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DownloadEvent {
+    /// Bytes began to move; `position` is what was already on disk.
+    /// Sent again when a failed attempt starts over.
+    Started {
+        file: String,
+        position: u64,
+        size: u64,
+    },
+    /// The file reached `position` of `size` bytes. Progress the
+    /// stream's reader has not caught up with is merged into one event.
+    Progress {
+        file: String,
+        position: u64,
+        size: u64,
+    },
+    /// A step other than moving bytes, such as a retry or falling back
+    /// to the next mirror.
+    Step { file: String, event: ProgressEvent },
+    /// Every byte of the file has arrived. [`ProgressEvent::Verifying`]
+    /// and [`ProgressEvent::Finalizing`] steps may follow.
+    Finished { file: String },
+}
+
+/// A download running in the background.
+///
+/// Read its events as a [`Stream`], then [`Download::finish`] for the
+/// installed files. The stream ends once the download does, whether or
+/// not it succeeded. Dropping the handle cancels the download.
+///
+/// ```no_run
+/// # async fn example() -> Result<(), paca::error::PacaError> {
+/// use futures_util::StreamExt;
+/// use paca::download::DownloadEvent;
+///
+/// let paca = paca::Paca::builder().build()?;
+/// let manifest = paca.manifest("unsloth/GLM-4.7-GGUF:BF16").await?;
+/// let mut download = paca.start_download(manifest)?;
+/// while let Some(event) = download.next().await {
+///     if let DownloadEvent::Progress { file, position, size } = event {
+///         println!("{file}: {position}/{size}");
+///     }
+/// }
+/// for file in download.finish().await? {
+///     println!("{}", file.path.display());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Download {
+    files: Arc<[FileState]>,
+    signals: mpsc::UnboundedReceiver<Signal>,
+    task: JoinHandle<Result<Vec<DownloadedFile>, PacaError>>,
+}
+
+/// Starts downloading the files of `manifest` from `registry` into the
+/// hub cache, connecting as the environment says. See [`Download`].
+///
+/// Must be called from within a Tokio runtime.
+pub fn start_download(
+    registry: Arc<dyn Registry>,
+    manifest: ModelManifest,
+    hub_dir: Option<PathBuf>,
+) -> Result<Download, PacaError> {
+    Paca::for_registry(registry, hub_dir)?.start_download(manifest)
+}
+
+impl Download {
+    pub(crate) fn start(
+        registry: Arc<dyn Registry>,
+        manifest: ModelManifest,
+        hub: HubLayout,
+        http: HttpOptions,
+    ) -> Self {
+        let files: Arc<[FileState]> = manifest
+            .files()
+            .map(|(name, size)| FileState {
+                name: name.to_string(),
+                size,
+                position: AtomicU64::new(0),
+                pending: AtomicBool::new(false),
+            })
+            .collect();
+        let (sender, signals) = mpsc::unbounded_channel();
+        // One reporter per manifest file, made here, so the counts
+        // always agree.
+        let progress = (0..files.len())
+            .map(|idx| {
+                Arc::new(Reporter {
+                    idx,
+                    files: Arc::clone(&files),
+                    signals: sender.clone(),
+                }) as Arc<dyn FileProgress>
+            })
+            .collect();
+        drop(sender);
+
+        let task =
+            tokio::spawn(
+                async move { install_model(registry, manifest, &hub, progress, &http).await },
+            );
+        Self {
+            files,
+            signals,
+            task,
+        }
+    }
+
+    /// Stops the download. Partial files stay in the cache, so a later
+    /// download resumes them; [`Download::finish`] returns
+    /// [`PacaError::Cancelled`].
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+
+    /// Waits for the download to end and returns the installed files.
+    /// Events not yet read are discarded.
+    pub async fn finish(mut self) -> Result<Vec<DownloadedFile>, PacaError> {
+        match (&mut self.task).await {
+            Ok(result) => result,
+            Err(e) if e.is_cancelled() => Err(PacaError::Cancelled),
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+
+    fn event(&self, signal: Signal) -> DownloadEvent {
+        match signal {
+            Signal::Event(event) => event,
+            Signal::Progress(idx) => {
+                let file = &self.files[idx];
+                // Cleared before the read, so an `inc` racing with it
+                // signals again rather than going unreported.
+                file.pending.store(false, Ordering::SeqCst);
+                DownloadEvent::Progress {
+                    file: file.name.clone(),
+                    position: file.position.load(Ordering::SeqCst),
+                    size: file.size,
+                }
+            }
+        }
+    }
+}
+
+impl Stream for Download {
+    type Item = DownloadEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<DownloadEvent>> {
+        self.signals
+            .poll_recv(cx)
+            .map(|signal| signal.map(|signal| self.event(signal)))
+    }
+}
+
+impl Drop for Download {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+struct FileState {
+    name: String,
+    size: u64,
+    position: AtomicU64,
+    /// Whether a [`Signal::Progress`] for this file is waiting to be read.
+    pending: AtomicBool,
+}
+
+enum Signal {
+    Event(DownloadEvent),
+    /// The file's position moved; read it from its [`FileState`].
+    Progress(usize),
+}
+
+/// Forwards one file's progress to its [`Download`].
+struct Reporter {
+    idx: usize,
+    files: Arc<[FileState]>,
+    signals: mpsc::UnboundedSender<Signal>,
+}
+
+impl Reporter {
+    fn file(&self) -> &FileState {
+        &self.files[self.idx]
+    }
+
+    fn send(&self, event: DownloadEvent) {
+        // The handle was dropped, which aborts the download anyway.
+        let _ = self.signals.send(Signal::Event(event));
+    }
+}
+
+impl FileProgress for Reporter {
+    fn start(&self, initial_position: u64) {
+        let file = self.file();
+        file.position.store(initial_position, Ordering::SeqCst);
+        self.send(DownloadEvent::Started {
+            file: file.name.clone(),
+            position: initial_position,
+            size: file.size,
+        });
+    }
+
+    fn inc(&self, delta: u64) {
+        let file = self.file();
+        file.position.fetch_add(delta, Ordering::SeqCst);
+        if !file.pending.swap(true, Ordering::SeqCst) {
+            let _ = self.signals.send(Signal::Progress(self.idx));
+        }
+    }
+
+    /// Everything a download reports arrives as a [`DownloadEvent::Step`].
+    fn println(&self, _: &str) {}
+
+    fn finish(&self) {
+        self.send(DownloadEvent::Finished {
+            file: self.file().name.clone(),
+        });
+    }
+
+    fn event(&self, event: &ProgressEvent) {
+        self.send(DownloadEvent::Step {
+            file: self.file().name.clone(),
+            event: event.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::HttpDirectory;
+    use futures_util::StreamExt;
+    use std::fs;
+    use std::time::Duration;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// A plain HTTP directory serving `owner/model-GGUF` as one Q4_K_M
+    /// file of `body`, sent after `delay`.
+    async fn directory(body: &'static [u8], delay: Duration) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/owner/model-GGUF/"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"<a href="model-Q4_K_M.gguf">model-Q4_K_M.gguf</a>"#),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/owner/model-GGUF/model-Q4_K_M.gguf"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-length", body.len().to_string().as_str()),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/owner/model-GGUF/model-Q4_K_M.gguf"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(body)
+                    .set_delay(delay),
+            )
+            .mount(&server)
+            .await;
+        server
+    }
+
+    async fn start(server: &MockServer, hub_dir: PathBuf) -> Download {
+        let registry: Arc<dyn Registry> = Arc::new(HttpDirectory::new(server.uri()).unwrap());
+        let manifest = crate::download::fetch_manifest(registry.as_ref(), "owner/model-GGUF")
+            .await
+            .unwrap();
+        start_download(registry, manifest, Some(hub_dir)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn streams_events_and_resolves_to_the_installed_files() {
+        let server = directory(b"gguf", Duration::ZERO).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let (events, files) =
+            temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+                let mut download = start(&server, dir.path().to_path_buf()).await;
+                let events: Vec<DownloadEvent> = (&mut download).collect().await;
+                (events, download.finish().await.unwrap())
+            })
+            .await;
+
+        let file = "model-Q4_K_M.gguf".to_string();
+        assert_eq!(
+            events.first(),
+            Some(&DownloadEvent::Step {
+                file: file.clone(),
+                event: ProgressEvent::Resolving,
+            })
+        );
+        assert!(events.contains(&DownloadEvent::Started {
+            file: file.clone(),
+            position: 0,
+            size: 4,
+        }));
+        assert!(events.contains(&DownloadEvent::Progress {
+            file: file.clone(),
+            position: 4,
+            size: 4,
+        }));
+        assert!(events.contains(&DownloadEvent::Finished { file }));
+        assert_eq!(files.len(), 1);
+        assert_eq!(fs::read(&files[0].path).unwrap(), b"gguf");
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_the_download() {
+        let server = directory(b"gguf", Duration::from_secs(30)).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let result = temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            let mut download = start(&server, dir.path().to_path_buf()).await;
+            download.next().await;
+            download.cancel();
+            while download.next().await.is_some() {}
+            download.finish().await
+        })
+        .await;
+
+        assert!(matches!(result, Err(PacaError::Cancelled)));
+        let snapshots = dir.path().join("models--owner--model-GGUF/snapshots");
+        assert!(!snapshots.exists());
+    }
+}
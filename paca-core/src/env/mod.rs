@@ -0,0 +1,329 @@
+//! The HuggingFace environment variables paca honours, resolved with the
+//! same precedence as `huggingface_hub` so that both tools agree on which
+//! cache and token a machine uses, plus the stores of other local model
+//! managers that `paca import` and `paca migrate` read from.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::error::PacaError;
+
+/// The HuggingFace home directory: `HF_HOME`, else
+/// `$XDG_CACHE_HOME/huggingface`, else `~/.cache/huggingface`.
+pub fn hf_home() -> Result<PathBuf, PacaError> {
+    if let Some(home) = var_path("HF_HOME")? {
+        return Ok(home);
+    }
+
+    let cache = match var_path("XDG_CACHE_HOME")? {
+        Some(cache) => cache,
+        None => home_dir()?.join(".cache"),
+    };
+
+    Ok(cache.join("huggingface"))
+}
+
+/// The hub cache directory: `HF_HUB_CACHE`, else the legacy
+/// `HUGGINGFACE_HUB_CACHE`, else `hub` inside [`hf_home`].
+pub fn hub_cache() -> Result<PathBuf, PacaError> {
+    hub_cache_with(None)
+}
+
+/// [`hub_cache`], with a config profile's `hub-dir` below the variables
+/// but above the `hf_home` default: `configured` counts only when
+/// `HF_HOME` is unset.
+pub fn hub_cache_with(configured: Option<&Path>) -> Result<PathBuf, PacaError> {
+    if let Some(cache) = var_path("HF_HUB_CACHE")? {
+        return Ok(cache);
+    }
+    if let Some(cache) = var_path("HUGGINGFACE_HUB_CACHE")? {
+        return Ok(cache);
+    }
+    if var_path("HF_HOME")?.is_none()
+        && let Some(configured) = configured
+    {
+        return Ok(configured.to_path_buf());
+    }
+
+    Ok(hf_home()?.join("hub"))
+}
+
+/// The config file: `PACA_CONFIG`, else `paca/config.toml` inside
+/// `$XDG_CONFIG_HOME` or `~/.config`.
+pub fn config_path() -> Result<PathBuf, PacaError> {
+    if let Some(path) = var_path("PACA_CONFIG")? {
+        return Ok(path);
+    }
+
+    let config = match var_path("XDG_CONFIG_HOME")? {
+        Some(config) => config,
+        None => home_dir()?.join(".config"),
+    };
+
+    Ok(config.join("paca").join("config.toml"))
+}
+
+/// The token file: `HF_TOKEN_PATH`, else `token` inside [`hf_home`].
+pub fn token_path() -> Result<PathBuf, PacaError> {
+    match var_path("HF_TOKEN_PATH")? {
+        Some(path) => Ok(path),
+        None => Ok(hf_home()?.join("token")),
+    }
+}
+
+/// Ollama's model store: `OLLAMA_MODELS`, else `~/.ollama/models`.
+pub fn ollama_models() -> Result<PathBuf, PacaError> {
+    match var_path("OLLAMA_MODELS")? {
+        Some(path) => Ok(path),
+        None => Ok(home_dir()?.join(".ollama").join("models")),
+    }
+}
+
+/// LM Studio's model store: `~/.lmstudio/models`, or the
+/// `~/.cache/lm-studio/models` older releases used when only that exists.
+pub fn lmstudio_models() -> Result<PathBuf, PacaError> {
+    let home = home_dir()?;
+    let current = home.join(".lmstudio").join("models");
+    let legacy = home.join(".cache").join("lm-studio").join("models");
+
+    if !current.is_dir() && legacy.is_dir() {
+        Ok(legacy)
+    } else {
+        Ok(current)
+    }
+}
+
+/// llama.cpp's own download cache: `LLAMA_CACHE`, else `llama.cpp` inside
+/// the platform cache directory (`$XDG_CACHE_HOME` or `~/.cache` on Linux,
+/// `~/Library/Caches` on macOS).
+pub fn llama_cache() -> Result<PathBuf, PacaError> {
+    if let Some(cache) = var_path("LLAMA_CACHE")? {
+        return Ok(cache);
+    }
+
+    let cache = if cfg!(target_os = "macos") {
+        home_dir()?.join("Library").join("Caches")
+    } else {
+        match var_path("XDG_CACHE_HOME")? {
+            Some(cache) => cache,
+            None => home_dir()?.join(".cache"),
+        }
+    };
+
+    Ok(cache.join("llama.cpp"))
+}
+
+/// Whether `HF_HUB_OFFLINE` forbids network access. Accepts the same
+/// truthy spellings as `huggingface_hub`.
+pub fn is_offline() -> bool {
+    env::var("HF_HUB_OFFLINE").is_ok_and(|value| is_truthy(&value))
+}
+
+/// Fails with [`PacaError::Offline`] when offline mode is on. Called at
+/// the top of every operation that would otherwise reach the network.
+pub(crate) fn ensure_online() -> Result<(), PacaError> {
+    if is_offline() {
+        return Err(PacaError::Offline);
+    }
+    Ok(())
+}
+
+pub(crate) fn is_truthy(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_uppercase().as_str(),
+        "1" | "ON" | "YES" | "TRUE"
+    )
+}
+
+/// Reads a path-valued variable, expanding a leading `~` the way
+/// `huggingface_hub` does. Empty values count as unset.
+fn var_path(name: &str) -> Result<Option<PathBuf>, PacaError> {
+    let Ok(value) = env::var(name) else {
+        return Ok(None);
+    };
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    match value.strip_prefix('~') {
+        Some("") => Ok(Some(home_dir()?)),
+        Some(rest) if rest.starts_with('/') => Ok(Some(home_dir()?.join(&rest[1..]))),
+        _ => Ok(Some(PathBuf::from(value))),
+    }
+}
+
+fn home_dir() -> Result<PathBuf, PacaError> {
+    dirs::home_dir().ok_or_else(|| {
+        PacaError::CacheDir(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Could not determine home directory",
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HUB_VARS: [&str; 4] = [
+        "HF_HOME",
+        "HF_HUB_CACHE",
+        "HUGGINGFACE_HUB_CACHE",
+        "XDG_CACHE_HOME",
+    ];
+
+    #[test]
+    fn hub_cache_defaults_to_home_cache() {
+        temp_env::with_vars_unset(HUB_VARS, || {
+            assert!(hub_cache().unwrap().ends_with(".cache/huggingface/hub"));
+        });
+    }
+
+    #[test]
+    fn hub_cache_follows_xdg_cache_home() {
+        temp_env::with_vars_unset(HUB_VARS, || {
+            temp_env::with_var("XDG_CACHE_HOME", Some("/xdg"), || {
+                assert_eq!(hub_cache().unwrap(), PathBuf::from("/xdg/huggingface/hub"));
+            });
+        });
+    }
+
+    #[test]
+    fn hub_cache_follows_hf_home() {
+        temp_env::with_vars_unset(HUB_VARS, || {
+            temp_env::with_vars(
+                [("HF_HOME", Some("/hf")), ("XDG_CACHE_HOME", Some("/xdg"))],
+                || {
+                    assert_eq!(hub_cache().unwrap(), PathBuf::from("/hf/hub"));
+                },
+            );
+        });
+    }
+
+    #[test]
+    fn hub_cache_prefers_legacy_variable_over_hf_home() {
+        temp_env::with_vars_unset(HUB_VARS, || {
+            temp_env::with_vars(
+                [
+                    ("HF_HOME", Some("/hf")),
+                    ("HUGGINGFACE_HUB_CACHE", Some("/legacy")),
+                ],
+                || {
+                    assert_eq!(hub_cache().unwrap(), PathBuf::from("/legacy"));
+                },
+            );
+        });
+    }
+
+    #[test]
+    fn hub_cache_prefers_hf_hub_cache_over_everything() {
+        temp_env::with_vars(
+            [
+                ("HF_HOME", Some("/hf")),
+                ("HF_HUB_CACHE", Some("/hub")),
+                ("HUGGINGFACE_HUB_CACHE", Some("/legacy")),
+                ("XDG_CACHE_HOME", Some("/xdg")),
+            ],
+            || {
+                assert_eq!(hub_cache().unwrap(), PathBuf::from("/hub"));
+            },
+        );
+    }
+
+    #[test]
+    fn hub_cache_falls_back_to_the_config_below_the_environment() {
+        let configured = Path::new("/configured");
+        temp_env::with_vars_unset(HUB_VARS, || {
+            assert_eq!(hub_cache_with(Some(configured)).unwrap(), configured);
+            temp_env::with_var("HF_HOME", Some("/hf"), || {
+                assert_eq!(
+                    hub_cache_with(Some(configured)).unwrap(),
+                    PathBuf::from("/hf/hub")
+                );
+            });
+            temp_env::with_var("HF_HUB_CACHE", Some("/hub"), || {
+                assert_eq!(
+                    hub_cache_with(Some(configured)).unwrap(),
+                    PathBuf::from("/hub")
+                );
+            });
+        });
+    }
+
+    #[test]
+    fn config_path_follows_xdg_config_home() {
+        temp_env::with_vars(
+            [("PACA_CONFIG", None), ("XDG_CONFIG_HOME", Some("/xdg"))],
+            || {
+                assert_eq!(
+                    config_path().unwrap(),
+                    PathBuf::from("/xdg/paca/config.toml")
+                );
+            },
+        );
+        temp_env::with_var("PACA_CONFIG", Some("/etc/paca.toml"), || {
+            assert_eq!(config_path().unwrap(), PathBuf::from("/etc/paca.toml"));
+        });
+    }
+
+    #[test]
+    fn hub_cache_expands_a_leading_tilde() {
+        temp_env::with_vars_unset(HUB_VARS, || {
+            temp_env::with_var("HF_HUB_CACHE", Some("~/models"), || {
+                assert_eq!(
+                    hub_cache().unwrap(),
+                    dirs::home_dir().unwrap().join("models")
+                );
+            });
+        });
+    }
+
+    #[test]
+    fn ollama_models_follows_ollama_models() {
+        temp_env::with_var("OLLAMA_MODELS", Some("/ollama"), || {
+            assert_eq!(ollama_models().unwrap(), PathBuf::from("/ollama"));
+        });
+        temp_env::with_var_unset("OLLAMA_MODELS", || {
+            assert!(ollama_models().unwrap().ends_with(".ollama/models"));
+        });
+    }
+
+    #[test]
+    fn llama_cache_prefers_llama_cache() {
+        temp_env::with_vars(
+            [
+                ("LLAMA_CACHE", Some("/llama")),
+                ("XDG_CACHE_HOME", Some("/xdg")),
+            ],
+            || {
+                assert_eq!(llama_cache().unwrap(), PathBuf::from("/llama"));
+            },
+        );
+    }
+
+    #[test]
+    fn token_path_follows_hf_home() {
+        temp_env::with_vars([("HF_HOME", Some("/hf")), ("HF_TOKEN_PATH", None)], || {
+            assert_eq!(token_path().unwrap(), PathBuf::from("/hf/token"));
+        });
+    }
+
+    #[test]
+    fn is_offline_accepts_truthy_values() {
+        for value in ["1", "true", "TRUE", "yes", "On"] {
+            temp_env::with_var("HF_HUB_OFFLINE", Some(value), || {
+                assert!(is_offline(), "{value} should enable offline mode");
+            });
+        }
+    }
+
+    #[test]
+    fn is_offline_rejects_other_values() {
+        for value in ["0", "false", "no", ""] {
+            temp_env::with_var("HF_HUB_OFFLINE", Some(value), || {
+                assert!(!is_offline(), "{value} should not enable offline mode");
+            });
+        }
+        temp_env::with_var_unset("HF_HUB_OFFLINE", || assert!(!is_offline()));
+    }
+}
@@ -38,14 +38,45 @@ pub enum PacaError {
     #[error("Failed to write file: {0}")]
     FileWrite(std::io::Error),
 
+    /// A downloaded blob's content does not match its registry digest
+    #[error("Downloaded file {path} has SHA-256 {actual}, expected {expected}")]
+    DigestMismatch {
+        actual: String,
+        expected: String,
+        path: String,
+    },
+
+    /// An OCI registry answered with a manifest paca cannot use
+    #[error("Unsupported OCI manifest: {0}")]
+    OciManifest(String),
+
+    /// `--registry` named something other than a known hub or a URL
+    #[error("Unknown registry: {0} (expected huggingface, modelscope or an http(s) URL)")]
+    UnknownRegistry(String),
+
+    /// The requested tag does not exist, with the closest ones that do
+    #[error("Tag not found: {model_ref}{}", did_you_mean(.suggestions))]
+    TagNotFound {
+        model_ref: String,
+        suggestions: Vec<String>,
+    },
+
     /// Missing blob hash (ETag) from registry response
     #[error("Missing blob hash (ETag) for: {0}")]
     MissingBlobHash(String),
 
+    /// The registry reported no size for a file named directly
+    #[error("Missing file size (X-Linked-Size or Content-Length) for: {0}")]
+    MissingFileSize(String),
+
     /// Missing commit hash from registry response
     #[error("Missing x-repo-commit header for: {0}")]
     MissingCommitHash(String),
 
+    /// `HF_HUB_OFFLINE` is set and the operation needs the network
+    #[error("Offline mode is enabled (HF_HUB_OFFLINE); this command needs network access")]
+    Offline,
+
     /// Model is not installed in the cache
     #[error("Model not installed: {0}")]
     ModelNotInstalled(String),
@@ -54,11 +85,23 @@ pub enum PacaError {
     #[error("{0}")]
     ModelRef(#[from] ModelRefError),
 
-    /// `HF_TOKEN` cannot be encoded as an HTTP header. Deliberately
+    /// The token cannot be encoded as an HTTP header. Deliberately
     /// carries no payload: the token is a credential.
-    #[error("HF_TOKEN is not a valid HTTP header value")]
+    #[error("HuggingFace token is not a valid HTTP header value")]
     InvalidToken,
 
+    /// No token was found in `HF_TOKEN` or the token file
+    #[error("Not logged in (set HF_TOKEN or run `paca login`)")]
+    NotLoggedIn,
+
+    /// Failed to read, write or delete the token file
+    #[error("Failed to access token file: {0}")]
+    TokenFile(std::io::Error),
+
+    /// The registry rejected the token
+    #[error("Token was rejected by the registry")]
+    Unauthorized,
+
     /// Insufficient disk space for download
     #[error("Insufficient disk space: need {needed} bytes but only {available} bytes available")]
     InsufficientDiskSpace { needed: u64, available: u64 },
@@ -82,6 +125,185 @@ pub enum PacaError {
     /// A value would place a cache path outside the hub directory
     #[error("Unsafe cache path component: '{0}'")]
     UnsafePath(String),
+
+    /// A launch command names a file the model does not have
+    #[error("Cannot substitute {{{placeholder}}}: {model} has no such file")]
+    MissingPlaceholder { model: String, placeholder: String },
+
+    /// An `export-config` template could not be parsed
+    #[error("Invalid config template at line {line}: {reason}")]
+    InvalidTemplate { line: usize, reason: String },
+
+    /// The mirror server could not bind or accept connections
+    #[error("Failed to serve: {0}")]
+    Serve(std::io::Error),
+
+    /// A download was given a different number of progress reporters
+    /// than its manifest has files
+    #[error("Expected {expected} progress reporters, one per file, but got {actual}")]
+    ReporterCount { expected: usize, actual: usize },
+
+    /// The download was cancelled through its handle
+    #[error("Download cancelled")]
+    Cancelled,
+
+    /// A proxy, bind address, DNS override or user agent is malformed
+    #[error("Invalid network setting: {0}")]
+    InvalidNetworkSetting(String),
+
+    /// A root certificate bundle could not be read or parsed
+    #[error("Failed to load certificates from {path}: {reason}")]
+    Certificate { path: String, reason: String },
+
+    /// The config file is not the TOML paca reads
+    #[error("Invalid config file at line {line}: {reason}")]
+    InvalidConfig { line: usize, reason: String },
+
+    /// Failed to read or write the config file
+    #[error("Failed to access config file: {0}")]
+    ConfigFile(std::io::Error),
+
+    /// `paca config` was given a key profiles do not have
+    #[error(
+        "Unknown config key: {0} (expected hub-dir, endpoints, token.<host>, token-command.<host>, concurrency, rate-limit or protected)"
+    )]
+    UnknownConfigKey(String),
+
+    /// `paca config set` was given a value its key cannot hold
+    #[error("Invalid value for {key}: {reason}")]
+    InvalidConfigValue { key: String, reason: String },
+
+    /// A configured token command failed or printed nothing
+    #[error("Token command for {host} failed: {reason}")]
+    TokenCommand { host: String, reason: String },
+
+    /// `rm` named a model the config file protects
+    #[error("{0} is protected by the config file (see `paca config get protected`)")]
+    Protected(String),
+
+    /// A launch command is empty
+    #[error("No command to run")]
+    EmptyCommand,
+
+    /// The cache holds a repo of the same name from another registry
+    #[error("{repo} is cached from {recorded}, not {origin}; remove it first")]
+    OriginConflict {
+        repo: String,
+        recorded: String,
+        origin: String,
+    },
+
+    /// A repo's `origin` file names no registry paca knows
+    #[error("Unreadable origin for {repo}: {reason}")]
+    UnknownOrigin { repo: String, reason: String },
+}
+
+impl PacaError {
+    /// The status `paca` exits with when this error ends a command.
+    pub fn exit_status(&self) -> ExitStatus {
+        match self {
+            Self::Http(e) | Self::ManifestFetch(e) => {
+                match e.status().map(|status| status.as_u16()) {
+                    Some(401 | 403) => ExitStatus::Auth,
+                    Some(404) => ExitStatus::NotFound,
+                    _ => ExitStatus::Network,
+                }
+            }
+            Self::RangeNotHonored(_) | Self::RateLimited(_) => ExitStatus::Network,
+            Self::ManifestParse(_)
+            | Self::NoFiles
+            | Self::OciManifest(_)
+            | Self::MissingBlobHash(_)
+            | Self::MissingFileSize(_)
+            | Self::MissingCommitHash(_) => ExitStatus::BadResponse,
+            Self::DigestMismatch { .. } | Self::SizeMismatch { .. } => ExitStatus::Integrity,
+            Self::FileWrite(e) if is_disk_full(e) => ExitStatus::InsufficientDisk,
+            Self::CacheDir(_)
+            | Self::Download(_)
+            | Self::FileDelete(_)
+            | Self::FileWrite(_)
+            | Self::Symlink(_)
+            | Self::OriginConflict { .. }
+            | Self::UnknownOrigin { .. } => ExitStatus::Filesystem,
+            Self::InsufficientDiskSpace { .. } => ExitStatus::InsufficientDisk,
+            Self::Offline => ExitStatus::Offline,
+            Self::ModelNotInstalled(_) => ExitStatus::NotInstalled,
+            Self::TagNotFound { .. } => ExitStatus::NotFound,
+            Self::ModelRef(_) | Self::UnsafePath(_) => ExitStatus::InvalidReference,
+            Self::Protected(_) => ExitStatus::Protected,
+            Self::InvalidToken
+            | Self::NotLoggedIn
+            | Self::TokenFile(_)
+            | Self::Unauthorized
+            | Self::TokenCommand { .. } => ExitStatus::Auth,
+            Self::UnknownRegistry(_)
+            | Self::MissingPlaceholder { .. }
+            | Self::InvalidTemplate { .. }
+            | Self::InvalidNetworkSetting(_)
+            | Self::Certificate { .. }
+            | Self::InvalidConfig { .. }
+            | Self::ConfigFile(_)
+            | Self::UnknownConfigKey(_)
+            | Self::InvalidConfigValue { .. }
+            | Self::EmptyCommand => ExitStatus::Config,
+            Self::Serve(_) | Self::ReporterCount { .. } | Self::Cancelled => ExitStatus::Failure,
+        }
+    }
+}
+
+/// Whether a write failed for want of space, which is no fault of the
+/// cache itself.
+fn is_disk_full(error: &std::io::Error) -> bool {
+    error.kind() == std::io::ErrorKind::StorageFull || error.raw_os_error() == Some(libc::ENOSPC)
+}
+
+/// The outcome of a `paca` command as its exit status. The numbers are
+/// stable, so scripts and CI jobs can branch on them; statuses from 10 up
+/// are grouped by tens, by what went wrong.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub enum ExitStatus {
+    Success = 0,
+    /// Any failure without a status of its own.
+    Failure = 1,
+    /// Bad command-line arguments, as clap reports them.
+    Usage = 2,
+    /// `outdated` found models with newer files.
+    Outdated = 10,
+    /// `outdated` could not reach some repos, and found nothing stale.
+    Unreachable = 11,
+    /// `HF_HUB_OFFLINE` kept a command, or `outdated`, off the network.
+    Offline = 12,
+    NotInstalled = 20,
+    /// The tag does not exist in the registry, or in the cache for `rm`.
+    NotFound = 21,
+    InvalidReference = 22,
+    Protected = 23,
+    InsufficientDisk = 30,
+    /// Reading or writing the cache failed.
+    Filesystem = 31,
+    Network = 40,
+    /// A download's size or digest did not match.
+    Integrity = 41,
+    /// The registry answered with something paca cannot use.
+    BadResponse = 42,
+    Auth = 50,
+    /// A setting, config file, template or argument was invalid.
+    Config = 60,
+}
+
+impl From<ExitStatus> for std::process::ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        Self::from(status as u8)
+    }
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(" (did you mean {}?)", suggestions.join(", "))
+    }
 }
 
 /// Errors that can occur while parsing model references
@@ -94,4 +316,89 @@ pub enum ModelRefError {
     /// Missing owner component (expected format: owner/model:tag)
     #[error("Invalid model reference: missing owner (expected format: owner/model:tag)")]
     MissingOwner,
+
+    /// An owner or repository name breaks the hub's naming rules
+    #[error("Invalid model reference: '{0}' is not a valid owner or repository name")]
+    InvalidName(String),
+
+    /// A file path that is empty or climbs out of the repository
+    #[error("Invalid model reference: bad file path '{0}'")]
+    InvalidPath(String),
+
+    /// An `@revision` is empty or given twice
+    #[error("Invalid model reference: bad revision in '{0}'")]
+    InvalidRevision(String),
+
+    /// A URL that is not a recognised hub page
+    #[error("Invalid model reference: unrecognised URL '{0}'")]
+    InvalidUrl(String),
+
+    /// Both a file path and a tag were given
+    #[error("Invalid model reference: a file path cannot also have a tag")]
+    TagWithFile,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_map_to_their_exit_status() {
+        assert_eq!(
+            PacaError::ModelNotInstalled("owner/model:Q4_K_M".to_string()).exit_status(),
+            ExitStatus::NotInstalled
+        );
+        assert_eq!(
+            PacaError::InsufficientDiskSpace {
+                needed: 2,
+                available: 1
+            }
+            .exit_status(),
+            ExitStatus::InsufficientDisk
+        );
+        assert_eq!(
+            PacaError::from(ModelRefError::MissingOwner).exit_status(),
+            ExitStatus::InvalidReference
+        );
+        assert_eq!(ExitStatus::Outdated as u8, 10);
+    }
+
+    #[test]
+    fn a_full_disk_maps_to_insufficient_disk() {
+        let full = std::io::Error::from_raw_os_error(libc::ENOSPC);
+        assert_eq!(
+            PacaError::FileWrite(full).exit_status(),
+            ExitStatus::InsufficientDisk
+        );
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            PacaError::FileWrite(denied).exit_status(),
+            ExitStatus::Filesystem
+        );
+    }
+
+    #[tokio::test]
+    async fn manifest_fetch_errors_map_by_their_http_status() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        for status in [401, 404, 500] {
+            Mock::given(path(format!("/{status}")))
+                .respond_with(ResponseTemplate::new(status))
+                .mount(&server)
+                .await;
+        }
+        let fetch = |status: u16| {
+            let url = format!("{}/{status}", server.uri());
+            async move {
+                let response = reqwest::get(url).await.unwrap();
+                PacaError::ManifestFetch(response.error_for_status().unwrap_err())
+            }
+        };
+
+        assert_eq!(fetch(401).await.exit_status(), ExitStatus::Auth);
+        assert_eq!(fetch(404).await.exit_status(), ExitStatus::NotFound);
+        assert_eq!(fetch(500).await.exit_status(), ExitStatus::Network);
+    }
 }
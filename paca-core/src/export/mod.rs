@@ -0,0 +1,432 @@
+//! Config files for servers that load several models, generated from the
+//! cache: llama-server's router presets and llama-swap's `config.yaml`.
+//!
+//! Both name each model by absolute snapshot path, which an update moves
+//! to a new commit. Regenerating the file after an update repoints every
+//! model; the hand-written part lives in a [`Template`] instead.
+
+use std::fmt;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::cache::{LocalModel, list_models, resolve_local_model};
+use crate::error::PacaError;
+use crate::model::ModelRef;
+
+/// A config file `paca export-config` writes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigFormat {
+    /// The INI presets `llama-server --models-preset` reads in router mode.
+    LlamaServerPreset,
+    /// llama-swap's YAML config.
+    LlamaSwap,
+}
+
+impl FromStr for ConfigFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "llama-server-preset" => Ok(Self::LlamaServerPreset),
+            "llama-swap" => Ok(Self::LlamaSwap),
+            _ => Err(format!(
+                "unknown format '{s}' (expected llama-server-preset or llama-swap)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::LlamaServerPreset => "llama-server-preset",
+            Self::LlamaSwap => "llama-swap",
+        })
+    }
+}
+
+/// llama-server options to set per model, in the INI form of the presets
+/// themselves: `key = value` lines, keys being long options without their
+/// dashes, under a section naming what they apply to.
+///
+/// ```ini
+/// [*]
+/// ctx-size = 8192
+///
+/// [unsloth/GLM-4.7-GGUF]
+/// jinja = true
+///
+/// [unsloth/GLM-4.7-GGUF:BF16]
+/// ctx-size = 32768
+/// ```
+///
+/// `[*]` applies to every model, `[owner/repo]` to each of its tags and
+/// `[owner/repo:tag]` to one tag, each overriding the one before. Names
+/// match case-insensitively, as tags do elsewhere.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Template {
+    sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Template {
+    /// The options for `model_ref`, in the order the template first sets
+    /// them.
+    fn options_for(&self, model_ref: &ModelRef) -> Vec<(String, String)> {
+        let repo = model_ref.repo();
+        let tagged = format!("{repo}:{}", model_ref.tag);
+        let mut options: Vec<(String, String)> = Vec::new();
+
+        for name in ["*", repo.as_str(), tagged.as_str()] {
+            let sections = self
+                .sections
+                .iter()
+                .filter(|(section, _)| section.eq_ignore_ascii_case(name));
+            for (key, value) in sections.flat_map(|(_, options)| options) {
+                match options.iter_mut().find(|(existing, _)| existing == key) {
+                    Some(option) => option.1.clone_from(value),
+                    None => options.push((key.clone(), value.clone())),
+                }
+            }
+        }
+
+        options
+    }
+}
+
+impl FromStr for Template {
+    type Err = PacaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+
+        for (index, line) in s.lines().enumerate() {
+            let invalid = |reason: &str| PacaError::InvalidTemplate {
+                line: index + 1,
+                reason: reason.to_string(),
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| invalid("unclosed section name"))?;
+                sections.push((name.trim().to_string(), Vec::new()));
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid("expected `key = value`"))?;
+            let key = key.trim().trim_start_matches('-');
+            if key.is_empty() {
+                return Err(invalid("empty option name"));
+            }
+            if key == "model" || key == "mmproj" {
+                return Err(invalid("model and mmproj come from the cache"));
+            }
+            let (_, options) = sections
+                .last_mut()
+                .ok_or_else(|| invalid("option outside a section"))?;
+            options.push((key.to_string(), value.trim().to_string()));
+        }
+
+        Ok(Self { sections })
+    }
+}
+
+/// A generated config file.
+#[derive(Debug, Default, Serialize)]
+pub struct ExportedConfig {
+    pub contents: String,
+    /// Listed models left out because their files are missing.
+    pub skipped: Vec<ModelRef>,
+}
+
+/// Writes a config for every tag in the cache, in [`list_models`] order
+/// so that regenerating an unchanged cache gives the same file. llama-swap
+/// commands launch `bin`, usually [`crate::run::DEFAULT_BINARY`].
+pub fn export_config(
+    format: ConfigFormat,
+    hub_dir: Option<PathBuf>,
+    template: &Template,
+    bin: &str,
+) -> Result<ExportedConfig, PacaError> {
+    let mut config = ExportedConfig::default();
+    let mut models = Vec::new();
+
+    for entry in list_models(hub_dir.clone())? {
+        match resolve_local_model(&entry.model_ref, hub_dir.clone()) {
+            Ok(local) => {
+                let options = template.options_for(&entry.model_ref);
+                models.push((entry.model_ref, local, options));
+            }
+            // A root-level projector lists as a tag of its own, and a
+            // dangling tag is for `clean`; neither has a model to serve.
+            Err(PacaError::ModelNotInstalled(_)) => config.skipped.push(entry.model_ref),
+            Err(e) => return Err(e),
+        }
+    }
+
+    config.contents = match format {
+        ConfigFormat::LlamaServerPreset => llama_server_preset(&models),
+        ConfigFormat::LlamaSwap => llama_swap(&models, bin),
+    };
+    Ok(config)
+}
+
+type ExportedModel = (ModelRef, LocalModel, Vec<(String, String)>);
+
+const HEADER: &str = "Generated by `paca export-config`; regenerate after updating models.";
+
+fn llama_server_preset(models: &[ExportedModel]) -> String {
+    let mut out = format!("; {HEADER}\n");
+    for (model_ref, local, options) in models {
+        let _ = write!(out, "\n[{model_ref}]\nmodel = {}\n", local.model.display());
+        if let Some(mmproj) = &local.mmproj {
+            let _ = writeln!(out, "mmproj = {}", mmproj.display());
+        }
+        for (key, value) in options {
+            let value = if value.is_empty() { "true" } else { value };
+            let _ = writeln!(out, "{key} = {value}");
+        }
+    }
+    out
+}
+
+fn llama_swap(models: &[ExportedModel], bin: &str) -> String {
+    let mut out = format!("# {HEADER}\nmodels:\n");
+    for (model_ref, local, options) in models {
+        let mut cmd = format!(
+            "{} --port ${{PORT}} -m {}",
+            shell_word(bin),
+            shell_word(&local.model.to_string_lossy())
+        );
+        if let Some(mmproj) = &local.mmproj {
+            let _ = write!(cmd, " --mmproj {}", shell_word(&mmproj.to_string_lossy()));
+        }
+        for (key, value) in options {
+            // llama-swap passes flags bare; the presets spell them `true`,
+            // and `false` for a flag left off.
+            match value.as_str() {
+                "false" => {}
+                "" | "true" => {
+                    let _ = write!(cmd, " --{key}");
+                }
+                value => {
+                    let _ = write!(cmd, " --{key} {}", shell_word(value));
+                }
+            }
+        }
+        let _ = write!(
+            out,
+            "  {}:\n    cmd: {}\n",
+            yaml_string(&model_ref.to_string()),
+            yaml_string(&cmd)
+        );
+    }
+    out
+}
+
+/// Single-quotes `word` when llama-swap's shell-style splitting of `cmd`
+/// would otherwise break it apart.
+fn shell_word(word: &str) -> String {
+    if !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-+:=,@%".contains(c))
+    {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// A double-quoted YAML scalar, which JSON string syntax is a subset of.
+fn yaml_string(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run::DEFAULT_BINARY;
+    use crate::test_support::{setup_model_dir, write_blob, write_ref, write_snapshot_symlink};
+    use std::path::Path;
+
+    fn install(hub: &Path, commit: &str, files: &[(&str, &str)]) -> PathBuf {
+        let model_dir = setup_model_dir(hub, "owner", "Model-GGUF");
+        write_ref(&model_dir, commit);
+        for (file, hash) in files {
+            write_blob(&model_dir, hash);
+            write_snapshot_symlink(&model_dir, commit, file, hash);
+        }
+        model_dir.join("snapshots").join(commit)
+    }
+
+    #[test]
+    fn template_overrides_from_general_to_specific() {
+        let template: Template = "\
+            [*]\n\
+            ctx-size = 8192\n\
+            jinja = true\n\
+            \n\
+            [owner/model-gguf]\n\
+            ctx-size = 16384\n\
+            \n\
+            [owner/Model-GGUF:Q8_0]\n\
+            --ctx-size = 32768\n\
+            "
+        .parse()
+        .unwrap();
+
+        let options = |model: &str| template.options_for(&model.parse().unwrap());
+        assert_eq!(
+            options("owner/Model-GGUF:Q8_0"),
+            vec![
+                ("ctx-size".to_string(), "32768".to_string()),
+                ("jinja".to_string(), "true".to_string()),
+            ]
+        );
+        assert_eq!(options("owner/Model-GGUF:Q4_K_M")[0].1, "16384");
+        assert_eq!(options("other/Model-GGUF:Q4_K_M")[0].1, "8192");
+    }
+
+    #[test]
+    fn template_rejects_malformed_lines() {
+        for (template, line) in [
+            ("ctx-size = 1", 1),
+            ("[*]\nctx-size", 2),
+            ("[*\n", 1),
+            ("[*]\nmodel = /elsewhere.gguf", 2),
+        ] {
+            let result = template.parse::<Template>();
+            assert!(
+                matches!(result, Err(PacaError::InvalidTemplate { line: l, .. }) if l == line),
+                "{template:?} should fail on line {line}"
+            );
+        }
+    }
+
+    #[test]
+    fn exports_llama_server_presets() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = install(
+            dir.path(),
+            "commit1",
+            &[
+                ("Q8_0/Model-Q8_0-00001-of-00002.gguf", "shard1"),
+                ("Q8_0/Model-Q8_0-00002-of-00002.gguf", "shard2"),
+                ("Model-Q4_K_M.gguf", "q4"),
+            ],
+        );
+        let template: Template = "[owner/Model-GGUF:Q8_0]\nctx-size = 32768\n"
+            .parse()
+            .unwrap();
+
+        let config = export_config(
+            ConfigFormat::LlamaServerPreset,
+            Some(dir.path().to_path_buf()),
+            &template,
+            DEFAULT_BINARY,
+        )
+        .unwrap();
+
+        assert!(config.skipped.is_empty());
+        assert_eq!(
+            config.contents,
+            format!(
+                "; {HEADER}\n\
+                 \n[owner/Model-GGUF:Q4_K_M]\nmodel = {}\n\
+                 \n[owner/Model-GGUF:Q8_0]\nmodel = {}\nctx-size = 32768\n",
+                snapshot.join("Model-Q4_K_M.gguf").display(),
+                snapshot
+                    .join("Q8_0/Model-Q8_0-00001-of-00002.gguf")
+                    .display(),
+            )
+        );
+    }
+
+    #[test]
+    fn exports_llama_swap_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let hub = dir.path().join("hub dir");
+        let snapshot = install(
+            &hub,
+            "commit1",
+            &[("Model-Q4_K_M.gguf", "q4"), ("mmproj-F16.gguf", "mmproj")],
+        );
+        let template: Template = "[*]\njinja = true\nmlock = false\nalias = it's\n"
+            .parse()
+            .unwrap();
+
+        let config = export_config(
+            ConfigFormat::LlamaSwap,
+            Some(hub),
+            &template,
+            "/opt/llama.cpp/llama-server",
+        )
+        .unwrap();
+
+        let model = snapshot.join("Model-Q4_K_M.gguf");
+        let mmproj = snapshot.join("mmproj-F16.gguf");
+        let cmd = format!(
+            "/opt/llama.cpp/llama-server --port ${{PORT}} -m '{}' --mmproj '{}' --jinja --alias 'it'\\''s'",
+            model.display(),
+            mmproj.display()
+        );
+        assert!(
+            config
+                .contents
+                .starts_with(&format!("# {HEADER}\nmodels:\n"))
+        );
+        assert!(config.contents.contains(&format!(
+            "  \"owner/Model-GGUF:Q4_K_M\":\n    cmd: {}\n",
+            yaml_string(&cmd)
+        )));
+    }
+
+    #[test]
+    fn export_repoints_models_after_an_update() {
+        let dir = tempfile::tempdir().unwrap();
+        install(dir.path(), "commit1", &[("Model-Q4_K_M.gguf", "q4")]);
+        let export = || {
+            export_config(
+                ConfigFormat::LlamaServerPreset,
+                Some(dir.path().to_path_buf()),
+                &Template::default(),
+                DEFAULT_BINARY,
+            )
+            .unwrap()
+            .contents
+        };
+        assert!(export().contains("snapshots/commit1/Model-Q4_K_M.gguf"));
+
+        install(dir.path(), "commit2", &[("Model-Q4_K_M.gguf", "q4")]);
+
+        assert!(export().contains("snapshots/commit2/Model-Q4_K_M.gguf"));
+    }
+
+    #[test]
+    fn export_skips_dangling_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_dir = setup_model_dir(dir.path(), "owner", "Model-GGUF");
+        write_ref(&model_dir, "commit1");
+        write_snapshot_symlink(&model_dir, "commit1", "Model-Q4_K_M.gguf", "gone");
+
+        let config = export_config(
+            ConfigFormat::LlamaSwap,
+            Some(dir.path().to_path_buf()),
+            &Template::default(),
+            DEFAULT_BINARY,
+        )
+        .unwrap();
+
+        assert_eq!(config.contents, format!("# {HEADER}\nmodels:\n"));
+    }
+}
@@ -0,0 +1,384 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::cache::{HubLayout, is_gguf};
+use crate::download::{is_commit_hash, sha256_file};
+use crate::env;
+use crate::error::{ModelRefError, PacaError};
+use crate::import::{Candidate, CandidateFile, ImportReport, Transfer, entries, install};
+use crate::model::tag_for_path;
+use crate::model::{ModelRef, percent_decode};
+use crate::registry::{Origin, sha256_hex};
+
+/// The `<file>.json` llama.cpp writes beside each download.
+#[derive(Debug, Deserialize)]
+struct Sidecar {
+    #[serde(default)]
+    etag: Option<String>,
+    url: String,
+}
+
+/// A file in llama.cpp's cache, mapped back to where it was downloaded
+/// from. The flat `owner_repo_file.gguf` name is ambiguous once names
+/// contain underscores, so the sidecar's URL is what gets parsed.
+struct CachedFile {
+    gguf: PathBuf,
+    sidecar: PathBuf,
+    owner: String,
+    repo: String,
+    revision: String,
+    /// Repository-relative, as the hub names it.
+    filename: String,
+    etag: Option<String>,
+}
+
+/// Moves the models `llama-server -hf` downloaded into llama.cpp's own
+/// cache (`LLAMA_CACHE`, unless `cache_dir` is given) into the hub cache,
+/// where `list`, `outdated` and `clean` see them.
+///
+/// A blob keeps the name llama.cpp recorded when its ETag is a SHA-256, as
+/// the hub's are for LFS files, and is hashed otherwise. Only a download
+/// pinned to a commit records which one; the rest are linked into the
+/// snapshot `refs/main` already names, or one derived from the repository
+/// name, which `outdated` then reports until the next download.
+///
+/// With `leave_symlinks`, each moved file is replaced by a symlink into the
+/// cache and its sidecar kept, so llama.cpp still finds it; otherwise the
+/// sidecar is deleted with it.
+pub fn migrate_llama_cache(
+    cache_dir: Option<PathBuf>,
+    hub_dir: Option<PathBuf>,
+    leave_symlinks: bool,
+) -> Result<ImportReport, PacaError> {
+    let cache_dir = match cache_dir {
+        Some(dir) => dir,
+        None => env::llama_cache()?,
+    };
+    if !cache_dir.is_dir() {
+        return Err(PacaError::CacheDir(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("llama.cpp cache not found at {}", cache_dir.display()),
+        )));
+    }
+
+    let hub = HubLayout::open(hub_dir)?;
+    let mut report = ImportReport::default();
+
+    // Group by tag first, so the shards of one tag land in the same
+    // candidate and share a snapshot.
+    let mut tags: BTreeMap<(String, String, String), Vec<CachedFile>> = BTreeMap::new();
+    for path in entries(&cache_dir)? {
+        // Symlinks are what an earlier migration left behind.
+        let is_file = path
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_file());
+        if !is_file || !is_gguf(&path) {
+            continue;
+        }
+        match cached_file(path.clone()) {
+            Ok(file) => {
+                let tag = tag_for_path(&file.filename, &file.repo)
+                    .unwrap_or_else(|| stem(&file.filename));
+                let key = (file.owner.clone(), file.repo.clone(), tag);
+                tags.entry(key).or_default().push(file);
+            }
+            Err(reason) => report.unmapped(&path, reason),
+        }
+    }
+
+    for ((owner, repo, tag), files) in tags {
+        let source = files[0].gguf.clone();
+        let candidate = match candidate(&owner, &repo, &tag, &files) {
+            Ok(candidate) => candidate,
+            Err(reason) => {
+                report.unmapped(&source, reason);
+                continue;
+            }
+        };
+
+        match install(&hub, &candidate, Transfer::Move) {
+            Ok(imported) => {
+                for (file, installed) in files.iter().zip(&imported) {
+                    if let Err(e) = leave_behind(file, &installed.path, leave_symlinks) {
+                        report.unmapped(&file.gguf, e);
+                    }
+                }
+                report.imported.extend(imported);
+            }
+            Err(e) => report.unmapped(&source, e),
+        }
+    }
+
+    report.sort();
+    Ok(report)
+}
+
+/// Fails with the reason the file cannot be migrated.
+fn cached_file(gguf: PathBuf) -> Result<CachedFile, String> {
+    let mut sidecar = gguf.clone().into_os_string();
+    sidecar.push(".json");
+    let sidecar = PathBuf::from(sidecar);
+
+    let contents = fs::read_to_string(&sidecar)
+        .map_err(|_| "no .json sidecar recording where it came from".to_string())?;
+    let parsed: Sidecar = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+    let (owner, repo, revision, filename) =
+        parse_resolve_url(&parsed.url).ok_or_else(|| format!("unrecognised URL {}", parsed.url))?;
+
+    Ok(CachedFile {
+        gguf,
+        sidecar,
+        owner,
+        repo,
+        revision,
+        filename,
+        etag: parsed.etag,
+    })
+}
+
+/// Splits `<endpoint>/<owner>/<repo>/resolve/<revision>/<file>` into its
+/// parts. Any endpoint is accepted, since llama.cpp honours `HF_ENDPOINT`.
+fn parse_resolve_url(url: &str) -> Option<(String, String, String, String)> {
+    let url = url.split(['?', '#']).next()?;
+    let (head, tail) = url.split_once("/resolve/")?;
+    let mut segments = head.rsplit('/');
+    let repo = segments.next()?;
+    let owner = segments.next()?;
+    let (revision, filename) = tail.split_once('/')?;
+
+    if owner.is_empty() || repo.is_empty() || filename.is_empty() {
+        return None;
+    }
+    Some((
+        owner.to_string(),
+        repo.to_string(),
+        percent_decode(revision)?,
+        percent_decode(filename)?,
+    ))
+}
+
+/// Fails with the reason the tag cannot be migrated.
+fn candidate(
+    owner: &str,
+    repo: &str,
+    tag: &str,
+    files: &[CachedFile],
+) -> Result<Candidate, String> {
+    let model_ref: ModelRef = format!("{owner}/{repo}:{tag}")
+        .parse()
+        .map_err(|e: ModelRefError| e.to_string())?;
+
+    let commit_hash = files
+        .iter()
+        .map(|file| file.revision.as_str())
+        .find(|revision| is_commit_hash(revision))
+        .map_or_else(
+            || sha256_hex(&format!("llama.cpp/{owner}/{repo}")),
+            str::to_string,
+        );
+
+    let files: Vec<CandidateFile> = files
+        .iter()
+        .map(|file| {
+            let blob_hash = match file.etag.as_deref().and_then(sha256_etag) {
+                Some(hash) => hash,
+                None => sha256_file(&file.gguf).map_err(|e| e.to_string())?,
+            };
+            // A file whose name carries no tag is nested under one, as
+            // `import` does, so `list` reads the same tag back.
+            let filename = if tag_for_path(&file.filename, repo).is_some() {
+                file.filename.clone()
+            } else {
+                format!("{tag}/{}", file.filename)
+            };
+            Ok(CandidateFile {
+                source: file.gguf.clone(),
+                blob_hash,
+                filename,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(Candidate {
+        model_ref,
+        commit_hash,
+        source: files[0].source.clone(),
+        files,
+        // `llama-server -hf` speaks the hub's protocol, mirror or not.
+        origin: Origin::HuggingFace,
+    })
+}
+
+/// The SHA-256 an ETag carries, for the hub's LFS files. Git-tracked
+/// files have SHA-1 ETags, which are not blob names.
+fn sha256_etag(etag: &str) -> Option<String> {
+    let hash = etag.trim().trim_start_matches("W/").trim_matches('"');
+    (hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then(|| hash.to_ascii_lowercase())
+}
+
+fn stem(filename: &str) -> String {
+    let name = filename.rsplit('/').next().unwrap_or(filename);
+    Path::new(name)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn leave_behind(
+    file: &CachedFile,
+    installed: &Path,
+    leave_symlinks: bool,
+) -> Result<(), PacaError> {
+    if leave_symlinks {
+        std::os::unix::fs::symlink(installed, &file.gguf).map_err(PacaError::Symlink)
+    } else {
+        fs::remove_file(&file.sidecar).map_err(PacaError::FileDelete)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::LinkMethod;
+    use sha2::{Digest, Sha256};
+
+    const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn write_cached(cache: &Path, name: &str, url: &str, etag: &str, content: &[u8]) {
+        fs::create_dir_all(cache).unwrap();
+        fs::write(cache.join(name), content).unwrap();
+        fs::write(
+            cache.join(format!("{name}.json")),
+            format!(r#"{{"etag": "{etag}", "lastModified": "", "url": "{url}"}}"#),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn parse_resolve_url_reads_the_repo_and_file() {
+        assert_eq!(
+            parse_resolve_url(
+                "https://huggingface.co/my_org/Model-GGUF/resolve/main/Q8_0/Model-Q8_0-00001-of-00002.gguf?download=true"
+            ),
+            Some((
+                "my_org".to_string(),
+                "Model-GGUF".to_string(),
+                "main".to_string(),
+                "Q8_0/Model-Q8_0-00001-of-00002.gguf".to_string(),
+            ))
+        );
+        assert_eq!(parse_resolve_url("https://example.test/model.gguf"), None);
+    }
+
+    #[test]
+    fn sha256_etag_ignores_git_etags() {
+        let sha256 = "A".repeat(64);
+        assert_eq!(sha256_etag(&format!("\"{sha256}\"")), Some("a".repeat(64)));
+        assert_eq!(
+            sha256_etag("W/\"0123456789abcdef0123456789abcdef01234567\""),
+            None
+        );
+    }
+
+    #[test]
+    fn moves_files_into_the_hub_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("llama.cpp");
+        let hash = "b".repeat(64);
+        write_cached(
+            &cache,
+            "owner_Model-GGUF_Model-Q4_K_M.gguf",
+            &format!("https://huggingface.co/owner/Model-GGUF/resolve/{COMMIT}/Model-Q4_K_M.gguf"),
+            &format!("\\\"{hash}\\\""),
+            b"q4",
+        );
+
+        let hub = dir.path().join("hub");
+        let report = migrate_llama_cache(Some(cache.clone()), Some(hub.clone()), false).unwrap();
+
+        assert!(report.unmapped.is_empty());
+        assert_eq!(report.imported.len(), 1);
+        assert_eq!(report.imported[0].method, LinkMethod::Move);
+        assert_eq!(
+            report.imported[0].model_ref.to_string(),
+            "owner/Model-GGUF:Q4_K_M"
+        );
+        let model_dir = hub.join("models--owner--Model-GGUF");
+        assert_eq!(
+            report.imported[0].path,
+            model_dir.join(format!("snapshots/{COMMIT}/Model-Q4_K_M.gguf"))
+        );
+        assert_eq!(
+            fs::read(model_dir.join("blobs").join(&hash)).unwrap(),
+            b"q4"
+        );
+        assert_eq!(
+            fs::read_to_string(model_dir.join("refs/main")).unwrap(),
+            COMMIT
+        );
+        assert!(!cache.join("owner_Model-GGUF_Model-Q4_K_M.gguf").exists());
+        assert!(
+            !cache
+                .join("owner_Model-GGUF_Model-Q4_K_M.gguf.json")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn hashes_files_without_a_sha256_etag_and_leaves_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("llama.cpp");
+        let name = "owner_Model-GGUF_Model-Q4_K_M.gguf";
+        write_cached(
+            &cache,
+            name,
+            "https://huggingface.co/owner/Model-GGUF/resolve/main/Model-Q4_K_M.gguf",
+            "W/\\\"1234\\\"",
+            b"q4",
+        );
+
+        let hub = dir.path().join("hub");
+        let report = migrate_llama_cache(Some(cache.clone()), Some(hub.clone()), true).unwrap();
+
+        let commit = sha256_hex("llama.cpp/owner/Model-GGUF");
+        let blob = hub
+            .join("models--owner--Model-GGUF/blobs")
+            .join(format!("{:x}", Sha256::digest(b"q4")));
+        assert!(blob.exists());
+        assert!(
+            report.imported[0]
+                .path
+                .ends_with(format!("{commit}/Model-Q4_K_M.gguf"))
+        );
+        assert!(cache.join(name).symlink_metadata().unwrap().is_symlink());
+        assert_eq!(fs::read(cache.join(name)).unwrap(), b"q4");
+        assert!(cache.join(format!("{name}.json")).exists());
+
+        // A second run leaves the symlinks alone.
+        let again = migrate_llama_cache(Some(cache), Some(hub), true).unwrap();
+        assert!(again.imported.is_empty() && again.unmapped.is_empty());
+    }
+
+    #[test]
+    fn reports_files_without_a_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("llama.cpp");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("orphan.gguf"), b"gguf").unwrap();
+        fs::write(cache.join("manifest=owner=Model-GGUF=latest.json"), "{}").unwrap();
+
+        let report =
+            migrate_llama_cache(Some(cache.clone()), Some(dir.path().join("hub")), false).unwrap();
+
+        assert!(report.imported.is_empty());
+        assert_eq!(report.unmapped.len(), 1);
+        assert!(report.unmapped[0].reason.contains("sidecar"));
+        assert!(cache.join("orphan.gguf").exists());
+    }
+}
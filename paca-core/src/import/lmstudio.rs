@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cache::{HubLayout, is_gguf};
+use crate::download::sha256_file;
+use crate::error::{ModelRefError, PacaError};
+use crate::import::{Candidate, CandidateFile, ImportReport, ImportSource, entries, subdirs};
+use crate::model::{ModelRef, derive_tag};
+use crate::path::join_within;
+use crate::registry::{Origin, sha256_hex};
+
+/// Maps an LM Studio store onto the hub layout. LM Studio keeps the
+/// HuggingFace `<publisher>/<repo>/` directories it downloaded from, so
+/// each GGUF file goes back to the repository and tag it came from.
+///
+/// Blobs are named by the SHA-256 of their content, which is also the name
+/// the hub gives LFS files: a later `paca download` of the same file finds
+/// it already cached. LM Studio records no commit, so a repository the
+/// cache does not know yet gets one derived from its name.
+pub(super) fn candidates(
+    store: &Path,
+    hub: &HubLayout,
+    report: &mut ImportReport,
+) -> Result<Vec<Candidate>, PacaError> {
+    let mut candidates = Vec::new();
+
+    for publisher in entries(store)? {
+        if is_gguf(&publisher) {
+            report.unmapped(&publisher, "not inside a <publisher>/<repo> directory");
+            continue;
+        }
+        for repo in subdirs(&publisher)? {
+            candidates.extend(repo_candidates(&repo, hub, report)?);
+        }
+    }
+
+    Ok(candidates)
+}
+
+fn repo_candidates(
+    repo_dir: &Path,
+    hub: &HubLayout,
+    report: &mut ImportReport,
+) -> Result<Vec<Candidate>, PacaError> {
+    let name = |path: &Path| {
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    };
+    let repo = name(repo_dir);
+    let publisher = repo_dir.parent().map(name).unwrap_or_default();
+
+    // Files whose name carries no tag (`model.gguf`, a stray projector)
+    // get a tag of their own from the file stem, and are nested under it
+    // so `list` reads the same tag back.
+    let mut tags: BTreeMap<String, Vec<(PathBuf, String)>> = BTreeMap::new();
+    for file in entries(repo_dir)? {
+        if !file.is_file() || !is_gguf(&file) {
+            continue;
+        }
+        let filename = name(&file);
+        match derive_tag(&filename, &repo) {
+            Some(tag) => tags.entry(tag).or_default().push((file, filename)),
+            None => {
+                let stem = file
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned();
+                let nested = format!("{stem}/{filename}");
+                tags.entry(stem).or_default().push((file, nested));
+            }
+        }
+    }
+
+    let commit_hash = sha256_hex(&format!("lmstudio/{publisher}/{repo}"));
+    let mut candidates = Vec::new();
+    for (tag, files) in tags {
+        let source = files[0].0.clone();
+        match candidate(hub, &publisher, &repo, &tag, files) {
+            Ok((model_ref, files)) => candidates.push(Candidate {
+                model_ref,
+                commit_hash: commit_hash.clone(),
+                files,
+                source,
+                origin: Origin::Import(ImportSource::LmStudio.to_string()),
+            }),
+            Err(reason) => report.unmapped(&source, reason),
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// Fails with the reason the tag cannot be imported.
+fn candidate(
+    hub: &HubLayout,
+    publisher: &str,
+    repo: &str,
+    tag: &str,
+    files: Vec<(PathBuf, String)>,
+) -> Result<(ModelRef, Vec<CandidateFile>), String> {
+    let model_ref: ModelRef = format!("{publisher}/{repo}:{tag}")
+        .parse()
+        .map_err(|e: ModelRefError| e.to_string())?;
+
+    let files = files
+        .into_iter()
+        .map(|(source, filename)| {
+            let blob_hash = match linked_blob(hub, &model_ref, &filename, &source) {
+                Some(blob_hash) => blob_hash,
+                None => sha256_file(&source).map_err(|e| e.to_string())?,
+            };
+            Ok(CandidateFile {
+                blob_hash,
+                source,
+                filename,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok((model_ref, files))
+}
+
+/// The blob an earlier import or download links `filename` to, when it
+/// is the size of `source`. Importing again then reads no GGUF through,
+/// where hashing each one would take minutes for a large store.
+fn linked_blob(
+    hub: &HubLayout,
+    model_ref: &ModelRef,
+    filename: &str,
+    source: &Path,
+) -> Option<String> {
+    let paths = hub.model(model_ref).ok()?;
+    let commit = paths.read_ref()?;
+    let link = join_within(&paths.snapshot(commit.trim()).ok()?, filename).ok()?;
+    let blob_hash = fs::read_link(&link)
+        .ok()?
+        .file_name()?
+        .to_str()?
+        .to_string();
+    let linked = fs::metadata(&link).ok()?;
+    (linked.is_file() && linked.len() == fs::metadata(source).ok()?.len()).then_some(blob_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{ImportSource, LinkMethod, import_models};
+    use std::fs;
+
+    fn write_gguf(store: &Path, relative: &str, content: &[u8]) {
+        let path = store.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn imports_each_tag_under_its_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("lmstudio");
+        write_gguf(&store, "owner/Model-GGUF/Model-Q4_K_M.gguf", b"q4");
+        write_gguf(
+            &store,
+            "owner/Model-GGUF/Model-Q8_0-00001-of-00002.gguf",
+            b"q8a",
+        );
+        write_gguf(
+            &store,
+            "owner/Model-GGUF/Model-Q8_0-00002-of-00002.gguf",
+            b"q8b",
+        );
+        write_gguf(&store, "owner/Model-GGUF/README.md", b"readme");
+
+        let hub = dir.path().join("hub");
+        let report = import_models(ImportSource::LmStudio, Some(store), Some(hub.clone())).unwrap();
+
+        assert!(report.unmapped.is_empty());
+        assert_eq!(
+            fs::read_to_string(hub.join("models--owner--Model-GGUF/origin")).unwrap(),
+            "import lmstudio\n"
+        );
+        let refs: Vec<String> = report
+            .imported
+            .iter()
+            .map(|f| f.model_ref.to_string())
+            .collect();
+        assert_eq!(
+            refs,
+            vec![
+                "owner/Model-GGUF:Q4_K_M",
+                "owner/Model-GGUF:Q8_0",
+                "owner/Model-GGUF:Q8_0",
+            ]
+        );
+
+        let blob = hub.join(format!(
+            "models--owner--Model-GGUF/blobs/{}",
+            sha256_file(&report.imported[0].path).unwrap()
+        ));
+        assert_eq!(fs::read(blob).unwrap(), b"q4");
+    }
+
+    #[test]
+    fn nests_a_file_without_a_tag_under_its_stem() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("lmstudio");
+        write_gguf(&store, "owner/Model-GGUF/mmproj-f16.gguf", b"proj");
+
+        let report = import_models(
+            ImportSource::LmStudio,
+            Some(store),
+            Some(dir.path().join("hub")),
+        )
+        .unwrap();
+
+        assert_eq!(report.imported.len(), 1);
+        assert_eq!(
+            report.imported[0].model_ref.to_string(),
+            "owner/Model-GGUF:mmproj-f16"
+        );
+        assert!(
+            report.imported[0]
+                .path
+                .ends_with("mmproj-f16/mmproj-f16.gguf")
+        );
+    }
+
+    #[test]
+    fn links_into_the_snapshot_the_cache_already_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("lmstudio");
+        write_gguf(&store, "owner/Model-GGUF/Model-Q4_K_M.gguf", b"q4");
+        let hub = dir.path().join("hub");
+        fs::create_dir_all(hub.join("models--owner--Model-GGUF/refs")).unwrap();
+        fs::write(hub.join("models--owner--Model-GGUF/refs/main"), "abc123").unwrap();
+
+        let report = import_models(ImportSource::LmStudio, Some(store), Some(hub.clone())).unwrap();
+
+        assert_eq!(
+            report.imported[0].path,
+            hub.join("models--owner--Model-GGUF/snapshots/abc123/Model-Q4_K_M.gguf")
+        );
+    }
+
+    #[test]
+    fn reimporting_reuses_a_linked_blob_of_the_same_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("lmstudio");
+        let hub = dir.path().join("hub");
+        write_gguf(&store, "owner/Model-GGUF/Model-Q4_K_M.gguf", b"q4");
+        let first = import_models(
+            ImportSource::LmStudio,
+            Some(store.clone()),
+            Some(hub.clone()),
+        )
+        .unwrap();
+
+        // Same size, other bytes: only a rehash would notice, and the
+        // existing link is trusted instead. Replacing the file keeps the
+        // hardlinked blob as it was.
+        fs::remove_file(store.join("owner/Model-GGUF/Model-Q4_K_M.gguf")).unwrap();
+        write_gguf(&store, "owner/Model-GGUF/Model-Q4_K_M.gguf", b"q5");
+        let again = import_models(
+            ImportSource::LmStudio,
+            Some(store.clone()),
+            Some(hub.clone()),
+        )
+        .unwrap();
+        assert_eq!(again.imported[0].method, LinkMethod::Existing);
+        assert_eq!(fs::read(&again.imported[0].path).unwrap(), b"q4");
+
+        // A different size is a different file.
+        fs::remove_file(store.join("owner/Model-GGUF/Model-Q4_K_M.gguf")).unwrap();
+        write_gguf(&store, "owner/Model-GGUF/Model-Q4_K_M.gguf", b"q4 v2");
+        let changed = import_models(ImportSource::LmStudio, Some(store), Some(hub)).unwrap();
+        assert_ne!(changed.imported[0].method, LinkMethod::Existing);
+        assert_eq!(fs::read(&changed.imported[0].path).unwrap(), b"q4 v2");
+        assert_eq!(first.imported[0].path, changed.imported[0].path);
+    }
+
+    #[test]
+    fn reports_files_outside_a_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("lmstudio");
+        write_gguf(&store, "loose.gguf", b"gguf");
+        write_gguf(&store, "bad name/Model-GGUF/Model-Q4_K_M.gguf", b"q4");
+
+        let report = import_models(
+            ImportSource::LmStudio,
+            Some(store),
+            Some(dir.path().join("hub")),
+        )
+        .unwrap();
+
+        assert!(report.imported.is_empty());
+        assert_eq!(report.unmapped.len(), 2);
+    }
+}
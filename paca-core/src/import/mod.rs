@@ -0,0 +1,370 @@
+//! Bringing models that other local tools already downloaded into the hub
+//! cache, so switching to llama.cpp does not mean downloading them again.
+//! Stores that stay in use are linked from; llama.cpp's own cache, which
+//! the hub cache replaces, is moved from.
+
+mod llama_cache;
+mod lmstudio;
+mod ollama;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::cache::HubLayout;
+use crate::download::create_snapshot_symlink;
+use crate::env;
+use crate::error::PacaError;
+use crate::model::ModelRef;
+use crate::registry::Origin;
+
+pub use llama_cache::migrate_llama_cache;
+
+/// A local model store `paca import` understands.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImportSource {
+    LmStudio,
+    Ollama,
+}
+
+impl ImportSource {
+    /// Where the store lives when no directory is given.
+    pub fn default_dir(self) -> Result<PathBuf, PacaError> {
+        match self {
+            Self::LmStudio => env::lmstudio_models(),
+            Self::Ollama => env::ollama_models(),
+        }
+    }
+}
+
+impl FromStr for ImportSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "lmstudio" | "lm-studio" => Ok(Self::LmStudio),
+            "ollama" => Ok(Self::Ollama),
+            _ => Err(format!(
+                "unknown source '{s}' (expected ollama or lmstudio)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ImportSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::LmStudio => "lmstudio",
+            Self::Ollama => "ollama",
+        })
+    }
+}
+
+/// How a blob came to be in the cache.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkMethod {
+    /// The cache already held the blob, e.g. from an earlier download.
+    Existing,
+    Hardlink,
+    Reflink,
+    Copy,
+    /// Renamed into the cache, leaving nothing behind in the source.
+    Move,
+}
+
+impl fmt::Display for LinkMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Existing => "already cached",
+            Self::Hardlink => "hardlink",
+            Self::Reflink => "reflink",
+            Self::Copy => "copy",
+            Self::Move => "moved",
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ImportedFile {
+    pub model_ref: ModelRef,
+    /// The snapshot symlink the file is now reachable through.
+    pub path: PathBuf,
+    pub method: LinkMethod,
+}
+
+/// A model in the source store that could not be imported.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct UnmappedModel {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub imported: Vec<ImportedFile>,
+    pub unmapped: Vec<UnmappedModel>,
+}
+
+impl ImportReport {
+    fn sort(&mut self) {
+        self.imported.sort_by(|a, b| a.path.cmp(&b.path));
+        self.unmapped.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    fn unmapped(&mut self, path: &Path, reason: impl fmt::Display) {
+        self.unmapped.push(UnmappedModel {
+            path: path.to_path_buf(),
+            reason: reason.to_string(),
+        });
+    }
+}
+
+/// Whether the source keeps its files after they reach the cache.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Transfer {
+    Link,
+    Move,
+}
+
+/// One model found in a source store, already mapped onto the hub layout.
+struct Candidate {
+    model_ref: ModelRef,
+    /// The snapshot to link into unless the cache already records one.
+    commit_hash: String,
+    files: Vec<CandidateFile>,
+    /// What to blame in the report if installing fails.
+    source: PathBuf,
+    /// What the cache records the repo as coming from.
+    origin: Origin,
+}
+
+struct CandidateFile {
+    source: PathBuf,
+    blob_hash: String,
+    /// Snapshot-relative, in the layout `list` and `rm` read tags from.
+    filename: String,
+}
+
+/// Imports every model in `source`'s store into the hub cache. Models are
+/// mapped independently, so one the hub layout cannot express is reported
+/// in [`ImportReport::unmapped`] without stopping the rest.
+///
+/// `source_dir` overrides where the store is looked for.
+pub fn import_models(
+    source: ImportSource,
+    source_dir: Option<PathBuf>,
+    hub_dir: Option<PathBuf>,
+) -> Result<ImportReport, PacaError> {
+    let source_dir = match source_dir {
+        Some(dir) => dir,
+        None => source.default_dir()?,
+    };
+    if !source_dir.is_dir() {
+        return Err(PacaError::CacheDir(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{source} store not found at {}", source_dir.display()),
+        )));
+    }
+
+    let hub = HubLayout::open(hub_dir)?;
+    let mut report = ImportReport::default();
+    let candidates = match source {
+        ImportSource::LmStudio => lmstudio::candidates(&source_dir, &hub, &mut report)?,
+        ImportSource::Ollama => ollama::candidates(&source_dir, &mut report)?,
+    };
+
+    for candidate in candidates {
+        match install(&hub, &candidate, Transfer::Link) {
+            Ok(files) => report.imported.extend(files),
+            Err(e) => report.unmapped(&candidate.source, e),
+        }
+    }
+
+    report.sort();
+    Ok(report)
+}
+
+/// Places a candidate's blobs and links its files into the snapshot,
+/// returning them in the order of [`Candidate::files`].
+fn install(
+    hub: &HubLayout,
+    candidate: &Candidate,
+    transfer: Transfer,
+) -> Result<Vec<ImportedFile>, PacaError> {
+    let paths = hub.model(&candidate.model_ref)?;
+    paths.check_origin(&candidate.origin)?;
+    fs::create_dir_all(paths.blobs()).map_err(PacaError::CacheDir)?;
+    paths.save_origin(&candidate.origin)?;
+
+    // Joining the snapshot `refs/main` already names keeps every tag of
+    // the repository visible, where a second snapshot would orphan one.
+    let recorded = paths.read_ref().map(|commit| commit.trim().to_string());
+    let commit_hash = recorded
+        .clone()
+        .unwrap_or_else(|| candidate.commit_hash.clone());
+
+    let mut imported = Vec::new();
+    for file in &candidate.files {
+        let blob = paths.blob(&file.blob_hash)?;
+        let method = match (blob.exists(), transfer) {
+            (true, Transfer::Link) => LinkMethod::Existing,
+            (true, Transfer::Move) => {
+                fs::remove_file(&file.source).map_err(PacaError::FileDelete)?;
+                LinkMethod::Existing
+            }
+            (false, Transfer::Link) => place_blob(&file.source, &blob)?,
+            (false, Transfer::Move) => move_blob(&file.source, &blob)?,
+        };
+        let path = create_snapshot_symlink(&paths, &commit_hash, &file.filename, &file.blob_hash)?;
+        imported.push(ImportedFile {
+            model_ref: candidate.model_ref.clone(),
+            path,
+            method,
+        });
+    }
+
+    if recorded.is_none() {
+        paths.save_ref(&commit_hash)?;
+    }
+    Ok(imported)
+}
+
+/// Puts `source`'s content at `blob` without duplicating it on disk where
+/// the filesystem allows: a hardlink on the same filesystem, else a
+/// copy-on-write clone, else a plain copy.
+fn place_blob(source: &Path, blob: &Path) -> Result<LinkMethod, PacaError> {
+    if fs::hard_link(source, blob).is_ok() {
+        return Ok(LinkMethod::Hardlink);
+    }
+
+    // Clone or copy to the blob's `.partial` name and rename, so an
+    // interrupted import leaves what `clean` (or a resumed download)
+    // expects rather than a truncated blob under its final name.
+    let staging = blob.with_extension("partial");
+    let method = if reflink(source, &staging).is_ok() {
+        LinkMethod::Reflink
+    } else {
+        fs::copy(source, &staging).map_err(PacaError::FileWrite)?;
+        LinkMethod::Copy
+    };
+    fs::rename(&staging, blob).map_err(PacaError::FileWrite)?;
+    Ok(method)
+}
+
+/// Renames `source` into the cache, falling back to [`place_blob`] and
+/// deleting the source when the two sit on different filesystems.
+fn move_blob(source: &Path, blob: &Path) -> Result<LinkMethod, PacaError> {
+    if fs::rename(source, blob).is_ok() {
+        return Ok(LinkMethod::Move);
+    }
+
+    place_blob(source, blob)?;
+    fs::remove_file(source).map_err(PacaError::FileDelete)?;
+    Ok(LinkMethod::Move)
+}
+
+/// The entries of `dir`, sorted so reports come out in a stable order. A
+/// missing directory is simply empty.
+fn entries(dir: &Path) -> Result<Vec<PathBuf>, PacaError> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut paths = fs::read_dir(dir)
+        .map_err(PacaError::CacheDir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(PacaError::CacheDir)?;
+    paths.sort();
+    Ok(paths)
+}
+
+fn subdirs(dir: &Path) -> Result<Vec<PathBuf>, PacaError> {
+    Ok(entries(dir)?.into_iter().filter(|p| p.is_dir()).collect())
+}
+
+#[cfg(target_os = "linux")]
+fn reflink(source: &Path, target: &Path) -> io::Result<()> {
+    use std::fs::File;
+    use std::os::fd::AsRawFd;
+
+    let source = File::open(source)?;
+    let target_file = File::create(target)?;
+    // SAFETY: both descriptors stay open for the duration of the call.
+    let result = unsafe { libc::ioctl(target_file.as_raw_fd(), libc::FICLONE, source.as_raw_fd()) };
+    if result == 0 {
+        return Ok(());
+    }
+
+    let error = io::Error::last_os_error();
+    drop(target_file);
+    let _ = fs::remove_file(target);
+    Err(error)
+}
+
+#[cfg(target_os = "macos")]
+fn reflink(source: &Path, target: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = |path: &Path| {
+        CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    };
+    let (source, target) = (c_path(source)?, c_path(target)?);
+
+    // SAFETY: both paths are NUL-terminated and outlive the call.
+    if unsafe { libc::clonefile(source.as_ptr(), target.as_ptr(), 0) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn reflink(_source: &Path, _target: &Path) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_source_parses_both_spellings_of_lm_studio() {
+        assert_eq!("lmstudio".parse(), Ok(ImportSource::LmStudio));
+        assert_eq!("LM-Studio".parse(), Ok(ImportSource::LmStudio));
+        assert_eq!("ollama".parse(), Ok(ImportSource::Ollama));
+        assert!("gpt4all".parse::<ImportSource>().is_err());
+    }
+
+    #[test]
+    fn place_blob_shares_the_source_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source.gguf");
+        let blob = dir.path().join("blob");
+        fs::write(&source, b"gguf").unwrap();
+
+        let method = place_blob(&source, &blob).unwrap();
+
+        assert_eq!(method, LinkMethod::Hardlink);
+        assert_eq!(fs::read(&blob).unwrap(), b"gguf");
+        assert!(source.exists());
+    }
+
+    #[test]
+    fn import_fails_when_the_store_is_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = import_models(
+            ImportSource::Ollama,
+            Some(dir.path().join("absent")),
+            Some(dir.path().join("hub")),
+        );
+
+        assert!(matches!(result, Err(PacaError::CacheDir(_))));
+    }
+}
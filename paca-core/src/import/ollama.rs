@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::{ModelRefError, PacaError};
+use crate::import::{Candidate, CandidateFile, ImportReport, entries, subdirs};
+use crate::model::ModelRef;
+use crate::registry::Origin;
+use crate::registry::oci::{base_for_host, commit_hash, stored_layer_files};
+
+/// Maps an Ollama store onto the hub layout. Each manifest under
+/// `manifests/<host>/<namespace>/<model>/<tag>` becomes
+/// `<namespace>/<model>:<tag>`, laid out exactly as `paca download
+/// oci://<host>/<namespace>/<model>:<tag>` would, so a later download of
+/// the same tag finds every blob already present.
+pub(super) fn candidates(
+    store: &Path,
+    report: &mut ImportReport,
+) -> Result<Vec<Candidate>, PacaError> {
+    let mut candidates = Vec::new();
+
+    for host in subdirs(&store.join("manifests"))? {
+        for namespace in subdirs(&host)? {
+            for model in subdirs(&namespace)? {
+                for tag in entries(&model)? {
+                    if !tag.is_file() {
+                        continue;
+                    }
+                    match candidate(store, &host, &tag) {
+                        Ok(candidate) => candidates.push(candidate),
+                        Err(reason) => report.unmapped(&tag, reason),
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// Fails with the reason the manifest cannot be imported.
+fn candidate(store: &Path, host: &Path, manifest: &Path) -> Result<Candidate, String> {
+    let name = |path: &Path| {
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    };
+    let model = manifest.parent().unwrap_or(manifest);
+    let namespace = model.parent().unwrap_or(model);
+
+    let model_ref: ModelRef = format!("{}/{}:{}", name(namespace), name(model), name(manifest))
+        .parse()
+        .map_err(|e: ModelRefError| e.to_string())?;
+    let contents = fs::read_to_string(manifest).map_err(|e| e.to_string())?;
+    let layers = stored_layer_files(&model_ref, &contents).map_err(|e| e.to_string())?;
+
+    let mut files = Vec::new();
+    for layer in layers {
+        let source = store.join("blobs").join(format!("sha256-{}", layer.sha256));
+        if !source.is_file() {
+            return Err(format!("blob sha256-{} is missing", layer.sha256));
+        }
+        files.push(CandidateFile {
+            source,
+            blob_hash: layer.sha256,
+            filename: layer.filename,
+        });
+    }
+
+    let base = base_for_host(&name(host));
+    Ok(Candidate {
+        commit_hash: commit_hash(&base, &model_ref),
+        model_ref,
+        files,
+        source: manifest.to_path_buf(),
+        origin: Origin::Oci(base),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{ImportSource, LinkMethod, import_models};
+    use crate::registry::sha256_hex;
+
+    fn digest(byte: char) -> String {
+        byte.to_string().repeat(64)
+    }
+
+    fn write_store(store: &Path, tag: &str, manifest: &str) {
+        let dir = store.join("manifests/registry.ollama.ai/library/gemma3");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(tag), manifest).unwrap();
+        fs::create_dir_all(store.join("blobs")).unwrap();
+    }
+
+    fn manifest(layers: &[(&str, String)]) -> String {
+        let layers: Vec<String> = layers
+            .iter()
+            .map(|(media_type, hex)| {
+                format!(r#"{{"mediaType": "{media_type}", "digest": "sha256:{hex}", "size": 4}}"#)
+            })
+            .collect();
+        format!(
+            r#"{{"schemaVersion": 2, "layers": [{}]}}"#,
+            layers.join(",")
+        )
+    }
+
+    #[test]
+    fn imports_the_weights_and_projector_of_a_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("ollama");
+        write_store(
+            &store,
+            "4b",
+            &manifest(&[
+                ("application/vnd.ollama.image.model", digest('a')),
+                ("application/vnd.ollama.image.projector", digest('b')),
+                ("application/vnd.ollama.image.template", digest('c')),
+            ]),
+        );
+        for byte in ['a', 'b'] {
+            fs::write(
+                store.join(format!("blobs/sha256-{}", digest(byte))),
+                b"gguf",
+            )
+            .unwrap();
+        }
+
+        let hub = dir.path().join("hub");
+        let report = import_models(ImportSource::Ollama, Some(store), Some(hub.clone())).unwrap();
+
+        assert!(report.unmapped.is_empty());
+        let commit = sha256_hex("https://registry.ollama.ai/library/gemma3");
+        let snapshot = hub.join(format!("models--library--gemma3/snapshots/{commit}"));
+        let paths: Vec<&Path> = report.imported.iter().map(|f| f.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                snapshot.join("4b/gemma3-4b.gguf"),
+                snapshot.join("4b/mmproj-gemma3-4b.gguf"),
+            ]
+        );
+        assert!(
+            report
+                .imported
+                .iter()
+                .all(|f| f.method == LinkMethod::Hardlink
+                    && f.model_ref.to_string() == "library/gemma3:4b")
+        );
+        assert_eq!(fs::read(paths[0]).unwrap(), b"gguf");
+        assert_eq!(
+            fs::read_to_string(hub.join("models--library--gemma3/refs/main")).unwrap(),
+            commit
+        );
+        assert_eq!(
+            fs::read_to_string(hub.join("models--library--gemma3/origin")).unwrap(),
+            "oci https://registry.ollama.ai\n"
+        );
+    }
+
+    #[test]
+    fn reports_a_tag_whose_blob_is_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("ollama");
+        write_store(
+            &store,
+            "latest",
+            &manifest(&[("application/vnd.ollama.image.model", digest('a'))]),
+        );
+
+        let report = import_models(
+            ImportSource::Ollama,
+            Some(store.clone()),
+            Some(dir.path().join("hub")),
+        )
+        .unwrap();
+
+        assert!(report.imported.is_empty());
+        assert_eq!(report.unmapped.len(), 1);
+        assert!(report.unmapped[0].path.ends_with("gemma3/latest"));
+        assert!(report.unmapped[0].reason.contains("missing"));
+    }
+
+    #[test]
+    fn reports_a_manifest_without_gguf_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("ollama");
+        write_store(
+            &store,
+            "latest",
+            &manifest(&[("application/vnd.ollama.image.license", digest('a'))]),
+        );
+
+        let report = import_models(
+            ImportSource::Ollama,
+            Some(store),
+            Some(dir.path().join("hub")),
+        )
+        .unwrap();
+
+        assert!(report.imported.is_empty());
+        assert!(report.unmapped[0].reason.contains("no GGUF layer"));
+    }
+}
@@ -1,13 +1,25 @@
 #![cfg_attr(feature = "strict", deny(warnings))]
 
+pub mod auth;
 pub mod cache;
+pub mod client;
+pub mod config;
 pub mod download;
+pub mod env;
 pub mod error;
+pub mod export;
+pub mod http;
+pub mod import;
+pub mod llama;
 pub mod model;
 pub(crate) mod path;
 pub mod progress;
-pub(crate) mod registry;
+pub mod registry;
+pub mod run;
+pub mod serve;
 pub mod sysinfo;
 
 #[cfg(test)]
 pub(crate) mod test_support;
+
+pub use client::{Paca, PacaBuilder};
@@ -0,0 +1,229 @@
+//! llama.cpp's own download cache (`LLAMA_CACHE`), filled from the hub
+//! cache so that `llama-server -hf <ref> --offline` starts without
+//! downloading anything.
+//!
+//! llama.cpp resolves `-hf owner/repo:tag` through its cached copy of the
+//! registry's manifest, `manifest=owner_repo=tag.json`, and then loads
+//! every file from `owner_repo_<path>`, with each `/` flattened to `_`.
+//! paca writes the former and symlinks the latter into the snapshot.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::cache::{HubLayout, installed_tag_files, is_mmproj};
+use crate::env;
+use crate::error::PacaError;
+use crate::model::ModelRef;
+use crate::path::join_child;
+
+/// Links an installed tag into llama.cpp's cache (`LLAMA_CACHE`, unless
+/// `llama_cache` is given). There is no registry response to hand, so the
+/// manifest is rebuilt from the files in the snapshot.
+///
+/// Returns the links and the manifest written.
+pub fn link_model(
+    model: &str,
+    hub_dir: Option<PathBuf>,
+    llama_cache: Option<PathBuf>,
+) -> Result<Vec<PathBuf>, PacaError> {
+    let model_ref: ModelRef = model.parse()?;
+    let hub = HubLayout::open(hub_dir)?;
+    let files = installed_tag_files(&hub, &model_ref)?;
+
+    let mut relatives = files.model;
+    relatives.extend(files.mmproj);
+    let manifest = synthesized_manifest(&relatives, None);
+
+    let llama_cache = match llama_cache {
+        Some(dir) => dir,
+        None => env::llama_cache()?,
+    };
+    write_cache(
+        &llama_cache,
+        &model_ref,
+        &files.snapshot,
+        &relatives,
+        Some(&manifest),
+        &[model_ref.tag.as_str()],
+    )
+}
+
+/// A manifest naming the files llama.cpp reads from one: the model file,
+/// `primary`, else the first file that is not a projector, and the
+/// projector if there is one.
+pub(crate) fn synthesized_manifest(files: &[String], primary: Option<&str>) -> serde_json::Value {
+    let primary = primary.or_else(|| {
+        files
+            .iter()
+            .find(|file| !is_mmproj(file))
+            .map(String::as_str)
+    });
+    let mmproj = files.iter().find(|file| is_mmproj(file));
+
+    let mut manifest = json!({});
+    if let Some(primary) = primary {
+        manifest["ggufFile"] = json!({ "rfilename": primary });
+    }
+    if let Some(mmproj) = mmproj {
+        manifest["mmprojFile"] = json!({ "rfilename": mmproj });
+    }
+    manifest
+}
+
+/// Symlinks each snapshot-relative file of `model_ref` into `llama_cache`
+/// and, given a manifest, caches it under each of `tags`. A file llama.cpp
+/// downloaded itself is left in place.
+pub(crate) fn write_cache(
+    llama_cache: &Path,
+    model_ref: &ModelRef,
+    snapshot: &Path,
+    files: &[String],
+    manifest: Option<&serde_json::Value>,
+    tags: &[&str],
+) -> Result<Vec<PathBuf>, PacaError> {
+    fs::create_dir_all(llama_cache).map_err(PacaError::CacheDir)?;
+    // The links must survive a relative `--hub-dir`.
+    let snapshot = std::path::absolute(snapshot).map_err(PacaError::CacheDir)?;
+    let mut written = Vec::new();
+
+    for relative in files {
+        let link = join_child(llama_cache, &cache_file_name(model_ref, relative))?;
+        match link.symlink_metadata() {
+            Ok(metadata) if !metadata.is_symlink() => continue,
+            Ok(_) => fs::remove_file(&link).map_err(PacaError::FileDelete)?,
+            Err(_) => {}
+        }
+        std::os::unix::fs::symlink(snapshot.join(relative), &link).map_err(PacaError::Symlink)?;
+        written.push(link);
+    }
+
+    if let Some(manifest) = manifest {
+        let contents = serde_json::to_vec(manifest)?;
+        for tag in tags {
+            let path = join_child(llama_cache, &manifest_file_name(model_ref, tag))?;
+            fs::write(&path, &contents).map_err(PacaError::FileWrite)?;
+            written.push(path);
+        }
+    }
+
+    Ok(written)
+}
+
+fn cache_file_name(model_ref: &ModelRef, relative: &str) -> String {
+    format!("{}_{relative}", model_ref.repo()).replace('/', "_")
+}
+
+fn manifest_file_name(model_ref: &ModelRef, tag: &str) -> String {
+    format!("manifest={}={tag}.json", model_ref.repo()).replace('/', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install(hub: &Path, commit: &str, files: &[&str]) {
+        let model_dir = hub.join("models--owner--Model-GGUF");
+        fs::create_dir_all(model_dir.join("refs")).unwrap();
+        fs::write(model_dir.join("refs/main"), commit).unwrap();
+        for file in files {
+            let path = model_dir.join("snapshots").join(commit).join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file.as_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn cache_names_flatten_the_repo_and_path() {
+        let model_ref: ModelRef = "owner/Model-GGUF:Q8_0".parse().unwrap();
+        assert_eq!(
+            cache_file_name(&model_ref, "Q8_0/Model-Q8_0-00001-of-00002.gguf"),
+            "owner_Model-GGUF_Q8_0_Model-Q8_0-00001-of-00002.gguf"
+        );
+        assert_eq!(
+            manifest_file_name(&model_ref, "Q8_0"),
+            "manifest=owner_Model-GGUF=Q8_0.json"
+        );
+    }
+
+    #[test]
+    fn synthesized_manifest_names_the_model_and_projector() {
+        let files = vec![
+            "Q8_0/Model-Q8_0-00001-of-00002.gguf".to_string(),
+            "Q8_0/Model-Q8_0-00002-of-00002.gguf".to_string(),
+            "mmproj-F16.gguf".to_string(),
+        ];
+        assert_eq!(
+            synthesized_manifest(&files, None),
+            json!({
+                "ggufFile": {"rfilename": "Q8_0/Model-Q8_0-00001-of-00002.gguf"},
+                "mmprojFile": {"rfilename": "mmproj-F16.gguf"},
+            })
+        );
+    }
+
+    #[test]
+    fn link_model_links_a_cached_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let hub = dir.path().join("hub");
+        install(
+            &hub,
+            "abc123",
+            &["Model-Q4_K_M.gguf", "Model-Q8_0.gguf", "mmproj-F16.gguf"],
+        );
+        let llama_cache = dir.path().join("llama.cpp");
+
+        let written = link_model(
+            "owner/Model-GGUF:q4_k_m",
+            Some(hub),
+            Some(llama_cache.clone()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            written,
+            vec![
+                llama_cache.join("owner_Model-GGUF_Model-Q4_K_M.gguf"),
+                llama_cache.join("owner_Model-GGUF_mmproj-F16.gguf"),
+                llama_cache.join("manifest=owner_Model-GGUF=q4_k_m.json"),
+            ]
+        );
+        assert_eq!(fs::read(&written[0]).unwrap(), b"Model-Q4_K_M.gguf");
+        let manifest: serde_json::Value =
+            serde_json::from_slice(&fs::read(&written[2]).unwrap()).unwrap();
+        assert_eq!(manifest["ggufFile"]["rfilename"], "Model-Q4_K_M.gguf");
+        assert_eq!(manifest["mmprojFile"]["rfilename"], "mmproj-F16.gguf");
+    }
+
+    #[test]
+    fn link_model_leaves_llama_downloads_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let hub = dir.path().join("hub");
+        install(&hub, "abc123", &["Model-Q4_K_M.gguf"]);
+        let llama_cache = dir.path().join("llama.cpp");
+        fs::create_dir_all(&llama_cache).unwrap();
+        let own = llama_cache.join("owner_Model-GGUF_Model-Q4_K_M.gguf");
+        fs::write(&own, b"llama's own").unwrap();
+
+        let written = link_model("owner/Model-GGUF:Q4_K_M", Some(hub), Some(llama_cache)).unwrap();
+
+        assert_eq!(written.len(), 1);
+        assert_eq!(fs::read(own).unwrap(), b"llama's own");
+    }
+
+    #[test]
+    fn link_model_fails_for_a_missing_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let hub = dir.path().join("hub");
+        install(&hub, "abc123", &["Model-Q4_K_M.gguf"]);
+
+        let result = link_model(
+            "owner/Model-GGUF:Q8_0",
+            Some(hub),
+            Some(dir.path().join("llama.cpp")),
+        );
+
+        assert!(matches!(result, Err(PacaError::TagNotFound { .. })));
+    }
+}
@@ -1,16 +1,51 @@
-use std::fmt;
+use std::cmp::Ordering;
+use std::fmt::{self, Write as _};
 use std::str::FromStr;
 
+use serde::{Deserialize, Serialize};
+
 use crate::error::ModelRefError;
+use crate::registry::endpoint::model_endpoints;
+
+/// The tag requested when a reference omits one. The registry answers it
+/// with the repo's default quantization, which is what `llama-server -hf
+/// owner/model` loads.
+pub const DEFAULT_TAG: &str = "latest";
+
+/// The revision every reference without an `@revision` resolves against.
+pub const DEFAULT_REVISION: &str = "main";
+
+/// Longest owner or repository name the hub accepts.
+const MAX_NAME_LEN: usize = 96;
 
-/// Represents a model reference in the format owner/model:tag
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// Represents a model reference.
+///
+/// Accepted forms, all normalised to the same fields:
+///
+/// - `owner/model:tag`, optionally `owner/model@revision:tag`
+/// - `owner/model/path/to/file.gguf` for exactly one named file
+/// - `hf://` followed by either of the above
+/// - hub URLs: `https://huggingface.co/owner/model`, and its `/blob/`,
+///   `/resolve/` and `/tree/` pages, on `huggingface.co`, `hf.co` or a
+///   configured endpoint
+///
+/// Serialises as its [`Display`](fmt::Display) form, so it round-trips
+/// through config files as a plain string.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+#[serde(into = "String", try_from = "String")]
 pub struct ModelRef {
+    /// A single file within the repository, when the reference names one
+    /// instead of a tag
+    pub file: Option<String>,
     /// The model identifier within the repository
     pub model: String,
     /// The repository owner on HuggingFace
     pub owner: String,
-    /// The specific tag/version of the model
+    /// Branch, tag or commit to resolve files against; `None` means
+    /// [`DEFAULT_REVISION`]
+    pub revision: Option<String>,
+    /// The specific tag/version of the model. For file references, the
+    /// tag that file belongs to.
     pub tag: String,
 }
 
@@ -18,28 +53,397 @@ impl FromStr for ModelRef {
     type Err = ModelRefError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (repo, tag) = s.split_once(':').ok_or(ModelRefError::MissingTag)?;
-        let (owner, model) = repo.split_once('/').ok_or(ModelRefError::MissingOwner)?;
-
-        Ok(Self {
-            model: model.to_string(),
-            owner: owner.to_string(),
-            tag: tag.to_string(),
-        })
+        parse(s, None)
     }
 }
 
 impl fmt::Display for ModelRef {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}/{}:{}", self.owner, self.model, self.tag)
+        write!(f, "{}/{}", self.owner, self.model)?;
+        if let Some(revision) = &self.revision {
+            // Slashes would read as a file path on the way back in.
+            write!(f, "@{}", percent_encode(revision, &['/', ':']))?;
+        }
+        match &self.file {
+            Some(file) => write!(f, "/{}", percent_encode(file, &[':'])),
+            None => write!(f, ":{}", self.tag),
+        }
+    }
+}
+
+impl From<ModelRef> for String {
+    fn from(model_ref: ModelRef) -> Self {
+        model_ref.to_string()
+    }
+}
+
+impl TryFrom<String> for ModelRef {
+    type Error = ModelRefError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Orders by repo, then tag, so sorted references read like `paca list`.
+impl Ord for ModelRef {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (
+            &self.owner,
+            &self.model,
+            &self.tag,
+            &self.revision,
+            &self.file,
+        )
+            .cmp(&(
+                &other.owner,
+                &other.model,
+                &other.tag,
+                &other.revision,
+                &other.file,
+            ))
+    }
+}
+
+impl PartialOrd for ModelRef {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 impl ModelRef {
+    /// Parses a reference like [`FromStr`], accepting one without a tag as
+    /// a request for [`DEFAULT_TAG`].
+    pub fn parse_with_default_tag(s: &str) -> Result<Self, ModelRefError> {
+        parse(s, Some(DEFAULT_TAG))
+    }
+
     /// Returns the repository identifier (owner/model)
     pub fn repo(&self) -> String {
         format!("{}/{}", self.owner, self.model)
     }
+
+    /// The revision to resolve files against.
+    pub fn revision(&self) -> &str {
+        self.revision.as_deref().unwrap_or(DEFAULT_REVISION)
+    }
+}
+
+fn parse(s: &str, default_tag: Option<&str>) -> Result<ModelRef, ModelRefError> {
+    let s = s.trim();
+
+    if let Some(rest) = s.strip_prefix("hf://") {
+        return parse_plain(rest, default_tag);
+    }
+    if let Some(rest) = s
+        .strip_prefix("https://")
+        .or_else(|| s.strip_prefix("http://"))
+    {
+        return parse_url(s, rest, default_tag);
+    }
+
+    parse_plain(s, default_tag)
+}
+
+/// `owner/model[@revision][:tag[@revision]]` or
+/// `owner/model[@revision]/path/to/file`.
+fn parse_plain(s: &str, default_tag: Option<&str>) -> Result<ModelRef, ModelRefError> {
+    let (head, tag) = match s.split_once(':') {
+        Some((head, tag)) => (head, Some(tag)),
+        None => (s, None),
+    };
+    let (owner, rest) = head.split_once('/').ok_or(ModelRefError::MissingOwner)?;
+    if owner.is_empty() {
+        return Err(ModelRefError::MissingOwner);
+    }
+    let (repo, file) = match rest.split_once('/') {
+        Some((repo, file)) => (repo, Some(file)),
+        None => (rest, None),
+    };
+    let (model, mut revision) = split_revision(repo)?;
+
+    let tag = match tag {
+        Some(tag) => {
+            let (tag, tag_revision) = split_revision(tag)?;
+            if tag_revision.is_some() {
+                if revision.is_some() {
+                    return Err(ModelRefError::InvalidRevision(s.to_string()));
+                }
+                revision = tag_revision;
+            }
+            Some(tag)
+        }
+        None => None,
+    };
+
+    let file = file
+        .map(|file| percent_decode(file).ok_or_else(|| ModelRefError::InvalidPath(file.into())))
+        .transpose()?;
+    let file = file.as_deref();
+
+    match (file, tag) {
+        (Some(_), Some(_)) => Err(ModelRefError::TagWithFile),
+        (Some(file), None) => build(owner, model, revision, None, Some(file)),
+        (None, Some(tag)) => build(owner, model, revision, Some(tag), None),
+        (None, None) => build(
+            owner,
+            model,
+            revision,
+            Some(default_tag.ok_or(ModelRefError::MissingTag)?),
+            None,
+        ),
+    }
+}
+
+/// A hub page URL: the repo root, or a `blob`/`resolve` file page, or a
+/// `tree` directory page whose first directory is taken as the tag. Only
+/// the hub's own hosts and the configured endpoints count; a page on any
+/// other site names something else.
+fn parse_url(
+    url: &str,
+    without_scheme: &str,
+    default_tag: Option<&str>,
+) -> Result<ModelRef, ModelRefError> {
+    let invalid = || ModelRefError::InvalidUrl(url.to_string());
+
+    let path = without_scheme.split(['?', '#']).next().unwrap_or_default();
+    let (host, path) = path.split_once('/').unwrap_or((path, ""));
+    if !is_hub_host(host) {
+        return Err(invalid());
+    }
+    let segments: Vec<&str> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    let [owner, model, rest @ ..] = segments.as_slice() else {
+        return Err(invalid());
+    };
+
+    match rest {
+        [] => build(
+            owner,
+            model,
+            None,
+            Some(default_tag.ok_or(ModelRefError::MissingTag)?),
+            None,
+        ),
+        ["blob" | "resolve", revision, file @ ..] if !file.is_empty() => build(
+            owner,
+            model,
+            Some(decode_revision(revision)?),
+            None,
+            Some(&file.join("/")),
+        ),
+        ["tree", revision, tag, ..] => build(
+            owner,
+            model,
+            Some(decode_revision(revision)?),
+            Some(tag),
+            None,
+        ),
+        ["tree", revision] => build(
+            owner,
+            model,
+            Some(decode_revision(revision)?),
+            Some(default_tag.ok_or(ModelRefError::MissingTag)?),
+            None,
+        ),
+        _ => Err(invalid()),
+    }
+}
+
+fn is_hub_host(host: &str) -> bool {
+    let endpoints = model_endpoints().iter().filter_map(|endpoint| {
+        let rest = endpoint
+            .split_once("://")
+            .map_or(endpoint.as_str(), |(_, rest)| rest);
+        rest.split('/').next()
+    });
+    ["huggingface.co", "hf.co"]
+        .into_iter()
+        .chain(endpoints)
+        .any(|known| known.eq_ignore_ascii_case(host))
+}
+
+fn split_revision(s: &str) -> Result<(&str, Option<String>), ModelRefError> {
+    match s.split_once('@') {
+        Some((name, revision)) => Ok((name, Some(decode_revision(revision)?))),
+        None => Ok((s, None)),
+    }
+}
+
+/// Revisions such as `refs/pr/1` appear as `refs%2Fpr%2F1` in hub URLs.
+fn decode_revision(revision: &str) -> Result<String, ModelRefError> {
+    percent_decode(revision).ok_or_else(|| ModelRefError::InvalidRevision(revision.to_string()))
+}
+
+fn build(
+    owner: &str,
+    model: &str,
+    revision: Option<String>,
+    tag: Option<&str>,
+    file: Option<&str>,
+) -> Result<ModelRef, ModelRefError> {
+    validate_name(owner)?;
+    validate_name(model)?;
+    if let Some(file) = file {
+        validate_file(file)?;
+    }
+
+    let revision = match revision {
+        Some(revision) if revision.is_empty() => {
+            return Err(ModelRefError::InvalidRevision(revision));
+        }
+        // Spelling out the default is the same reference as omitting it.
+        Some(revision) if revision == DEFAULT_REVISION => None,
+        revision => revision,
+    };
+
+    let tag = match (tag, file) {
+        (Some(tag), _) => tag.to_string(),
+        (None, Some(file)) => file_tag(file, model),
+        (None, None) => return Err(ModelRefError::MissingTag),
+    };
+    if tag.is_empty() {
+        return Err(ModelRefError::MissingTag);
+    }
+
+    Ok(ModelRef {
+        file: file.map(str::to_string),
+        model: model.to_string(),
+        owner: owner.to_string(),
+        revision,
+        tag,
+    })
+}
+
+/// The tag a named file would be listed under, falling back to the bare
+/// filename stem for repos whose names don't follow the `-GGUF` pattern.
+fn file_tag(file: &str, model: &str) -> String {
+    tag_for_path(file, model).unwrap_or_else(|| {
+        let name = file.rsplit('/').next().unwrap_or(file);
+        name.strip_suffix(".gguf").unwrap_or(name).to_string()
+    })
+}
+
+/// The tag a snapshot-relative GGUF path belongs to: its first directory
+/// for the sharded `{tag}/...` layout, otherwise whatever [`derive_tag`]
+/// reads from the filename.
+pub(crate) fn tag_for_path(relative: &str, model: &str) -> Option<String> {
+    match relative.split_once('/') {
+        Some((subdir, _)) => Some(subdir.to_string()),
+        None => derive_tag(relative, model),
+    }
+}
+
+/// Derives a quantization tag from a GGUF filename using the model name.
+///
+/// Strips the model base name prefix (model name without `-GGUF`), the
+/// `.gguf` suffix, and any shard suffix (e.g. `-00001-of-00002`).
+///
+/// Matching ignores case: uploaders routinely lowercase filenames in a
+/// repo whose name is mixed case, or the reverse.
+pub(crate) fn derive_tag(filename: &str, model: &str) -> Option<String> {
+    let stem = strip_suffix_ignore_case(filename, ".gguf")?;
+    let model_base = strip_suffix_ignore_case(model, "-GGUF")?;
+    let remainder = strip_prefix_ignore_case(stem, model_base)?.strip_prefix('-')?;
+
+    if remainder.is_empty() {
+        return None;
+    }
+
+    let tag = if let Some((before, _)) = remainder.rsplit_once("-of-") {
+        before.rsplit_once('-').map_or(before, |(t, _)| t)
+    } else {
+        remainder
+    };
+
+    (!tag.is_empty()).then(|| tag.to_string())
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &s[prefix.len()..])
+}
+
+fn strip_suffix_ignore_case<'a>(s: &'a str, suffix: &str) -> Option<&'a str> {
+    let split = s.len().checked_sub(suffix.len())?;
+    let tail = s.get(split..)?;
+    tail.eq_ignore_ascii_case(suffix).then(|| &s[..split])
+}
+
+/// The hub's naming rules for owners and repositories: word characters,
+/// `-` and `.`, starting and ending with a word character, at most 96
+/// long, with no `--` or `..` and no `.git` suffix.
+fn validate_name(name: &str) -> Result<(), ModelRefError> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.chars().all(|c| is_word(c) || c == '-' || c == '.')
+        && name.starts_with(is_word)
+        && name.ends_with(is_word)
+        && !name.contains("--")
+        && !name.contains("..")
+        && !name.ends_with(".git");
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ModelRefError::InvalidName(name.to_string()))
+    }
+}
+
+/// A file path must stay inside the repo: relative, with no empty, `.` or
+/// `..` segments.
+fn validate_file(file: &str) -> Result<(), ModelRefError> {
+    let valid = !file.starts_with('/')
+        && file
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ModelRefError::InvalidPath(file.to_string()))
+    }
+}
+
+/// Escapes `%` and each of `special` as `%XX`, so that none of them
+/// reads as a separator when [`percent_decode`] takes the text back.
+fn percent_encode(s: &str, special: &[char]) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '%' || special.contains(&c) {
+            // Every character escaped is ASCII.
+            let _ = write!(encoded, "%{:02X}", c as u32);
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+/// Decodes `%XX` escapes, or `None` when one is malformed or the result
+/// is not UTF-8.
+pub(crate) fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
 }
 
 #[cfg(test)]
@@ -73,10 +477,352 @@ mod tests {
         assert!(matches!(result.unwrap_err(), ModelRefError::MissingTag));
     }
 
+    #[test]
+    fn parse_with_default_tag_fills_in_the_default() {
+        let model_ref = ModelRef::parse_with_default_tag("unsloth/GLM-4.7-Flash-GGUF").unwrap();
+        assert_eq!(model_ref.tag, DEFAULT_TAG);
+    }
+
+    #[test]
+    fn parse_with_default_tag_keeps_an_explicit_tag() {
+        let model_ref =
+            ModelRef::parse_with_default_tag("unsloth/GLM-4.7-Flash-GGUF:Q2_K_XL").unwrap();
+        assert_eq!(model_ref.tag, "Q2_K_XL");
+    }
+
+    #[test]
+    fn parse_with_default_tag_still_requires_an_owner() {
+        let result = ModelRef::parse_with_default_tag("GLM-4.7-Flash-GGUF");
+        assert!(matches!(result, Err(ModelRefError::MissingOwner)));
+    }
+
     #[test]
     fn errors_when_owner_missing() {
         let result = "GLM-4.7-Flash-GGUF:Q2_K_XL".parse::<ModelRef>();
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), ModelRefError::MissingOwner));
     }
+
+    fn parse(s: &str) -> ModelRef {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_a_revision_after_the_repo() {
+        let model_ref = parse("unsloth/Qwen3-1.7B-GGUF@v2:Q4_K_M");
+        assert_eq!(model_ref.revision.as_deref(), Some("v2"));
+        assert_eq!(model_ref.tag, "Q4_K_M");
+        assert_eq!(model_ref.revision(), "v2");
+    }
+
+    #[test]
+    fn parses_a_revision_after_the_tag() {
+        assert_eq!(
+            parse("unsloth/Qwen3-1.7B-GGUF:Q4_K_M@v2"),
+            parse("unsloth/Qwen3-1.7B-GGUF@v2:Q4_K_M")
+        );
+    }
+
+    #[test]
+    fn treats_main_as_no_revision() {
+        assert_eq!(
+            parse("unsloth/Qwen3-1.7B-GGUF@main:Q4_K_M"),
+            parse("unsloth/Qwen3-1.7B-GGUF:Q4_K_M")
+        );
+    }
+
+    #[test]
+    fn errors_on_two_revisions() {
+        let result = "owner/model-GGUF@a:Q4@b".parse::<ModelRef>();
+        assert!(matches!(result, Err(ModelRefError::InvalidRevision(_))));
+    }
+
+    #[test]
+    fn parses_an_explicit_file_path() {
+        let model_ref = parse("unsloth/X-GGUF/Q4_K_M/X-Q4_K_M-00001-of-00003.gguf");
+        assert_eq!(model_ref.repo(), "unsloth/X-GGUF");
+        assert_eq!(
+            model_ref.file.as_deref(),
+            Some("Q4_K_M/X-Q4_K_M-00001-of-00003.gguf")
+        );
+        assert_eq!(model_ref.tag, "Q4_K_M");
+    }
+
+    #[test]
+    fn derives_the_tag_of_a_root_file() {
+        assert_eq!(parse("unsloth/X-GGUF/X-Q8_0.gguf").tag, "Q8_0");
+        assert_eq!(parse("owner/weights/model.gguf").tag, "model");
+    }
+
+    #[test]
+    fn errors_on_a_file_path_with_a_tag() {
+        let result = "owner/model-GGUF/model-Q4.gguf:Q4".parse::<ModelRef>();
+        assert!(matches!(result, Err(ModelRefError::TagWithFile)));
+    }
+
+    #[test]
+    fn rejects_file_paths_that_leave_the_repo() {
+        for bad in [
+            "owner/model/../../victim",
+            "owner/model/a//b.gguf",
+            "owner/model/./b.gguf",
+        ] {
+            assert!(
+                matches!(bad.parse::<ModelRef>(), Err(ModelRefError::InvalidPath(_))),
+                "{bad} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_hf_uris() {
+        assert_eq!(
+            parse("hf://unsloth/X-GGUF:Q4_K_M"),
+            parse("unsloth/X-GGUF:Q4_K_M")
+        );
+        assert_eq!(
+            parse("hf://unsloth/X-GGUF/X-Q4_K_M.gguf"),
+            parse("unsloth/X-GGUF/X-Q4_K_M.gguf")
+        );
+    }
+
+    #[test]
+    fn parses_a_blob_url() {
+        let model_ref = parse(
+            "https://huggingface.co/unsloth/X-GGUF/blob/main/Q4_K_M/X-Q4_K_M-00001-of-00003.gguf",
+        );
+        assert_eq!(model_ref.repo(), "unsloth/X-GGUF");
+        assert_eq!(model_ref.revision, None);
+        assert_eq!(
+            model_ref.file.as_deref(),
+            Some("Q4_K_M/X-Q4_K_M-00001-of-00003.gguf")
+        );
+    }
+
+    #[test]
+    fn parses_a_resolve_url_with_an_encoded_revision() {
+        let model_ref = parse(
+            "https://huggingface.co/unsloth/X-GGUF/resolve/refs%2Fpr%2F1/X-Q8_0.gguf?download=true",
+        );
+        assert_eq!(model_ref.revision.as_deref(), Some("refs/pr/1"));
+        assert_eq!(model_ref.file.as_deref(), Some("X-Q8_0.gguf"));
+    }
+
+    #[test]
+    fn parses_a_tree_url_as_a_tag() {
+        let model_ref = parse("https://huggingface.co/unsloth/X-GGUF/tree/main/BF16");
+        assert_eq!(model_ref.tag, "BF16");
+        assert_eq!(model_ref.file, None);
+    }
+
+    #[test]
+    fn parses_a_repo_url_only_with_a_default_tag() {
+        let url = "https://huggingface.co/unsloth/X-GGUF";
+        assert!(matches!(
+            url.parse::<ModelRef>(),
+            Err(ModelRefError::MissingTag)
+        ));
+        assert_eq!(
+            ModelRef::parse_with_default_tag(url).unwrap().tag,
+            DEFAULT_TAG
+        );
+    }
+
+    #[test]
+    fn errors_on_an_unrecognised_url() {
+        let result = "https://huggingface.co/unsloth".parse::<ModelRef>();
+        assert!(matches!(result, Err(ModelRefError::InvalidUrl(_))));
+    }
+
+    #[test]
+    fn parses_urls_only_on_the_hubs_hosts() {
+        assert_eq!(
+            parse("https://hf.co/unsloth/X-GGUF/tree/main/BF16"),
+            parse("unsloth/X-GGUF:BF16")
+        );
+        for url in [
+            "https://github.com/unsloth/X-GGUF/tree/main/BF16",
+            "https://huggingface.co.example.com/unsloth/X-GGUF/tree/main/BF16",
+        ] {
+            assert!(
+                matches!(url.parse::<ModelRef>(), Err(ModelRefError::InvalidUrl(_))),
+                "{url} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_names_outside_the_hub_rules() {
+        for bad in [
+            "../x:Q4",
+            "owner/..:Q4",
+            "owner/a--b:Q4",
+            "owner/-model:Q4",
+            "owner/model.:Q4",
+            "owner/model.git:Q4",
+            "own er/model:Q4",
+        ] {
+            assert!(
+                matches!(bad.parse::<ModelRef>(), Err(ModelRefError::InvalidName(_))),
+                "{bad} should be rejected"
+            );
+        }
+        let too_long = format!("owner/{}:Q4", "a".repeat(97));
+        assert!(too_long.parse::<ModelRef>().is_err());
+    }
+
+    #[test]
+    fn accepts_names_within_the_hub_rules() {
+        parse("unsloth/Qwen3.5-0.8B-GGUF:Q4");
+        parse("my_org/model_v1.2:Q4");
+    }
+
+    #[test]
+    fn errors_on_a_malformed_escape_in_a_revision() {
+        for bad in ["owner/model-GGUF@refs%2:Q4", "owner/model-GGUF@%ff:Q4"] {
+            assert!(
+                matches!(
+                    bad.parse::<ModelRef>(),
+                    Err(ModelRefError::InvalidRevision(_))
+                ),
+                "{bad} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn percent_decode_rejects_what_does_not_decode() {
+        assert_eq!(
+            percent_decode("refs%2Fpr%2F1").as_deref(),
+            Some("refs/pr/1")
+        );
+        assert_eq!(percent_decode("a%2"), None);
+        assert_eq!(percent_decode("a%zz"), None);
+        assert_eq!(percent_decode("%ff"), None);
+    }
+
+    #[test]
+    fn display_round_trips_every_form() {
+        for input in [
+            "owner/model-GGUF:Q4",
+            "owner/model-GGUF@v1:Q4",
+            "owner/model-GGUF@refs%2Fpr%2F1:Q4",
+            "owner/model-GGUF/BF16/model-BF16-00001-of-00002.gguf",
+            "owner/model-GGUF@abc123/model-Q4.gguf",
+            "owner/model-GGUF@50%25off:Q4",
+            "owner/model-GGUF@v1%3Ar2:Q4",
+            "owner/model-GGUF/model%3AQ4.gguf",
+        ] {
+            assert_eq!(parse(input).to_string(), input);
+            assert_eq!(parse(&parse(input).to_string()), parse(input));
+        }
+    }
+
+    #[test]
+    fn serialises_as_a_string() {
+        let model_ref = parse("owner/model-GGUF@v1:Q4");
+        let json = serde_json::to_string(&model_ref).unwrap();
+        assert_eq!(json, r#""owner/model-GGUF@v1:Q4""#);
+        assert_eq!(serde_json::from_str::<ModelRef>(&json).unwrap(), model_ref);
+        assert!(serde_json::from_str::<ModelRef>(r#""nope""#).is_err());
+    }
+
+    #[test]
+    fn serialises_separators_in_files_and_revisions() {
+        let with_colon = parse("owner/model-GGUF/model%3AQ4.gguf");
+        assert_eq!(with_colon.file.as_deref(), Some("model:Q4.gguf"));
+        let mut with_percent = parse("owner/model-GGUF:Q4");
+        with_percent.revision = Some("50%off".to_string());
+
+        for model_ref in [with_colon, with_percent] {
+            let json = serde_json::to_string(&model_ref).unwrap();
+            assert_eq!(serde_json::from_str::<ModelRef>(&json).unwrap(), model_ref);
+        }
+    }
+
+    #[test]
+    fn orders_by_repo_then_tag_and_works_as_a_map_key() {
+        let mut refs = vec![
+            parse("b/model:Q4"),
+            parse("a/model:Q8"),
+            parse("a/model:Q4"),
+        ];
+        refs.sort();
+        assert_eq!(
+            refs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["a/model:Q4", "a/model:Q8", "b/model:Q4"]
+        );
+
+        let set: std::collections::HashSet<ModelRef> = refs.into_iter().collect();
+        assert!(set.contains(&parse("a/model:Q8")));
+    }
+
+    #[test]
+    fn derive_tag_strips_model_base_and_gguf_suffix() {
+        assert_eq!(
+            derive_tag(
+                "Phi-4-mini-reasoning-BF16.gguf",
+                "Phi-4-mini-reasoning-GGUF"
+            ),
+            Some("BF16".to_string())
+        );
+    }
+
+    #[test]
+    fn derive_tag_handles_quantization_with_underscores() {
+        assert_eq!(
+            derive_tag("Qwen3-1.7B-Q4_K_M.gguf", "Qwen3-1.7B-GGUF"),
+            Some("Q4_K_M".to_string())
+        );
+    }
+
+    #[test]
+    fn derive_tag_handles_ud_prefix_quantization() {
+        assert_eq!(
+            derive_tag("Qwen3.5-0.8B-UD-Q2_K_XL.gguf", "Qwen3.5-0.8B-GGUF"),
+            Some("UD-Q2_K_XL".to_string())
+        );
+    }
+
+    #[test]
+    fn derive_tag_strips_shard_suffix() {
+        assert_eq!(
+            derive_tag("Model-BF16-00001-of-00002.gguf", "Model-GGUF"),
+            Some("BF16".to_string())
+        );
+    }
+
+    #[test]
+    fn derive_tag_ignores_case_in_the_model_base() {
+        assert_eq!(
+            derive_tag("qwen3-1.7b-Q4_K_M.GGUF", "Qwen3-1.7B-gguf"),
+            Some("Q4_K_M".to_string())
+        );
+    }
+
+    #[test]
+    fn derive_tag_returns_none_for_non_matching_prefix() {
+        assert_eq!(derive_tag("mmproj-BF16.gguf", "Qwen3.5-0.8B-GGUF"), None);
+    }
+
+    #[test]
+    fn tag_for_path_uses_the_subdirectory_for_sharded_files() {
+        assert_eq!(
+            tag_for_path("BF16/Model-BF16-00001-of-00002.gguf", "Model-GGUF"),
+            Some("BF16".to_string())
+        );
+    }
+
+    #[test]
+    fn tag_for_path_derives_from_root_filenames() {
+        assert_eq!(
+            tag_for_path("Model-Q4_K_M.gguf", "Model-GGUF"),
+            Some("Q4_K_M".to_string())
+        );
+    }
+
+    #[test]
+    fn derive_tag_returns_none_for_non_gguf_file() {
+        assert_eq!(derive_tag("readme.txt", "Model-GGUF"), None);
+    }
 }
@@ -1,3 +1,8 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
 /// Per-file download progress reporter. CLI crates provide the concrete
 /// rendering; paca-core depends only on this abstraction so nothing in
 /// the core speaks indicatif (or any other UI library).
@@ -15,4 +20,156 @@ pub trait FileProgress: Send + Sync {
 
     /// Mark the download complete.
     fn finish(&self);
+
+    /// A step of the download other than moving bytes. Reporters that
+    /// only show bytes can ignore these; by default retries and mirror
+    /// fallbacks are printed with [`FileProgress::println`], as they were
+    /// before events existed.
+    fn event(&self, event: &ProgressEvent) {
+        if let ProgressEvent::Retrying { .. } | ProgressEvent::MirrorFallback { .. } = event {
+            self.println(&event.to_string());
+        }
+    }
+}
+
+/// What a file's download is doing, for [`FileProgress::event`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ProgressEvent {
+    /// Asking the registry where the file's blob lives.
+    Resolving,
+    /// A parallel download began fetching one of its ranges, counted in
+    /// bytes from the start of the file.
+    ChunkStarted { idx: usize, range: Range<u64> },
+    /// An attempt failed and will be made again after `delay`.
+    Retrying {
+        attempt: u32,
+        delay: Duration,
+        error: String,
+    },
+    /// The server asked for no more requests before `until`; a
+    /// [`ProgressEvent::Retrying`] follows.
+    RateLimited { until: SystemTime },
+    /// The mirror `from` failed or serves another version of the file,
+    /// so the download moves on to `to`, resuming what is on disk.
+    MirrorFallback {
+        from: String,
+        to: String,
+        reason: String,
+    },
+    /// Checking the blob against the digest the registry published.
+    Verifying,
+    /// Every byte is on disk; the blob is being assembled and moved into
+    /// place.
+    Finalizing,
+    /// The blob was already in the cache, so nothing is fetched.
+    Cached,
+}
+
+impl fmt::Display for ProgressEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Resolving => f.write_str("Resolving"),
+            Self::ChunkStarted { idx, range } => write!(
+                f,
+                "Fetching chunk {idx} (bytes {}-{})",
+                range.start,
+                range.end.saturating_sub(1)
+            ),
+            Self::Retrying {
+                attempt,
+                delay,
+                error,
+            } => write!(
+                f,
+                "Download error: {error}. Retrying in {}s (attempt {attempt})...",
+                delay.as_secs()
+            ),
+            Self::RateLimited { until } => {
+                let wait = until.duration_since(SystemTime::now()).unwrap_or_default();
+                write!(f, "Rate limited for {}s", wait.as_secs())
+            }
+            Self::MirrorFallback { from, to, reason } => {
+                write!(f, "{from}: {reason}. Trying {to}...")
+            }
+            Self::Verifying => f.write_str("Verifying"),
+            Self::Finalizing => f.write_str("Finalizing"),
+            Self::Cached => f.write_str("Already cached"),
+        }
+    }
+}
+
+/// Makes a reporter for each file a download fetches, for callers that
+/// hand paca a source of reporters rather than a list matched to the
+/// manifest.
+pub trait ProgressFactory: Send + Sync {
+    fn file_progress(&self, filename: &str, size: u64) -> Arc<dyn FileProgress>;
+}
+
+impl<F> ProgressFactory for F
+where
+    F: Fn(&str, u64) -> Arc<dyn FileProgress> + Send + Sync,
+{
+    fn file_progress(&self, filename: &str, size: u64) -> Arc<dyn FileProgress> {
+        self(filename, size)
+    }
+}
+
+/// Reports nothing; the default for library callers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoProgress;
+
+impl FileProgress for NoProgress {
+    fn start(&self, _: u64) {}
+
+    fn inc(&self, _: u64) {}
+
+    fn println(&self, _: &str) {}
+
+    fn finish(&self) {}
+}
+
+impl ProgressFactory for NoProgress {
+    fn file_progress(&self, _: &str, _: u64) -> Arc<dyn FileProgress> {
+        Arc::new(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Printed(Mutex<Vec<String>>);
+
+    impl FileProgress for Printed {
+        fn start(&self, _: u64) {}
+
+        fn inc(&self, _: u64) {}
+
+        fn println(&self, msg: &str) {
+            self.0.lock().unwrap().push(msg.to_string());
+        }
+
+        fn finish(&self) {}
+    }
+
+    #[test]
+    fn reporters_without_events_still_print_retries() {
+        let progress = Printed::default();
+
+        progress.event(&ProgressEvent::Resolving);
+        progress.event(&ProgressEvent::Retrying {
+            attempt: 2,
+            delay: Duration::from_secs(4),
+            error: "connection reset".to_string(),
+        });
+        progress.event(&ProgressEvent::Cached);
+
+        assert_eq!(
+            *progress.0.lock().unwrap(),
+            ["Download error: connection reset. Retrying in 4s (attempt 2)..."]
+        );
+    }
 }
@@ -4,29 +4,122 @@ use std::sync::OnceLock;
 const DEFAULT_ENDPOINT: &str = "https://huggingface.co";
 
 /// Returns the HuggingFace model endpoint, resolved from `MODEL_ENDPOINT`
-/// or `HF_ENDPOINT` with the default falling back to `huggingface.co`.
+/// or `HF_ENDPOINT`, with the default falling back to `huggingface.co`.
 ///
 /// Cached after the first call; env changes within a single process are
 /// not picked up.
 pub fn model_endpoint() -> &'static str {
     static ENDPOINT: OnceLock<String> = OnceLock::new();
-    ENDPOINT.get_or_init(resolve_endpoint)
+    ENDPOINT.get_or_init(|| resolve_endpoint(&[]))
 }
 
-fn resolve_endpoint() -> String {
+/// Every endpoint to try, in order: the mirrors `PACA_MIRRORS` lists,
+/// comma-separated, then [`model_endpoint`] as the last resort.
+///
+/// Cached after the first call, like [`model_endpoint`].
+pub fn model_endpoints() -> &'static [String] {
+    static ENDPOINTS: OnceLock<Vec<String>> = OnceLock::new();
+    ENDPOINTS.get_or_init(|| resolve_endpoints(model_endpoint(), &[]))
+}
+
+/// [`model_endpoints`] for a config profile's `endpoints`, which stand in
+/// when none of the variables is set; the last of them is the hub.
+/// Resolved afresh on each call.
+pub fn model_endpoints_with(configured: &[String]) -> Vec<String> {
+    resolve_endpoints(&resolve_endpoint(configured), configured)
+}
+
+fn resolve_endpoints(hub: &str, configured: &[String]) -> Vec<String> {
+    let mirrors = env::var("PACA_MIRRORS").unwrap_or_default();
+    let mirrors: Vec<&str> = mirrors
+        .split(',')
+        .map(str::trim)
+        .filter(|mirror| !mirror.is_empty())
+        .collect();
+    if mirrors.is_empty() && env_endpoint().is_none() && !configured.is_empty() {
+        return configured.to_vec();
+    }
+
+    mirrors.into_iter().chain([hub]).map(String::from).collect()
+}
+
+fn resolve_endpoint(configured: &[String]) -> String {
+    env_endpoint()
+        .or_else(|| configured.last().cloned())
+        .unwrap_or_else(|| DEFAULT_ENDPOINT.to_string())
+}
+
+fn env_endpoint() -> Option<String> {
     env::var("MODEL_ENDPOINT")
         .or_else(|_| env::var("HF_ENDPOINT"))
-        .unwrap_or_else(|_| DEFAULT_ENDPOINT.to_string())
+        .ok()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn resolve_endpoints_puts_mirrors_before_the_hub() {
+        temp_env::with_var(
+            "PACA_MIRRORS",
+            Some("http://models.lan:8080, https://mirror.example.com,"),
+            || {
+                assert_eq!(
+                    resolve_endpoints("https://huggingface.co", &[]),
+                    [
+                        "http://models.lan:8080",
+                        "https://mirror.example.com",
+                        "https://huggingface.co"
+                    ]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn resolve_endpoints_is_the_hub_alone_without_mirrors() {
+        temp_env::with_var_unset("PACA_MIRRORS", || {
+            assert_eq!(
+                resolve_endpoints("https://huggingface.co", &[]),
+                ["https://huggingface.co"]
+            );
+        });
+    }
+
+    #[test]
+    fn resolve_endpoints_uses_the_config_only_without_env_vars() {
+        let configured = [
+            "http://models.lan:8080".to_string(),
+            "https://hub.example.com".to_string(),
+        ];
+        temp_env::with_vars_unset(["PACA_MIRRORS", "HF_ENDPOINT", "MODEL_ENDPOINT"], || {
+            assert_eq!(resolve_endpoint(&configured), "https://hub.example.com");
+            assert_eq!(
+                resolve_endpoints("https://hub.example.com", &configured),
+                configured
+            );
+        });
+        temp_env::with_vars(
+            [
+                ("PACA_MIRRORS", None),
+                ("HF_ENDPOINT", Some("https://env.example.com")),
+                ("MODEL_ENDPOINT", None),
+            ],
+            || {
+                assert_eq!(resolve_endpoint(&configured), "https://env.example.com");
+                assert_eq!(
+                    resolve_endpoints("https://env.example.com", &configured),
+                    ["https://env.example.com"]
+                );
+            },
+        );
+    }
+
     #[test]
     fn resolve_endpoint_returns_default_when_no_env_vars_set() {
         temp_env::with_vars_unset(["HF_ENDPOINT", "MODEL_ENDPOINT"], || {
-            assert_eq!(resolve_endpoint(), "https://huggingface.co");
+            assert_eq!(resolve_endpoint(&[]), "https://huggingface.co");
         });
     }
 
@@ -38,7 +131,7 @@ mod tests {
                 ("MODEL_ENDPOINT", None),
             ],
             || {
-                assert_eq!(resolve_endpoint(), "https://custom-hf.example.com");
+                assert_eq!(resolve_endpoint(&[]), "https://custom-hf.example.com");
             },
         );
     }
@@ -51,7 +144,7 @@ mod tests {
                 ("MODEL_ENDPOINT", Some("https://custom-model.example.com")),
             ],
             || {
-                assert_eq!(resolve_endpoint(), "https://custom-model.example.com");
+                assert_eq!(resolve_endpoint(&[]), "https://custom-model.example.com");
             },
         );
     }
@@ -64,7 +157,7 @@ mod tests {
                 ("MODEL_ENDPOINT", Some("https://model.example.com")),
             ],
             || {
-                assert_eq!(resolve_endpoint(), "https://model.example.com");
+                assert_eq!(resolve_endpoint(&[]), "https://model.example.com");
             },
         );
     }
@@ -0,0 +1,290 @@
+use reqwest::Client;
+use reqwest::header::HeaderMap;
+
+use crate::error::PacaError;
+use crate::http::HttpOptions;
+use crate::model::{ModelRef, percent_decode};
+use crate::registry::manifest::{GgufFile, Manifest, manifest_for_tag, tags_in};
+use crate::registry::{
+    BoxFuture, Origin, Registry, ResolveInfo, anonymous_headers, sha256_hex, url_origin,
+};
+
+/// GGUF files served from a plain HTTP directory listing, laid out as
+/// `<base>/<owner>/<model>/<file>.gguf` — an nginx `autoindex`, an
+/// artifact server, or `python -m http.server`.
+///
+/// Such a server has no commits or content hashes, so both are derived:
+/// the commit is the SHA-256 of the model's directory listing, and a blob
+/// is named by the SHA-256 of the file's URL, size and validators. Either
+/// changes whenever a file is replaced, which is what `outdated` needs.
+pub struct HttpDirectory {
+    base: String,
+    client: Client,
+}
+
+impl HttpDirectory {
+    pub fn new(base: impl Into<String>) -> Result<Self, PacaError> {
+        Self::with_http(base, &HttpOptions::from_env()?)
+    }
+
+    /// A directory at `base` whose client connects as `http` says.
+    pub fn with_http(base: impl Into<String>, http: &HttpOptions) -> Result<Self, PacaError> {
+        Ok(Self {
+            base: base.into().trim_end_matches('/').to_string(),
+            client: http.client(anonymous_headers())?,
+        })
+    }
+
+    fn dir_url(&self, model_ref: &ModelRef) -> String {
+        format!("{}/{}/", self.base, model_ref.repo())
+    }
+
+    async fn listing(&self, model_ref: &ModelRef) -> Result<String, PacaError> {
+        self.client
+            .get(self.dir_url(model_ref))
+            .send()
+            .await
+            .map_err(PacaError::ManifestFetch)?
+            .error_for_status()
+            .map_err(PacaError::ManifestFetch)?
+            .text()
+            .await
+            .map_err(PacaError::ManifestFetch)
+    }
+
+    /// Sizes a file and names its blob from a HEAD request.
+    async fn head(&self, url: &str) -> Result<(u64, String), PacaError> {
+        let response = self.client.head(url).send().await?.error_for_status()?;
+        let headers = response.headers();
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+        };
+
+        let size = header("content-length")
+            .parse::<u64>()
+            .map_err(|_| PacaError::MissingFileSize(url.to_string()))?;
+        let blob_hash = sha256_hex(&format!(
+            "{url}\n{size}\n{}\n{}",
+            header("etag"),
+            header("last-modified")
+        ));
+
+        Ok((size, blob_hash))
+    }
+}
+
+/// The files a directory listing links to directly. Sorting links
+/// (`?C=N;O=D`), parent links and subdirectories are skipped.
+fn listed_files(listing: &str) -> Vec<String> {
+    let mut files: Vec<String> = listing
+        .split("href=")
+        .skip(1)
+        .filter_map(|rest| {
+            let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let end = rest[1..].find(quote)?;
+            percent_decode(&rest[1..=end])
+        })
+        .filter(|href| {
+            !href.is_empty()
+                && !href.starts_with(['?', '#', '.'])
+                && !href.contains('/')
+                && !href.contains(':')
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+impl Registry for HttpDirectory {
+    fn manifest<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+    ) -> BoxFuture<'a, Result<Manifest, PacaError>> {
+        Box::pin(async move {
+            let listing = self.listing(model_ref).await?;
+            let files = listed_files(&listing)
+                .into_iter()
+                .map(|filename| GgufFile { filename, size: 0 })
+                .collect();
+
+            // Select first, so only the tag's own files cost a HEAD.
+            let mut manifest = manifest_for_tag(files, model_ref)?;
+            for file in &mut manifest.gguf_files {
+                let (size, _) = self
+                    .head(&self.file_url(model_ref, &file.filename))
+                    .await
+                    .map_err(|e| match e {
+                        PacaError::Http(e) => PacaError::ManifestFetch(e),
+                        other => other,
+                    })?;
+                file.size = size;
+            }
+
+            Ok(manifest)
+        })
+    }
+
+    fn resolve<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<ResolveInfo, PacaError>> {
+        Box::pin(async move {
+            let listing = self.listing(model_ref).await?;
+            let (size, blob_hash) = self.head(&self.file_url(model_ref, filename)).await?;
+
+            Ok(ResolveInfo {
+                blob_hash,
+                commit_hash: sha256_hex(&listing),
+                endpoint: None,
+                sha256: None,
+                size: Some(size),
+            })
+        })
+    }
+
+    fn file_url(&self, model_ref: &ModelRef, filename: &str) -> String {
+        format!("{}{filename}", self.dir_url(model_ref))
+    }
+
+    fn tags<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+    ) -> BoxFuture<'a, Result<Vec<String>, PacaError>> {
+        Box::pin(async move {
+            let files = listed_files(&self.listing(model_ref).await?);
+            Ok(tags_in(files.iter().map(String::as_str), &model_ref.model))
+        })
+    }
+
+    fn headers(&self) -> Result<HeaderMap, PacaError> {
+        Ok(anonymous_headers())
+    }
+
+    fn endpoint_of(&self, url: &str) -> String {
+        if url.starts_with(&self.base) {
+            self.base.clone()
+        } else {
+            url_origin(url).to_string()
+        }
+    }
+
+    fn origin(&self) -> Origin {
+        Origin::HttpDirectory(self.base.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const LISTING: &str = r#"<html><body><h1>Index of /owner/Model-GGUF/</h1>
+        <a href="?C=N;O=D">Name</a>
+        <a href="../">../</a>
+        <a href="notes/">notes/</a>
+        <a href="Model-Q4_K_M.gguf">Model-Q4_K_M.gguf</a>
+        <a href='Model-Q8_0-00001-of-00002.gguf'>Model-Q8_0-00001-of-00002.gguf</a>
+        <a href="Model-Q8_0-00002-of-00002.gguf">Model-Q8_0-00002-of-00002.gguf</a>
+        <a href="README.md">README.md</a>
+        </body></html>"#;
+
+    async fn stand_in() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models/owner/Model-GGUF/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(LISTING))
+            .mount(&server)
+            .await;
+        for (file, size) in [
+            ("Model-Q4_K_M.gguf", 10),
+            ("Model-Q8_0-00001-of-00002.gguf", 20),
+            ("Model-Q8_0-00002-of-00002.gguf", 21),
+        ] {
+            Mock::given(method("HEAD"))
+                .and(path(format!("/models/owner/Model-GGUF/{file}")))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("content-length", size.to_string())
+                        .insert_header("etag", format!("\"{file}\"")),
+                )
+                .mount(&server)
+                .await;
+        }
+        server
+    }
+
+    #[test]
+    fn listed_files_keeps_only_direct_file_links() {
+        assert_eq!(
+            listed_files(LISTING),
+            vec![
+                "Model-Q4_K_M.gguf",
+                "Model-Q8_0-00001-of-00002.gguf",
+                "Model-Q8_0-00002-of-00002.gguf",
+                "README.md",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn manifest_sizes_the_shards_of_the_tag() {
+        let server = stand_in().await;
+        let registry = HttpDirectory::new(format!("{}/models/", server.uri())).unwrap();
+        let model_ref: ModelRef = "owner/Model-GGUF:Q8_0".parse().unwrap();
+
+        let manifest = registry.manifest(&model_ref).await.unwrap();
+
+        assert_eq!(
+            manifest.gguf_files,
+            vec![
+                GgufFile {
+                    filename: "Model-Q8_0-00001-of-00002.gguf".to_string(),
+                    size: 20,
+                },
+                GgufFile {
+                    filename: "Model-Q8_0-00002-of-00002.gguf".to_string(),
+                    size: 21,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_derives_a_stable_commit_and_blob() {
+        let server = stand_in().await;
+        let registry = HttpDirectory::new(format!("{}/models", server.uri())).unwrap();
+        let model_ref: ModelRef = "owner/Model-GGUF:Q4_K_M".parse().unwrap();
+
+        let first = registry
+            .resolve(&model_ref, "Model-Q4_K_M.gguf")
+            .await
+            .unwrap();
+        let again = registry
+            .resolve(&model_ref, "Model-Q4_K_M.gguf")
+            .await
+            .unwrap();
+
+        assert_eq!(first, again);
+        assert_eq!(first.commit_hash, sha256_hex(LISTING));
+        assert_eq!(first.size, Some(10));
+        assert_eq!(first.blob_hash.len(), 64);
+    }
+
+    #[tokio::test]
+    async fn tags_lists_every_tag_in_the_directory() {
+        let server = stand_in().await;
+        let registry = HttpDirectory::new(format!("{}/models", server.uri())).unwrap();
+        let model_ref: ModelRef = "owner/Model-GGUF:Q4".parse().unwrap();
+
+        assert_eq!(
+            registry.tags(&model_ref).await.unwrap(),
+            vec!["Q4_K_M", "Q8_0"]
+        );
+    }
+}
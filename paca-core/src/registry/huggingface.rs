@@ -0,0 +1,520 @@
+use std::future::Future;
+
+use reqwest::Client;
+use reqwest::header::HeaderMap;
+
+use crate::auth::resolve_token_with;
+use crate::config::Profile;
+use crate::error::PacaError;
+use crate::http::HttpOptions;
+use crate::model::ModelRef;
+use crate::registry::endpoint::{model_endpoints, model_endpoints_with};
+use crate::registry::manifest::{Manifest, fetch_manifest, fetch_remote_tags};
+use crate::registry::{
+    BoxFuture, Origin, Registry, ResolveInfo, anonymous_headers, fetch_resolve_info,
+    is_unavailable, token_headers, url_origin,
+};
+
+/// The HuggingFace Hub, or any mirror speaking its API.
+///
+/// With fallbacks, each request goes to the endpoints in order, moving on
+/// from one that is unreachable or lacks what was asked for.
+pub struct HuggingFace {
+    credentials: Credentials,
+    /// Never empty; the first is the one named in file URLs.
+    endpoints: Vec<Endpoint>,
+    http: HttpOptions,
+}
+
+/// Where a registry's tokens come from.
+#[derive(Clone, Debug)]
+pub(crate) enum Credentials {
+    /// This token, or none, for every endpoint.
+    Given(Option<String>),
+    /// Each endpoint's own, as [`resolve_token_with`] finds it for its
+    /// host.
+    Resolved(Profile),
+}
+
+impl Credentials {
+    fn headers(&self, endpoint: &str) -> Result<HeaderMap, PacaError> {
+        match self {
+            Self::Given(token) => token_headers(token.as_deref()),
+            Self::Resolved(profile) => {
+                token_headers(resolve_token_with(endpoint, profile)?.as_deref())
+            }
+        }
+    }
+}
+
+/// One endpoint, with clients carrying its credentials: a mirror's token
+/// is never sent to another host.
+struct Endpoint {
+    url: String,
+    client: Client,
+    head_client: Client,
+    headers: HeaderMap,
+}
+
+impl HuggingFace {
+    /// A registry at `endpoint`, authenticated with the resolved
+    /// HuggingFace token.
+    pub fn new(endpoint: impl Into<String>) -> Result<Self, PacaError> {
+        let credentials = Credentials::Resolved(Profile::default());
+        Self::with_http(endpoint, credentials, &HttpOptions::from_env()?)
+    }
+
+    /// A registry at `endpoint` authenticated with `token`, or anonymous
+    /// without one, whatever the environment holds.
+    pub fn with_token(endpoint: impl Into<String>, token: Option<&str>) -> Result<Self, PacaError> {
+        let credentials = Credentials::Given(token.map(String::from));
+        Self::with_http(endpoint, credentials, &HttpOptions::from_env()?)
+    }
+
+    /// A registry at `endpoint` whose clients connect as `http` says,
+    /// for a [`crate::Paca`] with options of its own.
+    pub(crate) fn with_http(
+        endpoint: impl Into<String>,
+        credentials: Credentials,
+        http: &HttpOptions,
+    ) -> Result<Self, PacaError> {
+        let mut registry = Self {
+            credentials,
+            endpoints: Vec::new(),
+            http: http.clone(),
+        };
+        registry.push_endpoint(endpoint.into())?;
+        Ok(registry)
+    }
+
+    /// Adds endpoints to fall back to, in order, after those already set,
+    /// each authenticated with its own token. Mirrors store each blob
+    /// under the same hash, so a download one leaves unfinished resumes
+    /// from the next, once its ETag and commit show it serves the same
+    /// file.
+    pub fn with_fallbacks(
+        mut self,
+        endpoints: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<Self, PacaError> {
+        for endpoint in endpoints {
+            self.push_endpoint(endpoint.into())?;
+        }
+        Ok(self)
+    }
+
+    /// The mirrors `PACA_MIRRORS` lists, then the registry named by
+    /// `MODEL_ENDPOINT` or `HF_ENDPOINT`, else `huggingface.co`.
+    pub fn from_env() -> Result<Self, PacaError> {
+        let (first, rest) = model_endpoints()
+            .split_first()
+            .expect("the hub is always an endpoint");
+        Self::new(first)?.with_fallbacks(rest)
+    }
+
+    /// [`HuggingFace::from_env`], with `profile`'s endpoints and tokens
+    /// below the variables.
+    pub fn from_profile(profile: &Profile) -> Result<Self, PacaError> {
+        Self::from_profile_with(profile, &HttpOptions::from_env_with(profile)?)
+    }
+
+    /// [`HuggingFace::from_profile`], connecting as `http` says.
+    pub fn from_profile_with(profile: &Profile, http: &HttpOptions) -> Result<Self, PacaError> {
+        let endpoints = model_endpoints_with(&profile.endpoints);
+        let (first, rest) = endpoints
+            .split_first()
+            .expect("the hub is always an endpoint");
+        let credentials = Credentials::Resolved(profile.clone());
+        Self::with_http(first, credentials, http)?.with_fallbacks(rest)
+    }
+
+    fn push_endpoint(&mut self, url: String) -> Result<(), PacaError> {
+        let headers = self.credentials.headers(&url)?;
+        self.endpoints.push(Endpoint {
+            client: self.http.client(headers.clone())?,
+            head_client: self.http.resolve_client(headers.clone())?,
+            headers,
+            url,
+        });
+        Ok(())
+    }
+
+    /// The endpoint `url` points into, if it is one of ours.
+    fn endpoint_for(&self, url: &str) -> Option<&Endpoint> {
+        self.endpoints.iter().find(|endpoint| {
+            url.strip_prefix(endpoint.url.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// Runs `request` against each endpoint until one is available; the
+    /// last endpoint's answer stands.
+    async fn first_available<'a, T, F>(
+        &'a self,
+        request: impl Fn(&'a Endpoint) -> F,
+    ) -> Result<T, PacaError>
+    where
+        F: Future<Output = Result<T, PacaError>>,
+    {
+        let (last, rest) = self.endpoints.split_last().expect("at least one endpoint");
+        for endpoint in rest {
+            match request(endpoint).await {
+                Err(e) if is_unavailable(&e) => {}
+                result => return result,
+            }
+        }
+        request(last).await
+    }
+
+    fn url_at(endpoint: &str, model_ref: &ModelRef, revision: &str, filename: &str) -> String {
+        format!(
+            "{endpoint}/{}/resolve/{revision}/{filename}",
+            model_ref.repo()
+        )
+    }
+}
+
+impl Registry for HuggingFace {
+    fn manifest<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+    ) -> BoxFuture<'a, Result<Manifest, PacaError>> {
+        Box::pin(
+            self.first_available(|endpoint| {
+                fetch_manifest(&endpoint.client, &endpoint.url, model_ref)
+            }),
+        )
+    }
+
+    fn resolve<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<ResolveInfo, PacaError>> {
+        Box::pin(self.first_available(move |endpoint| async move {
+            let url = Self::url_at(&endpoint.url, model_ref, model_ref.revision(), filename);
+            Ok(ResolveInfo {
+                endpoint: Some(endpoint.url.clone()),
+                ..fetch_resolve_info(&endpoint.head_client, &url).await?
+            })
+        }))
+    }
+
+    fn file_url(&self, model_ref: &ModelRef, filename: &str) -> String {
+        Self::url_at(
+            &self.endpoints[0].url,
+            model_ref,
+            model_ref.revision(),
+            filename,
+        )
+    }
+
+    /// From the mirror that resolved the file onwards: those before it
+    /// already failed to. The fallbacks name the resolved commit, so one
+    /// whose branch has moved on still serves the same file.
+    fn blob_urls(
+        &self,
+        model_ref: &ModelRef,
+        filename: &str,
+        resolved: &ResolveInfo,
+    ) -> Vec<String> {
+        let start = self
+            .endpoints
+            .iter()
+            .position(|endpoint| resolved.endpoint.as_ref() == Some(&endpoint.url))
+            .unwrap_or(0);
+        self.endpoints[start..]
+            .iter()
+            .enumerate()
+            .map(|(i, endpoint)| {
+                let revision = if i == 0 {
+                    model_ref.revision()
+                } else {
+                    &resolved.commit_hash
+                };
+                Self::url_at(&endpoint.url, model_ref, revision, filename)
+            })
+            .collect()
+    }
+
+    fn serves_blob<'a>(
+        &'a self,
+        url: &'a str,
+        resolved: &'a ResolveInfo,
+    ) -> BoxFuture<'a, Result<bool, PacaError>> {
+        Box::pin(async move {
+            let info = match self.endpoint_for(url) {
+                Some(endpoint) => fetch_resolve_info(&endpoint.head_client, url).await?,
+                None => {
+                    let client = self.http.resolve_client(anonymous_headers())?;
+                    fetch_resolve_info(&client, url).await?
+                }
+            };
+            Ok(info.blob_hash == resolved.blob_hash && info.commit_hash == resolved.commit_hash)
+        })
+    }
+
+    fn tags<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+    ) -> BoxFuture<'a, Result<Vec<String>, PacaError>> {
+        Box::pin(self.first_available(|endpoint| {
+            fetch_remote_tags(&endpoint.client, &endpoint.url, model_ref)
+        }))
+    }
+
+    fn headers(&self) -> Result<HeaderMap, PacaError> {
+        Ok(self.endpoints[0].headers.clone())
+    }
+
+    /// The credentials of the endpoint `url` points into, and none for
+    /// anywhere else.
+    fn headers_for(&self, url: &str) -> Result<HeaderMap, PacaError> {
+        Ok(self
+            .endpoint_for(url)
+            .map_or_else(anonymous_headers, |endpoint| endpoint.headers.clone()))
+    }
+
+    fn endpoint_of(&self, url: &str) -> String {
+        self.endpoint_for(url).map_or_else(
+            || url_origin(url).to_string(),
+            |endpoint| endpoint.url.clone(),
+        )
+    }
+
+    fn origin(&self) -> Origin {
+        Origin::HuggingFace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Nothing listens on port 1, so connecting fails at once.
+    const UNREACHABLE: &str = "http://127.0.0.1:1";
+
+    #[test]
+    fn file_url_resolves_at_the_reference_revision() {
+        let registry = HuggingFace::new("https://hub.example.test").unwrap();
+        let model_ref: ModelRef = "owner/model-GGUF@v2:Q4".parse().unwrap();
+
+        assert_eq!(
+            registry.file_url(&model_ref, "model-Q4.gguf"),
+            "https://hub.example.test/owner/model-GGUF/resolve/v2/model-Q4.gguf"
+        );
+    }
+
+    #[test]
+    fn with_token_authenticates_with_the_given_token() {
+        temp_env::with_var("HF_TOKEN", Some("from-env"), || {
+            let registry =
+                HuggingFace::with_token("https://hub.example.test", Some("given")).unwrap();
+            assert_eq!(registry.headers().unwrap()["Authorization"], "Bearer given");
+            let anonymous = HuggingFace::with_token("https://hub.example.test", None).unwrap();
+            assert!(anonymous.headers().unwrap().get("Authorization").is_none());
+        });
+    }
+
+    #[test]
+    fn blob_urls_pin_the_fallbacks_to_the_resolved_commit() {
+        let registry = HuggingFace::new("https://hub.example.test")
+            .unwrap()
+            .with_fallbacks(["https://mirror.example.test"])
+            .unwrap();
+        let model_ref: ModelRef = "owner/model-GGUF:Q4".parse().unwrap();
+        let resolved = ResolveInfo {
+            blob_hash: "blob1".to_string(),
+            commit_hash: "commit1".to_string(),
+            endpoint: Some("https://hub.example.test".to_string()),
+            sha256: None,
+            size: None,
+        };
+
+        assert_eq!(
+            registry.blob_urls(&model_ref, "model-Q4.gguf", &resolved),
+            [
+                "https://hub.example.test/owner/model-GGUF/resolve/main/model-Q4.gguf",
+                "https://mirror.example.test/owner/model-GGUF/resolve/commit1/model-Q4.gguf",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn from_profile_sends_each_endpoint_its_own_token() {
+        let file = "/owner/model-GGUF/resolve/main/model-Q4.gguf";
+        let mirror = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(header("Authorization", "Bearer mirror-token"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mirror)
+            .await;
+        let hub = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path(file))
+            .and(header("Authorization", "Bearer hub-token"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-repo-commit", "commit1")
+                    .insert_header("etag", "\"blob1\""),
+            )
+            .mount(&hub)
+            .await;
+        let authority = |server: &MockServer| server.address().to_string();
+        let profile = Profile {
+            endpoints: vec![mirror.uri(), hub.uri()],
+            tokens: [
+                (authority(&mirror), "mirror-token".to_string()),
+                (authority(&hub), "hub-token".to_string()),
+            ]
+            .into(),
+            ..Profile::default()
+        };
+        let unset = ["HF_TOKEN", "PACA_MIRRORS", "MODEL_ENDPOINT", "HF_ENDPOINT"];
+        let registry = temp_env::with_vars_unset(unset, || {
+            HuggingFace::from_profile_with(&profile, &HttpOptions::default()).unwrap()
+        });
+        let model_ref: ModelRef = "owner/model-GGUF:Q4".parse().unwrap();
+
+        let resolved = registry.resolve(&model_ref, "model-Q4.gguf").await.unwrap();
+
+        assert_eq!(resolved.endpoint, Some(hub.uri()));
+        let url = format!("{}{file}", hub.uri());
+        assert_eq!(
+            registry.headers_for(&url).unwrap()["Authorization"],
+            "Bearer hub-token"
+        );
+        let elsewhere = "https://elsewhere.example.test/model-Q4.gguf";
+        assert!(
+            registry
+                .headers_for(elsewhere)
+                .unwrap()
+                .get("Authorization")
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn serves_blob_compares_the_mirrors_etag_and_commit() {
+        let mirror = MockServer::start().await;
+        for (file, etag) in [("same.gguf", "\"blob1\""), ("other.gguf", "\"blob2\"")] {
+            Mock::given(method("HEAD"))
+                .and(path(format!("/owner/model-GGUF/resolve/commit1/{file}")))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("x-repo-commit", "commit1")
+                        .insert_header("etag", etag),
+                )
+                .mount(&mirror)
+                .await;
+        }
+        let registry = HuggingFace::new(mirror.uri()).unwrap();
+        let resolved = ResolveInfo {
+            blob_hash: "blob1".to_string(),
+            commit_hash: "commit1".to_string(),
+            endpoint: None,
+            sha256: None,
+            size: None,
+        };
+        let url = |file: &str| format!("{}/owner/model-GGUF/resolve/commit1/{file}", mirror.uri());
+
+        assert!(
+            registry
+                .serves_blob(&url("same.gguf"), &resolved)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !registry
+                .serves_blob(&url("other.gguf"), &resolved)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_falls_back_past_unreachable_and_missing_mirrors() {
+        let missing = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&missing)
+            .await;
+        let hub = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path("/owner/model-GGUF/resolve/main/model-Q4.gguf"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-repo-commit", "commit1")
+                    .insert_header("etag", "\"blob1\""),
+            )
+            .mount(&hub)
+            .await;
+        let registry = HuggingFace::new(UNREACHABLE)
+            .unwrap()
+            .with_fallbacks([missing.uri(), hub.uri()])
+            .unwrap();
+        let model_ref: ModelRef = "owner/model-GGUF:Q4".parse().unwrap();
+
+        let resolved = registry.resolve(&model_ref, "model-Q4.gguf").await.unwrap();
+
+        assert_eq!(resolved.blob_hash, "blob1");
+        assert_eq!(resolved.endpoint, Some(hub.uri()));
+        assert_eq!(
+            registry.blob_urls(&model_ref, "model-Q4.gguf", &resolved),
+            [format!(
+                "{}/owner/model-GGUF/resolve/main/model-Q4.gguf",
+                hub.uri()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn manifest_falls_back_to_a_mirror_with_the_tag() {
+        let missing = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&missing)
+            .await;
+        let hub = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/owner/model-GGUF/manifests/Q4"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ggufFile": {"rfilename": "model-Q4.gguf", "size": 4}
+            })))
+            .mount(&hub)
+            .await;
+        let registry = HuggingFace::new(missing.uri())
+            .unwrap()
+            .with_fallbacks([hub.uri()])
+            .unwrap();
+        let model_ref: ModelRef = "owner/model-GGUF:Q4".parse().unwrap();
+
+        let manifest = registry.manifest(&model_ref).await.unwrap();
+
+        assert_eq!(manifest.primary.as_deref(), Some("model-Q4.gguf"));
+    }
+
+    #[tokio::test]
+    async fn manifest_stops_at_a_mirror_that_rejects_the_request() {
+        let forbidden = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&forbidden)
+            .await;
+        let hub = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&hub)
+            .await;
+        let registry = HuggingFace::new(forbidden.uri())
+            .unwrap()
+            .with_fallbacks([hub.uri()])
+            .unwrap();
+        let model_ref: ModelRef = "owner/model-GGUF:Q4".parse().unwrap();
+
+        assert!(registry.manifest(&model_ref).await.is_err());
+    }
+}
@@ -5,7 +5,12 @@ use serde::Deserialize;
 
 use crate::cache::is_gguf;
 use crate::error::PacaError;
-use crate::model::ModelRef;
+use crate::model::tag_for_path;
+use crate::model::{DEFAULT_TAG, ModelRef};
+
+/// The tag registries without a default of their own fall back to when
+/// asked for [`DEFAULT_TAG`], matching llama.cpp's preference.
+pub(crate) const PREFERRED_TAG: &str = "Q4_K_M";
 
 #[derive(Debug, Deserialize)]
 struct TreeEntry {
@@ -13,6 +18,12 @@ struct TreeEntry {
     size: u64,
 }
 
+/// A tree entry reduced to its path; directories carry no size.
+#[derive(Debug, Deserialize)]
+struct TreePath {
+    path: String,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct GgufFile {
     /// The filename of the GGUF file
@@ -40,10 +51,16 @@ impl From<TreeEntry> for GgufFile {
 pub struct Manifest {
     /// List of GGUF files to download
     pub gguf_files: Vec<GgufFile>,
+    /// The model file the registry picked for the tag (its `ggufFile`
+    /// entry), as opposed to projectors and other companions
+    pub primary: Option<String>,
+    /// The manifest response itself, from registries speaking the
+    /// HuggingFace manifest API, which is what llama.cpp caches
+    pub response: Option<serde_json::Value>,
 }
 
 /// Fetches the model manifest from HuggingFace, handling both single and sharded files
-pub async fn fetch_manifest(
+pub(crate) async fn fetch_manifest(
     client: &Client,
     endpoint: &str,
     model_ref: &ModelRef,
@@ -63,6 +80,10 @@ pub async fn fetch_manifest(
         .map_err(PacaError::ManifestFetch)?;
 
     let parsed: serde_json::Value = response.json().await.map_err(PacaError::ManifestFetch)?;
+    let primary = parsed
+        .pointer("/ggufFile/rfilename")
+        .and_then(|v| v.as_str())
+        .map(std::string::ToString::to_string);
     let discovered = collect_manifest_files(&parsed);
 
     if discovered.is_empty() {
@@ -78,7 +99,11 @@ pub async fn fetch_manifest(
         }
     }
 
-    Ok(Manifest { gguf_files })
+    Ok(Manifest {
+        gguf_files,
+        primary,
+        response: Some(parsed),
+    })
 }
 
 /// Walks top-level JSON entries and collects any object with `rfilename` (String) + `size` (u64)
@@ -119,8 +144,9 @@ async fn fetch_tree_files(
     let subdir = rfilename.rsplit_once('/').map_or("", |(dir, _)| dir);
 
     let url = format!(
-        "{endpoint}/api/models/{}/tree/main/{subdir}",
-        model_ref.repo()
+        "{endpoint}/api/models/{}/tree/{}/{subdir}",
+        model_ref.repo(),
+        model_ref.revision()
     );
 
     let response = client
@@ -144,6 +170,98 @@ async fn fetch_tree_files(
     Ok(gguf_files)
 }
 
+/// Lists every tag the repo offers at the reference's revision, derived
+/// from its GGUF paths the same way `list` derives them from the cache.
+pub(crate) async fn fetch_remote_tags(
+    client: &Client,
+    endpoint: &str,
+    model_ref: &ModelRef,
+) -> Result<Vec<String>, PacaError> {
+    let url = format!(
+        "{endpoint}/api/models/{}/tree/{}?recursive=true",
+        model_ref.repo(),
+        model_ref.revision()
+    );
+
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(PacaError::ManifestFetch)?
+        .error_for_status()
+        .map_err(PacaError::ManifestFetch)?;
+
+    let entries: Vec<TreePath> = response.json().await.map_err(PacaError::ManifestFetch)?;
+
+    Ok(tags_in(
+        entries.iter().map(|entry| entry.path.as_str()),
+        &model_ref.model,
+    ))
+}
+
+/// Narrows a repo's full file list to the reference's tag, for registries
+/// that list files rather than serve per-tag manifests. Tags compare
+/// case-insensitively; [`DEFAULT_TAG`] picks [`PREFERRED_TAG`] when the
+/// repo has it and the first tag otherwise.
+pub(crate) fn manifest_for_tag(
+    files: Vec<GgufFile>,
+    model_ref: &ModelRef,
+) -> Result<Manifest, PacaError> {
+    let tagged: Vec<(String, GgufFile)> = files
+        .into_iter()
+        .filter(|file| is_gguf(&file.filename))
+        .filter_map(|file| Some((tag_for_path(&file.filename, &model_ref.model)?, file)))
+        .collect();
+
+    let wanted = if model_ref.tag == DEFAULT_TAG
+        && !tagged
+            .iter()
+            .any(|(tag, _)| tag.eq_ignore_ascii_case(DEFAULT_TAG))
+    {
+        let mut tags: Vec<&String> = tagged.iter().map(|(tag, _)| tag).collect();
+        tags.sort();
+        tags.iter()
+            .find(|tag| tag.eq_ignore_ascii_case(PREFERRED_TAG))
+            .or_else(|| tags.first())
+            .map(|tag| tag.to_string())
+            .ok_or(PacaError::NoFiles)?
+    } else {
+        model_ref.tag.clone()
+    };
+
+    let mut gguf_files: Vec<GgufFile> = tagged
+        .into_iter()
+        .filter(|(tag, _)| tag.eq_ignore_ascii_case(&wanted))
+        .map(|(_, file)| file)
+        .collect();
+    gguf_files.sort_by(|a, b| a.filename.cmp(&b.filename));
+
+    if gguf_files.is_empty() {
+        return Err(PacaError::NoFiles);
+    }
+
+    Ok(Manifest {
+        primary: Some(gguf_files[0].filename.clone()),
+        response: None,
+        gguf_files,
+    })
+}
+
+/// Every distinct tag among `paths`, sorted.
+pub(crate) fn tags_in<'a, I>(paths: I, model: &str) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut tags: Vec<String> = paths
+        .into_iter()
+        .filter(|path| is_gguf(path))
+        .filter_map(|path| tag_for_path(path, model))
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
 fn shard_count(filename: &str) -> Option<usize> {
     let stem = filename.strip_suffix(".gguf")?;
     let of_part = stem.rsplit_once("-of-")?;
@@ -153,6 +271,99 @@ fn shard_count(filename: &str) -> Option<usize> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn files(names: &[&str]) -> Vec<GgufFile> {
+        names
+            .iter()
+            .map(|name| GgufFile {
+                filename: name.to_string(),
+                size: 10,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn manifest_for_tag_selects_the_tag_case_insensitively() {
+        let model_ref: ModelRef = "owner/Model-GGUF:q8_0".parse().unwrap();
+        let manifest = manifest_for_tag(
+            files(&["Model-Q4_K_M.gguf", "Model-Q8_0.gguf", "README.md"]),
+            &model_ref,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.gguf_files, files(&["Model-Q8_0.gguf"]));
+        assert_eq!(manifest.primary.as_deref(), Some("Model-Q8_0.gguf"));
+    }
+
+    #[test]
+    fn manifest_for_tag_prefers_q4_k_m_for_the_default_tag() {
+        let model_ref = ModelRef::parse_with_default_tag("owner/Model-GGUF").unwrap();
+        let manifest = manifest_for_tag(
+            files(&["Model-BF16.gguf", "Model-Q4_K_M.gguf", "Model-Q8_0.gguf"]),
+            &model_ref,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.primary.as_deref(), Some("Model-Q4_K_M.gguf"));
+    }
+
+    #[test]
+    fn manifest_for_tag_collects_every_shard() {
+        let model_ref: ModelRef = "owner/Model-GGUF:BF16".parse().unwrap();
+        let manifest = manifest_for_tag(
+            files(&[
+                "BF16/Model-BF16-00002-of-00002.gguf",
+                "BF16/Model-BF16-00001-of-00002.gguf",
+                "Model-Q8_0.gguf",
+            ]),
+            &model_ref,
+        )
+        .unwrap();
+
+        assert_eq!(
+            manifest.gguf_files,
+            files(&[
+                "BF16/Model-BF16-00001-of-00002.gguf",
+                "BF16/Model-BF16-00002-of-00002.gguf",
+            ])
+        );
+    }
+
+    #[test]
+    fn manifest_for_tag_reports_a_missing_tag_as_no_files() {
+        let model_ref: ModelRef = "owner/Model-GGUF:Q2_K".parse().unwrap();
+        let result = manifest_for_tag(files(&["Model-Q8_0.gguf"]), &model_ref);
+        assert!(matches!(result, Err(PacaError::NoFiles)));
+    }
+
+    #[tokio::test]
+    async fn fetch_remote_tags_collects_flat_and_sharded_tags() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/models/owner/Model-GGUF/tree/main"))
+            .and(query_param("recursive", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"[
+                    {"type":"file","path":"README.md","size":10},
+                    {"type":"directory","path":"BF16","size":0},
+                    {"type":"file","path":"BF16/Model-BF16-00001-of-00002.gguf","size":10},
+                    {"type":"file","path":"BF16/Model-BF16-00002-of-00002.gguf","size":10},
+                    {"type":"file","path":"Model-Q4_K_M.gguf","size":10},
+                    {"type":"file","path":"model-q8_0.gguf","size":10}
+                ]"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let model_ref: ModelRef = "owner/Model-GGUF:Q4".parse().unwrap();
+        let tags = fetch_remote_tags(&Client::new(), &server.uri(), &model_ref)
+            .await
+            .unwrap();
+
+        assert_eq!(tags, vec!["BF16", "Q4_K_M", "q8_0"]);
+    }
 
     #[test]
     fn manifest_struct_holds_single_file() {
@@ -161,6 +372,8 @@ mod tests {
                 filename: "model.gguf".to_string(),
                 size: 1024,
             }],
+            primary: Some("model.gguf".to_string()),
+            response: None,
         };
         assert_eq!(manifest.gguf_files.len(), 1);
         assert_eq!(manifest.gguf_files[0].filename, "model.gguf");
@@ -180,6 +393,8 @@ mod tests {
                     size: 2048,
                 },
             ],
+            primary: Some("file-00001-of-00002.gguf".to_string()),
+            response: None,
         };
         assert_eq!(manifest.gguf_files.len(), 2);
         assert_eq!(manifest.gguf_files[0].filename, "file-00001-of-00002.gguf");
@@ -1,16 +1,197 @@
+//! Where models come from. Each hub is a [`Registry`]; HuggingFace is the
+//! default, with ModelScope and plain HTTP directories alongside it.
+
 pub mod endpoint;
+pub mod http_dir;
+pub mod huggingface;
 pub mod manifest;
+pub mod modelscope;
+pub mod oci;
+pub mod origin;
 
-use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
 
 use reqwest::Client;
 use reqwest::header::{HeaderMap, HeaderValue};
-use reqwest::redirect;
+use sha2::{Digest, Sha256};
+
+use crate::config::Profile;
+use crate::error::{ModelRefError, PacaError};
+use crate::http::{HttpOptions, USER_AGENT};
+use crate::model::ModelRef;
+
+pub use http_dir::HttpDirectory;
+pub use huggingface::HuggingFace;
+pub use manifest::{GgufFile, Manifest};
+pub use modelscope::ModelScope;
+pub use oci::Oci;
+pub use origin::Origin;
+
+/// A boxed future, so that [`Registry`] stays usable as a trait object.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A hub paca can download models from.
+///
+/// Implementations translate a [`ModelRef`] into their own URL shapes; the
+/// cache layout, resumable transfers and progress reporting stay shared.
+pub trait Registry: Send + Sync {
+    /// Lists the files that make up the reference's tag. For
+    /// [`crate::model::DEFAULT_TAG`], the registry picks the tag and names
+    /// its model file in [`Manifest::primary`].
+    fn manifest<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+    ) -> BoxFuture<'a, Result<Manifest, PacaError>>;
+
+    /// Resolves one file to the blob and commit it is stored under.
+    fn resolve<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<ResolveInfo, PacaError>>;
 
-use crate::error::PacaError;
+    /// The URL a file's bytes are downloaded from.
+    fn file_url(&self, model_ref: &ModelRef, filename: &str) -> String;
 
-/// User agent string used for HTTP requests
-const USER_AGENT: &str = "llama-cpp";
+    /// The URL to download a resolved file from. Content-addressed
+    /// registries locate bytes by digest rather than by name.
+    fn blob_url(&self, model_ref: &ModelRef, filename: &str, _resolved: &ResolveInfo) -> String {
+        self.file_url(model_ref, filename)
+    }
+
+    /// Every URL a resolved file may be downloaded from, in the order to
+    /// try them. Registries with mirrors list one per mirror.
+    fn blob_urls(
+        &self,
+        model_ref: &ModelRef,
+        filename: &str,
+        resolved: &ResolveInfo,
+    ) -> Vec<String> {
+        vec![self.blob_url(model_ref, filename, resolved)]
+    }
+
+    /// Whether `url`, one of [`Registry::blob_urls`] after the first,
+    /// serves the blob `resolved` names, so that a download may fall back,
+    /// or resume a partial file, there. Registries with a single URL per
+    /// blob have nothing to check.
+    fn serves_blob<'a>(
+        &'a self,
+        _url: &'a str,
+        _resolved: &'a ResolveInfo,
+    ) -> BoxFuture<'a, Result<bool, PacaError>> {
+        Box::pin(async { Ok(true) })
+    }
+
+    /// Every tag the repo offers, used to suggest alternatives when a
+    /// requested tag does not exist. Registries that cannot list tags
+    /// report none.
+    fn tags<'a>(
+        &'a self,
+        _model_ref: &'a ModelRef,
+    ) -> BoxFuture<'a, Result<Vec<String>, PacaError>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// Headers to send with file downloads. Credentials belong to one
+    /// registry, so each decides its own.
+    fn headers(&self) -> Result<HeaderMap, PacaError>;
+
+    /// Headers to download `url`, one of [`Registry::blob_urls`], with.
+    /// A registry whose mirrors hold credentials of their own sends each
+    /// only to its mirror.
+    fn headers_for(&self, url: &str) -> Result<HeaderMap, PacaError> {
+        let _ = url;
+        self.headers()
+    }
+
+    /// The endpoint or mirror `url`, one of [`Registry::blob_urls`],
+    /// belongs to: its scheme and host unless the registry knows better.
+    fn endpoint_of(&self, url: &str) -> String {
+        url_origin(url).to_string()
+    }
+
+    /// What the cache records a repo downloaded from here as.
+    fn origin(&self) -> Origin;
+}
+
+/// `url` up to the end of its host and port.
+pub(crate) fn url_origin(url: &str) -> &str {
+    let authority = url.find("://").map_or(0, |i| i + 3);
+    let end = url[authority..]
+        .find(['/', '?', '#'])
+        .map_or(url.len(), |i| authority + i);
+    &url[..end]
+}
+
+/// Picks a registry by name: `huggingface` (or `hf`), `modelscope`, or
+/// the `http(s)://` URL of a directory served over plain HTTP.
+pub fn from_name(name: &str) -> Result<Arc<dyn Registry>, PacaError> {
+    from_name_with(name, &Profile::default(), &HttpOptions::from_env()?)
+}
+
+/// [`from_name`], with HuggingFace at `profile`'s endpoints and tokens,
+/// connecting as `http` says. See [`HuggingFace::from_profile`].
+pub fn from_name_with(
+    name: &str,
+    profile: &Profile,
+    http: &HttpOptions,
+) -> Result<Arc<dyn Registry>, PacaError> {
+    match name {
+        "hf" | "huggingface" => Ok(Arc::new(HuggingFace::from_profile_with(profile, http)?)),
+        "modelscope" => Ok(Arc::new(ModelScope::with_http(
+            modelscope::DEFAULT_ENDPOINT,
+            http,
+        )?)),
+        url if url.starts_with("http://") || url.starts_with("https://") => {
+            Ok(Arc::new(HttpDirectory::with_http(url, http)?))
+        }
+        other => Err(PacaError::UnknownRegistry(other.to_string())),
+    }
+}
+
+/// Picks the registry for a model reference. An `oci://host/…` reference
+/// names its own registry; anything else goes to the one `name` picks
+/// (see [`from_name`]). Returns the reference with any `oci://host`
+/// prefix removed, ready for [`crate::download::fetch_manifest`].
+pub fn for_model(name: &str, model: &str) -> Result<(Arc<dyn Registry>, String), PacaError> {
+    for_model_with(name, model, &Profile::default(), &HttpOptions::from_env()?)
+}
+
+/// [`for_model`], with HuggingFace at `profile`'s endpoints and tokens,
+/// connecting as `http` says.
+pub fn for_model_with(
+    name: &str,
+    model: &str,
+    profile: &Profile,
+    http: &HttpOptions,
+) -> Result<(Arc<dyn Registry>, String), PacaError> {
+    let Some(rest) = model.strip_prefix("oci://") else {
+        return Ok((from_name_with(name, profile, http)?, model.to_string()));
+    };
+
+    match rest.split_once('/') {
+        Some((host, reference)) if !host.is_empty() && !reference.is_empty() => {
+            let registry = Oci::with_http(oci::base_for_host(host), http)?;
+            Ok((Arc::new(registry), reference.to_string()))
+        }
+        _ => Err(ModelRefError::InvalidUrl(model.to_string()).into()),
+    }
+}
+
+/// Hex SHA-256 of `input`, for registries that must derive their own
+/// commit or blob names.
+pub(crate) fn sha256_hex(input: &str) -> String {
+    format!("{:x}", Sha256::digest(input.as_bytes()))
+}
+
+/// Headers for registries that take no credentials.
+pub(crate) fn anonymous_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("User-Agent", HeaderValue::from_static(USER_AGENT));
+    headers
+}
 
 /// Information resolved from a HEAD request to a HuggingFace file URL
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -19,16 +200,33 @@ pub struct ResolveInfo {
     pub blob_hash: String,
     /// Commit hash for this revision (from X-Repo-Commit header)
     pub commit_hash: String,
+    /// The mirror that resolved the file, for registries with several;
+    /// its download starts there
+    pub endpoint: Option<String>,
+    /// SHA-256 of the file's content, when the registry vouches for one.
+    /// Downloads are verified against it.
+    pub sha256: Option<String>,
+    /// File size in bytes (from X-Linked-Size, else Content-Length), when
+    /// the registry reports one
+    pub size: Option<u64>,
 }
 
-pub fn default_headers() -> Result<HeaderMap, PacaError> {
-    let mut headers = HeaderMap::new();
-    headers.insert("User-Agent", HeaderValue::from_static(USER_AGENT));
+/// Whether a request failed in a way another mirror might not: the
+/// endpoint is unreachable, or lacks what was asked for.
+pub(crate) fn is_unavailable(error: &PacaError) -> bool {
+    match error {
+        PacaError::Http(e) | PacaError::ManifestFetch(e) => {
+            e.is_connect() || e.is_timeout() || e.status() == Some(reqwest::StatusCode::NOT_FOUND)
+        }
+        _ => false,
+    }
+}
 
-    let token = env::var("HF_TOKEN").unwrap_or_default();
-    let token = token.trim();
+/// Headers carrying `token` as a bearer credential, if there is one.
+pub(crate) fn token_headers(token: Option<&str>) -> Result<HeaderMap, PacaError> {
+    let mut headers = anonymous_headers();
 
-    if !token.is_empty() {
+    if let Some(token) = token {
         let mut auth_value: HeaderValue = format!("Bearer {token}")
             .parse()
             .map_err(|_| PacaError::InvalidToken)?;
@@ -39,16 +237,16 @@ pub fn default_headers() -> Result<HeaderMap, PacaError> {
     Ok(headers)
 }
 
-/// Builds a Client configured for resolve-info HEAD requests (no redirect following).
-pub fn build_resolve_client() -> Result<Client, PacaError> {
-    Ok(Client::builder()
-        .default_headers(default_headers()?)
-        .redirect(redirect::Policy::none())
-        .build()?)
-}
-
-pub async fn fetch_resolve_info(client: &Client, url: &str) -> Result<ResolveInfo, PacaError> {
+pub(crate) async fn fetch_resolve_info(
+    client: &Client,
+    url: &str,
+) -> Result<ResolveInfo, PacaError> {
     let response = client.head(url).send().await?;
+    // Refusals carry none of the headers below; report them as what they
+    // are rather than as a malformed answer.
+    if matches!(response.status().as_u16(), 401 | 403 | 404) {
+        return Err(response.error_for_status().unwrap_err().into());
+    }
     let headers = response.headers();
 
     let commit_hash = headers
@@ -66,18 +264,41 @@ pub async fn fetch_resolve_info(client: &Client, url: &str) -> Result<ResolveInf
         .map(std::string::ToString::to_string)
         .ok_or_else(|| PacaError::MissingBlobHash(url.to_string()))?;
 
+    // A redirect's own Content-Length describes the redirect body, so only
+    // trust it when there is no linked size and the file was served here.
+    let size = headers
+        .get("x-linked-size")
+        .or_else(|| {
+            response
+                .status()
+                .is_success()
+                .then(|| headers.get("content-length"))
+                .flatten()
+        })
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok());
+
     Ok(ResolveInfo {
         blob_hash,
         commit_hash,
+        endpoint: None,
+        sha256: None,
+        size,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::resolve_token;
+    use crate::http::HttpOptions;
     use wiremock::matchers::method;
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
+    fn default_headers() -> Result<HeaderMap, PacaError> {
+        token_headers(resolve_token()?.as_deref())
+    }
+
     async fn resolve_info_from(commit: &str, etag: &str) -> Result<ResolveInfo, PacaError> {
         let server = MockServer::start().await;
         Mock::given(method("HEAD"))
@@ -92,6 +313,27 @@ mod tests {
         fetch_resolve_info(&Client::new(), &server.uri()).await
     }
 
+    #[tokio::test]
+    async fn fetch_resolve_info_reads_the_linked_size() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("x-repo-commit", "commit1")
+                    .insert_header("x-linked-etag", "\"abc123\"")
+                    .insert_header("x-linked-size", "4096")
+                    .insert_header("location", "https://cdn.example.test/blob"),
+            )
+            .mount(&server)
+            .await;
+
+        let client = HttpOptions::default()
+            .resolve_client(anonymous_headers())
+            .unwrap();
+        let result = fetch_resolve_info(&client, &server.uri()).await.unwrap();
+        assert_eq!(result.size, Some(4096));
+    }
+
     #[tokio::test]
     async fn fetch_resolve_info_accepts_ordinary_hashes() {
         let result = resolve_info_from("commit1", "\"abc123\"").await.unwrap();
@@ -99,6 +341,54 @@ mod tests {
         assert_eq!(result.commit_hash, "commit1");
     }
 
+    #[tokio::test]
+    async fn fetch_resolve_info_reports_a_missing_file_as_unavailable() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let error = fetch_resolve_info(&Client::new(), &server.uri())
+            .await
+            .unwrap_err();
+        assert!(is_unavailable(&error));
+    }
+
+    #[test]
+    fn from_name_rejects_unknown_registries() {
+        assert!(matches!(
+            from_name("gitlab"),
+            Err(PacaError::UnknownRegistry(name)) if name == "gitlab"
+        ));
+    }
+
+    #[test]
+    fn for_model_splits_the_host_off_an_oci_reference() {
+        let (_, reference) =
+            for_model("huggingface", "oci://registry.ollama.ai/library/gemma3:4b").unwrap();
+        assert_eq!(reference, "library/gemma3:4b");
+    }
+
+    #[test]
+    fn for_model_leaves_other_references_alone() {
+        let (_, reference) = for_model("huggingface", "owner/model-GGUF:Q4").unwrap();
+        assert_eq!(reference, "owner/model-GGUF:Q4");
+    }
+
+    #[test]
+    fn for_model_rejects_an_oci_reference_without_a_repository() {
+        assert!(matches!(
+            for_model("huggingface", "oci://registry.ollama.ai"),
+            Err(PacaError::ModelRef(ModelRefError::InvalidUrl(_)))
+        ));
+    }
+
+    #[test]
+    fn from_name_accepts_a_directory_url() {
+        assert!(from_name("https://models.example.test/gguf").is_ok());
+    }
+
     #[test]
     fn default_headers_trims_whitespace_from_hf_token() {
         // `docker --env-file` with CRLF endings, a .env loader, `$(cat token)`
@@ -114,9 +404,17 @@ mod tests {
 
     #[test]
     fn default_headers_ignores_a_blank_hf_token() {
-        temp_env::with_vars([("HF_TOKEN", Some("   "))], || {
-            assert!(default_headers().unwrap().get("Authorization").is_none());
-        });
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("token");
+        temp_env::with_vars(
+            [
+                ("HF_TOKEN", Some("   ")),
+                ("HF_TOKEN_PATH", Some(missing.to_str().unwrap())),
+            ],
+            || {
+                assert!(default_headers().unwrap().get("Authorization").is_none());
+            },
+        );
     }
 
     #[test]
@@ -144,10 +442,38 @@ mod tests {
 
     #[test]
     fn default_headers_excludes_authorization_when_hf_token_unset() {
-        temp_env::with_vars_unset(["HF_TOKEN"], || {
-            let headers = default_headers().unwrap();
-            assert!(headers.get("Authorization").is_none());
-        });
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("token");
+        temp_env::with_vars(
+            [
+                ("HF_TOKEN", None),
+                ("HF_TOKEN_PATH", Some(missing.to_str().unwrap())),
+            ],
+            || {
+                let headers = default_headers().unwrap();
+                assert!(headers.get("Authorization").is_none());
+            },
+        );
+    }
+
+    #[test]
+    fn default_headers_reads_the_token_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("token");
+        std::fs::write(&file, "file-token\n").unwrap();
+        temp_env::with_vars(
+            [
+                ("HF_TOKEN", None),
+                ("HF_TOKEN_PATH", Some(file.to_str().unwrap())),
+            ],
+            || {
+                let headers = default_headers().unwrap();
+                assert_eq!(
+                    headers.get("Authorization").unwrap().to_str().unwrap(),
+                    "Bearer file-token"
+                );
+            },
+        );
     }
 
     #[test]
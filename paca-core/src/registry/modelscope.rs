@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+
+use reqwest::Client;
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+
+use crate::error::PacaError;
+use crate::http::HttpOptions;
+use crate::model::ModelRef;
+use crate::registry::manifest::{GgufFile, Manifest, manifest_for_tag, tags_in};
+use crate::registry::{BoxFuture, Origin, Registry, ResolveInfo, anonymous_headers};
+
+/// The public ModelScope hub.
+pub const DEFAULT_ENDPOINT: &str = "https://www.modelscope.cn";
+
+/// ModelScope names its default branch `master`, not `main`.
+const DEFAULT_REVISION: &str = "master";
+
+#[derive(Debug, Deserialize)]
+struct FilesResponse {
+    #[serde(rename = "Data")]
+    data: FilesData,
+}
+
+#[derive(Debug, Deserialize)]
+struct FilesData {
+    #[serde(rename = "Files")]
+    files: Vec<RepoFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoFile {
+    #[serde(rename = "Path")]
+    path: String,
+    #[serde(rename = "Revision")]
+    revision: String,
+    #[serde(rename = "Sha256", default)]
+    sha256: Option<String>,
+    #[serde(rename = "Size")]
+    size: u64,
+    #[serde(rename = "Type")]
+    kind: String,
+}
+
+/// The files of a repo at one revision.
+type Listing = Arc<[RepoFile]>;
+
+/// A ModelScope hub. Its file listing carries each file's SHA-256 and the
+/// commit it belongs to, so a single request answers both manifest and
+/// resolve.
+pub struct ModelScope {
+    client: Client,
+    endpoint: String,
+    /// The last listing of each repo and revision. A manifest fetches it
+    /// afresh; resolving that manifest's files reads it back.
+    listings: Mutex<HashMap<(String, String), Listing>>,
+}
+
+impl ModelScope {
+    pub fn new(endpoint: impl Into<String>) -> Result<Self, PacaError> {
+        Self::with_http(endpoint, &HttpOptions::from_env()?)
+    }
+
+    /// A hub at `endpoint` whose client connects as `http` says.
+    pub fn with_http(endpoint: impl Into<String>, http: &HttpOptions) -> Result<Self, PacaError> {
+        Ok(Self {
+            client: http.client(anonymous_headers())?,
+            endpoint: endpoint.into(),
+            listings: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn listing_key(model_ref: &ModelRef) -> (String, String) {
+        (model_ref.repo(), revision(model_ref).to_string())
+    }
+
+    /// The listing a manifest already fetched, else a fresh one.
+    async fn listing(&self, model_ref: &ModelRef) -> Result<Listing, PacaError> {
+        let cached = self
+            .listings
+            .lock()
+            .expect("listings lock poisoned")
+            .get(&Self::listing_key(model_ref))
+            .cloned();
+        match cached {
+            Some(files) => Ok(files),
+            None => self.list_files(model_ref).await,
+        }
+    }
+
+    async fn list_files(&self, model_ref: &ModelRef) -> Result<Listing, PacaError> {
+        let url = format!(
+            "{}/api/v1/models/{}/repo/files?Revision={}&Recursive=true",
+            self.endpoint,
+            model_ref.repo(),
+            query_value(revision(model_ref))
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(PacaError::ManifestFetch)?
+            .error_for_status()
+            .map_err(PacaError::ManifestFetch)?;
+
+        let parsed: FilesResponse = response.json().await.map_err(PacaError::ManifestFetch)?;
+        let files: Listing = parsed
+            .data
+            .files
+            .into_iter()
+            .filter(|file| file.kind == "blob")
+            .collect();
+        self.listings
+            .lock()
+            .expect("listings lock poisoned")
+            .insert(Self::listing_key(model_ref), Arc::clone(&files));
+        Ok(files)
+    }
+}
+
+/// Percent-encodes a query parameter, leaving `/` readable.
+fn query_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+fn revision(model_ref: &ModelRef) -> &str {
+    model_ref.revision.as_deref().unwrap_or(DEFAULT_REVISION)
+}
+
+impl Registry for ModelScope {
+    fn manifest<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+    ) -> BoxFuture<'a, Result<Manifest, PacaError>> {
+        Box::pin(async move {
+            let files = self
+                .list_files(model_ref)
+                .await?
+                .iter()
+                .map(|file| GgufFile {
+                    filename: file.path.clone(),
+                    size: file.size,
+                })
+                .collect();
+            manifest_for_tag(files, model_ref)
+        })
+    }
+
+    fn resolve<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<ResolveInfo, PacaError>> {
+        Box::pin(async move {
+            let url = self.file_url(model_ref, filename);
+            let files = self.listing(model_ref).await?;
+            let file = files
+                .iter()
+                .find(|file| file.path == filename)
+                .ok_or_else(|| PacaError::MissingBlobHash(url.clone()))?;
+
+            let sha256 = file
+                .sha256
+                .clone()
+                .filter(|hash| !hash.is_empty())
+                .ok_or(PacaError::MissingBlobHash(url))?;
+
+            Ok(ResolveInfo {
+                blob_hash: sha256.clone(),
+                commit_hash: file.revision.clone(),
+                endpoint: None,
+                sha256: Some(sha256),
+                size: Some(file.size),
+            })
+        })
+    }
+
+    fn file_url(&self, model_ref: &ModelRef, filename: &str) -> String {
+        format!(
+            "{}/api/v1/models/{}/repo?Revision={}&FilePath={}",
+            self.endpoint,
+            model_ref.repo(),
+            query_value(revision(model_ref)),
+            query_value(filename)
+        )
+    }
+
+    fn tags<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+    ) -> BoxFuture<'a, Result<Vec<String>, PacaError>> {
+        Box::pin(async move {
+            let files = self.list_files(model_ref).await?;
+            Ok(tags_in(
+                files.iter().map(|file| file.path.as_str()),
+                &model_ref.model,
+            ))
+        })
+    }
+
+    fn headers(&self) -> Result<HeaderMap, PacaError> {
+        Ok(anonymous_headers())
+    }
+
+    fn origin(&self) -> Origin {
+        Origin::ModelScope(self.endpoint.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const FILES: &str = r#"{
+        "Code": 200,
+        "Data": {"Files": [
+            {"Path": "README.md", "Revision": "c0ffee", "Sha256": "aaa", "Size": 5, "Type": "blob"},
+            {"Path": "BF16", "Revision": "c0ffee", "Sha256": "", "Size": 0, "Type": "tree"},
+            {"Path": "BF16/Model-BF16-00001-of-00002.gguf", "Revision": "c0ffee", "Sha256": "bf1", "Size": 20, "Type": "blob"},
+            {"Path": "BF16/Model-BF16-00002-of-00002.gguf", "Revision": "c0ffee", "Sha256": "bf2", "Size": 21, "Type": "blob"},
+            {"Path": "Model-Q4_K_M.gguf", "Revision": "c0ffee", "Sha256": "q4", "Size": 10, "Type": "blob"}
+        ]},
+        "Success": true
+    }"#;
+
+    async fn stand_in() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/models/owner/Model-GGUF/repo/files"))
+            .and(query_param("Revision", "master"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(FILES))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn resolves_a_manifests_files_from_its_listing() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/models/owner/Model-GGUF/repo/files"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(FILES))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let registry = ModelScope::new(server.uri()).unwrap();
+        let model_ref: ModelRef = "owner/Model-GGUF:BF16".parse().unwrap();
+
+        let manifest = registry.manifest(&model_ref).await.unwrap();
+        for file in &manifest.gguf_files {
+            registry.resolve(&model_ref, &file.filename).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn manifest_lists_the_files_of_the_tag() {
+        let server = stand_in().await;
+        let registry = ModelScope::new(server.uri()).unwrap();
+        let model_ref: ModelRef = "owner/Model-GGUF:bf16".parse().unwrap();
+
+        let manifest = registry.manifest(&model_ref).await.unwrap();
+
+        let names: Vec<&str> = manifest
+            .gguf_files
+            .iter()
+            .map(|file| file.filename.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "BF16/Model-BF16-00001-of-00002.gguf",
+                "BF16/Model-BF16-00002-of-00002.gguf",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_reads_the_hash_and_commit_from_the_listing() {
+        let server = stand_in().await;
+        let registry = ModelScope::new(server.uri()).unwrap();
+        let model_ref: ModelRef = "owner/Model-GGUF:Q4_K_M".parse().unwrap();
+
+        let info = registry
+            .resolve(&model_ref, "Model-Q4_K_M.gguf")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            info,
+            ResolveInfo {
+                blob_hash: "q4".to_string(),
+                commit_hash: "c0ffee".to_string(),
+                endpoint: None,
+                sha256: Some("q4".to_string()),
+                size: Some(10),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn tags_lists_every_tag_in_the_repo() {
+        let server = stand_in().await;
+        let registry = ModelScope::new(server.uri()).unwrap();
+        let model_ref: ModelRef = "owner/Model-GGUF:Q4".parse().unwrap();
+
+        assert_eq!(
+            registry.tags(&model_ref).await.unwrap(),
+            vec!["BF16", "Q4_K_M"]
+        );
+    }
+
+    #[test]
+    fn file_url_downloads_from_the_master_branch_by_default() {
+        let registry = ModelScope::new("https://ms.example.test").unwrap();
+        let model_ref: ModelRef = "owner/Model-GGUF:Q4_K_M".parse().unwrap();
+
+        assert_eq!(
+            registry.file_url(&model_ref, "Model-Q4_K_M.gguf"),
+            "https://ms.example.test/api/v1/models/owner/Model-GGUF/repo?Revision=master&FilePath=Model-Q4_K_M.gguf"
+        );
+    }
+
+    #[test]
+    fn file_url_encodes_the_file_path() {
+        let registry = ModelScope::new("https://ms.example.test").unwrap();
+        let model_ref: ModelRef = "owner/Model-GGUF:Q4_K_M".parse().unwrap();
+
+        assert_eq!(
+            registry.file_url(&model_ref, "Q4 K/Model&Q4#1.gguf"),
+            "https://ms.example.test/api/v1/models/owner/Model-GGUF/repo?Revision=master&FilePath=Q4%20K/Model%26Q4%231.gguf"
+        );
+    }
+}
@@ -0,0 +1,694 @@
+use std::sync::Mutex;
+
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Client, Response, StatusCode};
+use serde::Deserialize;
+
+use crate::error::{ModelRefError, PacaError};
+use crate::http::HttpOptions;
+use crate::model::ModelRef;
+use crate::registry::manifest::{GgufFile, Manifest};
+use crate::registry::{BoxFuture, Origin, Registry, ResolveInfo, anonymous_headers, sha256_hex};
+
+/// Manifest formats paca asks for: OCI, and Docker's v2 which Ollama serves.
+const MANIFEST_ACCEPT: &str = "application/vnd.oci.image.manifest.v1+json, application/vnd.docker.distribution.manifest.v2+json";
+
+/// Layer media types holding model weights (Ollama, Docker Model Runner).
+const MODEL_MEDIA_TYPES: [&str; 2] = [
+    "application/vnd.ollama.image.model",
+    "application/vnd.docker.ai.gguf.v3",
+];
+
+/// Layer media types holding a multimodal projector.
+const PROJECTOR_MEDIA_TYPES: [&str; 2] = [
+    "application/vnd.ollama.image.projector",
+    "application/vnd.docker.ai.mmproj",
+];
+
+#[derive(Debug, Deserialize)]
+struct OciManifest {
+    #[serde(default)]
+    layers: Vec<Layer>,
+    #[serde(rename = "mediaType", default)]
+    media_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Layer {
+    digest: String,
+    #[serde(rename = "mediaType")]
+    media_type: String,
+    size: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagList {
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    token: Option<String>,
+}
+
+/// A GGUF layer under the name it is linked into the snapshot as.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct LayerFile {
+    pub(crate) filename: String,
+    pub(crate) sha256: String,
+    pub(crate) size: u64,
+}
+
+/// An OCI distribution registry serving models as artifacts whose layers
+/// are plain GGUF blobs, as Ollama and Docker Model Runner publish them.
+///
+/// A tag's layers are linked as `<tag>/<model>-<tag>.gguf` (sharded when
+/// there are several), so `list` and `rm` find them the way they find a
+/// HuggingFace repo's per-quantization subdirectories. OCI has no commits:
+/// every tag of a repository shares one snapshot named after the
+/// repository, and a retagged model shows up as a blob the cache lacks.
+pub struct Oci {
+    base: String,
+    client: Client,
+    /// A bearer token obtained from the registry's auth challenge, reused
+    /// for the manifest, blob and tag requests that follow.
+    token: Mutex<Option<String>>,
+}
+
+impl Oci {
+    /// A registry at `base`, e.g. `https://registry.ollama.ai`.
+    pub fn new(base: impl Into<String>) -> Result<Self, PacaError> {
+        Self::with_http(base, &HttpOptions::from_env()?)
+    }
+
+    /// A registry at `base` whose client connects as `http` says.
+    pub fn with_http(base: impl Into<String>, http: &HttpOptions) -> Result<Self, PacaError> {
+        Ok(Self {
+            base: base.into().trim_end_matches('/').to_string(),
+            client: http.client(anonymous_headers())?,
+            token: Mutex::new(None),
+        })
+    }
+
+    /// The registry named by the host of an `oci://` reference. Loopback
+    /// hosts are reached over plain HTTP, as Docker allows for local
+    /// registries.
+    pub fn for_host(host: &str) -> Result<Self, PacaError> {
+        Self::new(base_for_host(host))
+    }
+
+    fn bearer(&self) -> Option<String> {
+        self.token
+            .lock()
+            .expect("token lock poisoned")
+            .as_ref()
+            .map(|token| format!("Bearer {token}"))
+    }
+
+    /// GETs `url`, answering a bearer challenge once if the registry
+    /// demands one.
+    async fn get(
+        &self,
+        url: &str,
+        accept: &str,
+        model_ref: &ModelRef,
+    ) -> Result<Response, PacaError> {
+        let response = self.send(url, accept).await?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        let Some(challenge) = response
+            .headers()
+            .get("www-authenticate")
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_challenge)
+        else {
+            return Ok(response);
+        };
+
+        let token = self.fetch_token(&challenge, model_ref).await?;
+        *self.token.lock().expect("token lock poisoned") = Some(token);
+        self.send(url, accept).await
+    }
+
+    async fn send(&self, url: &str, accept: &str) -> Result<Response, PacaError> {
+        let mut request = self.client.get(url).header("Accept", accept);
+        if let Some(bearer) = self.bearer() {
+            request = request.header("Authorization", bearer);
+        }
+        request.send().await.map_err(PacaError::ManifestFetch)
+    }
+
+    async fn fetch_token(
+        &self,
+        challenge: &[(String, String)],
+        model_ref: &ModelRef,
+    ) -> Result<String, PacaError> {
+        let param = |key: &str| {
+            challenge
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.clone())
+        };
+        let realm = param("realm").ok_or(PacaError::Unauthorized)?;
+        let scope =
+            param("scope").unwrap_or_else(|| format!("repository:{}:pull", model_ref.repo()));
+
+        let mut query = vec![("scope", scope)];
+        if let Some(service) = param("service") {
+            query.push(("service", service));
+        }
+
+        let url = reqwest::Url::parse_with_params(&realm, &query)
+            .map_err(|_| PacaError::OciManifest(format!("invalid auth realm {realm}")))?;
+        let parsed: TokenResponse = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(PacaError::ManifestFetch)?
+            .error_for_status()
+            .map_err(PacaError::ManifestFetch)?
+            .json()
+            .await
+            .map_err(PacaError::ManifestFetch)?;
+
+        parsed
+            .token
+            .or(parsed.access_token)
+            .ok_or(PacaError::Unauthorized)
+    }
+
+    async fn layer_files(&self, model_ref: &ModelRef) -> Result<Vec<LayerFile>, PacaError> {
+        validate_tag(&model_ref.tag)?;
+
+        let url = self.manifest_url(model_ref);
+        let manifest: OciManifest = self
+            .get(&url, MANIFEST_ACCEPT, model_ref)
+            .await?
+            .error_for_status()
+            .map_err(PacaError::ManifestFetch)?
+            .json()
+            .await
+            .map_err(PacaError::ManifestFetch)?;
+
+        layer_files(model_ref, manifest)
+    }
+
+    fn manifest_url(&self, model_ref: &ModelRef) -> String {
+        format!(
+            "{}/v2/{}/manifests/{}",
+            self.base,
+            model_ref.repo(),
+            model_ref.tag
+        )
+    }
+
+    /// The snapshot every tag of the repository is linked into.
+    pub(crate) fn commit_hash(&self, model_ref: &ModelRef) -> String {
+        commit_hash(&self.base, model_ref)
+    }
+}
+
+/// The snapshot every tag of a repository at `base` is linked into, for
+/// callers with no [`Oci`] to ask.
+pub(crate) fn commit_hash(base: &str, model_ref: &ModelRef) -> String {
+    sha256_hex(&format!("{base}/{}", model_ref.repo()))
+}
+
+/// The base URL [`Oci::for_host`] reaches `host` at.
+pub(crate) fn base_for_host(host: &str) -> String {
+    let loopback = ["localhost", "127.", "[::1]"]
+        .iter()
+        .any(|prefix| host.starts_with(prefix));
+    let scheme = if loopback { "http" } else { "https" };
+    format!("{scheme}://{host}")
+}
+
+/// Lays out a manifest that was read from disk rather than fetched, such
+/// as one in Ollama's local store.
+pub(crate) fn stored_layer_files(
+    model_ref: &ModelRef,
+    manifest: &str,
+) -> Result<Vec<LayerFile>, PacaError> {
+    validate_tag(&model_ref.tag)?;
+    layer_files(model_ref, serde_json::from_str(manifest)?)
+}
+
+/// Names each GGUF layer after the tag it belongs to. Layers that are
+/// neither weights nor projectors (templates, licenses, params) are
+/// skipped.
+fn layer_files(model_ref: &ModelRef, manifest: OciManifest) -> Result<Vec<LayerFile>, PacaError> {
+    if let Some(media_type) = manifest
+        .media_type
+        .filter(|t| t.contains("index") || t.contains("list"))
+    {
+        return Err(PacaError::OciManifest(format!(
+            "{media_type} (image indexes are not supported)"
+        )));
+    }
+
+    let (tag, model) = (&model_ref.tag, &model_ref.model);
+    let of_kind = |kinds: &[&str]| -> Vec<&Layer> {
+        manifest
+            .layers
+            .iter()
+            .filter(|layer| kinds.contains(&layer.media_type.as_str()))
+            .collect()
+    };
+    let weights = of_kind(&MODEL_MEDIA_TYPES);
+    let projectors = of_kind(&PROJECTOR_MEDIA_TYPES);
+
+    if weights.is_empty() {
+        return Err(PacaError::OciManifest(format!(
+            "{} has no GGUF layer",
+            model_ref
+        )));
+    }
+
+    let mut files = Vec::new();
+    for (i, layer) in weights.iter().enumerate() {
+        let filename = if weights.len() == 1 {
+            format!("{tag}/{model}-{tag}.gguf")
+        } else {
+            format!(
+                "{tag}/{model}-{tag}-{:05}-of-{:05}.gguf",
+                i + 1,
+                weights.len()
+            )
+        };
+        files.push(layer_file(filename, layer)?);
+    }
+    for (i, layer) in projectors.iter().enumerate() {
+        let filename = if projectors.len() == 1 {
+            format!("{tag}/mmproj-{model}-{tag}.gguf")
+        } else {
+            format!("{tag}/mmproj-{model}-{tag}-{}.gguf", i + 1)
+        };
+        files.push(layer_file(filename, layer)?);
+    }
+
+    Ok(files)
+}
+
+fn layer_file(filename: String, layer: &Layer) -> Result<LayerFile, PacaError> {
+    let sha256 = layer
+        .digest
+        .strip_prefix("sha256:")
+        .filter(|hex| hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+        .ok_or_else(|| PacaError::OciManifest(format!("unsupported digest {}", layer.digest)))?;
+
+    Ok(LayerFile {
+        filename,
+        sha256: sha256.to_ascii_lowercase(),
+        size: layer.size,
+    })
+}
+
+/// OCI tags are `[A-Za-z0-9_][A-Za-z0-9._-]{0,127}`. Anything else would
+/// also make a poor directory name in the snapshot.
+fn validate_tag(tag: &str) -> Result<(), PacaError> {
+    let valid = tag.len() <= 128
+        && tag
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ModelRefError::InvalidName(tag.to_string()).into())
+    }
+}
+
+/// Parses `Bearer realm="…",service="…",scope="…"` into its parameters.
+/// Values are quoted and may contain commas (`scope="…:pull,push"`).
+fn parse_challenge(header: &str) -> Option<Vec<(String, String)>> {
+    let (scheme, mut rest) = header.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+
+    let mut params = Vec::new();
+    loop {
+        rest = rest.trim_start_matches([' ', ',']);
+        let Some((key, after)) = rest.split_once('=') else {
+            break;
+        };
+
+        let (value, remainder) = match after.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => after.split_once(',').unwrap_or((after, "")),
+        };
+
+        params.push((key.trim().to_string(), value.to_string()));
+        rest = remainder;
+    }
+
+    Some(params)
+}
+
+impl Registry for Oci {
+    fn manifest<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+    ) -> BoxFuture<'a, Result<Manifest, PacaError>> {
+        Box::pin(async move {
+            let files = self.layer_files(model_ref).await?;
+            Ok(Manifest {
+                primary: files.first().map(|file| file.filename.clone()),
+                response: None,
+                gguf_files: files
+                    .into_iter()
+                    .map(|file| GgufFile {
+                        filename: file.filename,
+                        size: file.size,
+                    })
+                    .collect(),
+            })
+        })
+    }
+
+    fn resolve<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<ResolveInfo, PacaError>> {
+        Box::pin(async move {
+            let file = self
+                .layer_files(model_ref)
+                .await?
+                .into_iter()
+                .find(|file| file.filename == filename)
+                .ok_or_else(|| PacaError::MissingBlobHash(self.file_url(model_ref, filename)))?;
+
+            Ok(ResolveInfo {
+                blob_hash: file.sha256.clone(),
+                commit_hash: self.commit_hash(model_ref),
+                endpoint: None,
+                sha256: Some(file.sha256),
+                size: Some(file.size),
+            })
+        })
+    }
+
+    /// Layers have no URL of their own until resolved to a digest; the
+    /// manifest that lists them stands in.
+    fn file_url(&self, model_ref: &ModelRef, _filename: &str) -> String {
+        self.manifest_url(model_ref)
+    }
+
+    fn blob_url(&self, model_ref: &ModelRef, _filename: &str, resolved: &ResolveInfo) -> String {
+        format!(
+            "{}/v2/{}/blobs/sha256:{}",
+            self.base,
+            model_ref.repo(),
+            resolved.blob_hash
+        )
+    }
+
+    fn tags<'a>(
+        &'a self,
+        model_ref: &'a ModelRef,
+    ) -> BoxFuture<'a, Result<Vec<String>, PacaError>> {
+        Box::pin(async move {
+            let url = format!("{}/v2/{}/tags/list", self.base, model_ref.repo());
+            let mut list: TagList = self
+                .get(&url, "application/json", model_ref)
+                .await?
+                .error_for_status()
+                .map_err(PacaError::ManifestFetch)?
+                .json()
+                .await
+                .map_err(PacaError::ManifestFetch)?;
+            list.tags.sort();
+            Ok(list.tags)
+        })
+    }
+
+    fn headers(&self) -> Result<HeaderMap, PacaError> {
+        let mut headers = anonymous_headers();
+        if let Some(bearer) = self.bearer() {
+            let mut value: HeaderValue = bearer.parse().map_err(|_| PacaError::InvalidToken)?;
+            value.set_sensitive(true);
+            headers.insert("Authorization", value);
+        }
+        Ok(headers)
+    }
+
+    fn origin(&self) -> Origin {
+        Origin::Oci(self.base.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const DIGEST: &str = "sha256:2bd67bd5f5f7ef4a6a1bb7c5a2f3ea3d3a42f4a6c8b7a9c1e8d2f3a4b5c6d7e8";
+
+    fn manifest_json(layers: &str) -> String {
+        format!(
+            r#"{{"schemaVersion":2,"mediaType":"application/vnd.docker.distribution.manifest.v2+json","layers":[{layers}]}}"#
+        )
+    }
+
+    fn layer(media_type: &str, digest: &str, size: u64) -> String {
+        format!(r#"{{"mediaType":"{media_type}","digest":"{digest}","size":{size}}}"#)
+    }
+
+    fn model_ref(s: &str) -> ModelRef {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn layer_files_names_weights_and_projector_under_the_tag() {
+        let manifest: OciManifest = serde_json::from_str(&manifest_json(
+            &[
+                layer("application/vnd.ollama.image.model", DIGEST, 10),
+                layer("application/vnd.ollama.image.template", DIGEST, 1),
+                layer("application/vnd.ollama.image.projector", DIGEST, 2),
+            ]
+            .join(","),
+        ))
+        .unwrap();
+
+        let files = layer_files(&model_ref("library/gemma3:4b"), manifest).unwrap();
+        let names: Vec<&str> = files.iter().map(|f| f.filename.as_str()).collect();
+
+        assert_eq!(names, vec!["4b/gemma3-4b.gguf", "4b/mmproj-gemma3-4b.gguf"]);
+        assert_eq!(files[0].sha256, DIGEST.strip_prefix("sha256:").unwrap());
+    }
+
+    #[test]
+    fn layer_files_shards_multiple_weight_layers() {
+        let manifest: OciManifest = serde_json::from_str(&manifest_json(
+            &[
+                layer("application/vnd.docker.ai.gguf.v3", DIGEST, 10),
+                layer("application/vnd.docker.ai.gguf.v3", DIGEST, 11),
+            ]
+            .join(","),
+        ))
+        .unwrap();
+
+        let files = layer_files(&model_ref("ai/qwen3:latest"), manifest).unwrap();
+        let names: Vec<&str> = files.iter().map(|f| f.filename.as_str()).collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "latest/qwen3-latest-00001-of-00002.gguf",
+                "latest/qwen3-latest-00002-of-00002.gguf",
+            ]
+        );
+    }
+
+    #[test]
+    fn layer_files_rejects_a_manifest_without_weights() {
+        let manifest: OciManifest = serde_json::from_str(&manifest_json(&layer(
+            "application/vnd.oci.image.layer.v1.tar",
+            DIGEST,
+            1,
+        )))
+        .unwrap();
+
+        let result = layer_files(&model_ref("library/gemma3:4b"), manifest);
+        assert!(matches!(result, Err(PacaError::OciManifest(_))));
+    }
+
+    #[test]
+    fn layer_files_rejects_non_sha256_digests() {
+        let manifest: OciManifest = serde_json::from_str(&manifest_json(&layer(
+            "application/vnd.ollama.image.model",
+            "sha512:abc",
+            1,
+        )))
+        .unwrap();
+
+        let result = layer_files(&model_ref("library/gemma3:4b"), manifest);
+        assert!(matches!(result, Err(PacaError::OciManifest(_))));
+    }
+
+    #[test]
+    fn validate_tag_rejects_path_separators() {
+        assert!(validate_tag("4b").is_ok());
+        assert!(validate_tag("v1.0-q4_K_M").is_ok());
+        assert!(validate_tag("a/b").is_err());
+        assert!(validate_tag(".hidden").is_err());
+    }
+
+    #[test]
+    fn parse_challenge_reads_quoted_parameters() {
+        let params = parse_challenge(
+            r#"Bearer realm="https://auth.example.test/token",service="registry",scope="repository:ai/qwen3:pull,push""#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            params,
+            vec![
+                (
+                    "realm".to_string(),
+                    "https://auth.example.test/token".to_string()
+                ),
+                ("service".to_string(), "registry".to_string()),
+                (
+                    "scope".to_string(),
+                    "repository:ai/qwen3:pull,push".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_challenge_ignores_other_schemes() {
+        assert_eq!(parse_challenge(r#"Basic realm="registry""#), None);
+    }
+
+    #[test]
+    fn for_host_uses_plain_http_only_for_loopback() {
+        assert_eq!(
+            Oci::for_host("localhost:5000").unwrap().base,
+            "http://localhost:5000"
+        );
+        assert_eq!(
+            Oci::for_host("registry.ollama.ai").unwrap().base,
+            "https://registry.ollama.ai"
+        );
+    }
+
+    #[tokio::test]
+    async fn manifest_answers_a_bearer_challenge() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .and(query_param("scope", "repository:ai/qwen3:pull"))
+            .and(query_param("service", "stand-in"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token":"t0k"}"#))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v2/ai/qwen3/manifests/latest"))
+            .and(header("authorization", "Bearer t0k"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(manifest_json(&layer(
+                    "application/vnd.docker.ai.gguf.v3",
+                    DIGEST,
+                    42,
+                ))),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v2/ai/qwen3/manifests/latest"))
+            .respond_with(ResponseTemplate::new(401).insert_header(
+                "www-authenticate",
+                format!(
+                    r#"Bearer realm="{}/token",service="stand-in",scope="repository:ai/qwen3:pull""#,
+                    server.uri()
+                ),
+            ))
+            .with_priority(10)
+            .mount(&server)
+            .await;
+
+        let registry = Oci::new(server.uri()).unwrap();
+        let manifest = registry
+            .manifest(&model_ref("ai/qwen3:latest"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            manifest.gguf_files,
+            vec![GgufFile {
+                filename: "latest/qwen3-latest.gguf".to_string(),
+                size: 42,
+            }]
+        );
+        assert_eq!(
+            registry.headers().unwrap().get("authorization").unwrap(),
+            "Bearer t0k"
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_names_the_blob_by_digest_in_a_per_repo_snapshot() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/library/gemma3/manifests/4b"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(manifest_json(&layer(
+                    "application/vnd.ollama.image.model",
+                    DIGEST,
+                    42,
+                ))),
+            )
+            .mount(&server)
+            .await;
+
+        let registry = Oci::new(server.uri()).unwrap();
+        let mr = model_ref("library/gemma3:4b");
+        let info = registry.resolve(&mr, "4b/gemma3-4b.gguf").await.unwrap();
+        let hex = DIGEST.strip_prefix("sha256:").unwrap();
+
+        assert_eq!(info.blob_hash, hex);
+        assert_eq!(info.sha256.as_deref(), Some(hex));
+        assert_eq!(
+            info.commit_hash,
+            registry.commit_hash(&model_ref("library/gemma3:1b"))
+        );
+        assert_eq!(
+            registry.blob_url(&mr, "4b/gemma3-4b.gguf", &info),
+            format!("{}/v2/library/gemma3/blobs/{DIGEST}", server.uri())
+        );
+    }
+
+    #[tokio::test]
+    async fn tags_lists_the_repository_tags() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/library/gemma3/tags/list"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"name":"library/gemma3","tags":["4b","1b","latest"]}"#),
+            )
+            .mount(&server)
+            .await;
+
+        let registry = Oci::new(server.uri()).unwrap();
+        let tags = registry.tags(&model_ref("library/gemma3:4")).await.unwrap();
+
+        assert_eq!(tags, vec!["1b", "4b", "latest"]);
+    }
+}
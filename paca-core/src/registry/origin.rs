@@ -0,0 +1,110 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::error::PacaError;
+use crate::registry::{HttpDirectory, HuggingFace, ModelScope, Oci, Registry};
+
+/// The registry a cached repo came from, recorded beside its `refs/` so
+/// `outdated` asks that registry again, and so repos of the same name on
+/// two registries do not end up sharing one directory.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Origin {
+    /// A HuggingFace-compatible hub. Mirrors serve the same repos, so
+    /// whichever endpoints are configured are asked.
+    HuggingFace,
+    /// A ModelScope hub at this endpoint.
+    ModelScope(String),
+    /// A plain HTTP directory at this base URL.
+    HttpDirectory(String),
+    /// An OCI registry at this base URL.
+    Oci(String),
+    /// Imported from a local store, such as LM Studio's, that records no
+    /// registry to check against.
+    Import(String),
+}
+
+impl Origin {
+    /// A registry to check the repo against, with the environment's
+    /// settings, or `None` for an import.
+    pub fn registry(&self) -> Result<Option<Arc<dyn Registry>>, PacaError> {
+        Ok(Some(match self {
+            Self::HuggingFace => Arc::new(HuggingFace::from_env()?),
+            Self::ModelScope(endpoint) => Arc::new(ModelScope::new(endpoint)?),
+            Self::HttpDirectory(base) => Arc::new(HttpDirectory::new(base)?),
+            Self::Oci(base) => Arc::new(Oci::new(base)?),
+            Self::Import(_) => return Ok(None),
+        }))
+    }
+
+    /// Whether a repo recorded as `self` may take files from `incoming`.
+    /// Imports give way to a download, and join whatever they find.
+    pub(crate) fn admits(&self, incoming: &Origin) -> bool {
+        self == incoming || matches!(self, Self::Import(_)) || matches!(incoming, Self::Import(_))
+    }
+}
+
+/// One line: the kind, then the endpoint or store, as in
+/// `oci https://registry.ollama.ai`.
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HuggingFace => f.write_str("huggingface"),
+            Self::ModelScope(endpoint) => write!(f, "modelscope {endpoint}"),
+            Self::HttpDirectory(base) => write!(f, "http {base}"),
+            Self::Oci(base) => write!(f, "oci {base}"),
+            Self::Import(source) => write!(f, "import {source}"),
+        }
+    }
+}
+
+impl FromStr for Origin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (kind, value) = s.split_once(' ').unwrap_or((s, ""));
+        let value = value.trim().to_string();
+        match (kind, value.is_empty()) {
+            ("huggingface", true) => Ok(Self::HuggingFace),
+            ("modelscope", false) => Ok(Self::ModelScope(value)),
+            ("http", false) => Ok(Self::HttpDirectory(value)),
+            ("oci", false) => Ok(Self::Oci(value)),
+            ("import", false) => Ok(Self::Import(value)),
+            _ => Err(format!("unknown origin '{s}'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_its_line() {
+        for origin in [
+            Origin::HuggingFace,
+            Origin::ModelScope("https://www.modelscope.cn".to_string()),
+            Origin::HttpDirectory("https://models.example.com".to_string()),
+            Origin::Oci("https://registry.ollama.ai".to_string()),
+            Origin::Import("lmstudio".to_string()),
+        ] {
+            assert_eq!(origin.to_string().parse::<Origin>(), Ok(origin));
+        }
+        assert!("oci".parse::<Origin>().is_err());
+        assert!("ftp example.com".parse::<Origin>().is_err());
+    }
+
+    #[test]
+    fn imports_give_way_but_registries_do_not() {
+        let hf = Origin::HuggingFace;
+        let oci = Origin::Oci("https://registry.ollama.ai".to_string());
+        let lmstudio = Origin::Import("lmstudio".to_string());
+
+        assert!(hf.admits(&hf));
+        assert!(!hf.admits(&oci));
+        assert!(!oci.admits(&hf));
+        assert!(lmstudio.admits(&oci));
+        assert!(hf.admits(&lmstudio));
+    }
+}
@@ -0,0 +1,198 @@
+//! Commands that launch llama.cpp, or any other program, on a model
+//! resolved from the cache with [`resolve_local_model`].
+//!
+//! Arguments may name the model's files through placeholders, substituted
+//! wherever they appear in an argument:
+//!
+//! - `{model}`: the model file, the first shard when sharded
+//! - `{mmproj}`: the multimodal projector
+//! - `{dir}`: the directory holding the model file
+//!
+//! [`resolve_local_model`]: crate::cache::resolve_local_model
+
+use std::ffi::OsStr;
+use std::process::Command;
+
+use crate::cache::LocalModel;
+use crate::error::PacaError;
+
+/// The llama.cpp binary launched when none is configured.
+pub const DEFAULT_BINARY: &str = "llama-server";
+
+/// `binary -m <model> [--mmproj <mmproj>] <args>`, the invocation
+/// `llama-server` and `llama-cli` both accept.
+pub fn llama_command(
+    binary: impl AsRef<OsStr>,
+    model: &LocalModel,
+    args: &[String],
+) -> Result<Command, PacaError> {
+    let mut command = Command::new(binary);
+    command.arg("-m").arg(&model.model);
+    if let Some(mmproj) = &model.mmproj {
+        command.arg("--mmproj").arg(mmproj);
+    }
+    command.args(substitute_all(args, model)?);
+    Ok(command)
+}
+
+/// An arbitrary command, `argv[0]` being the program, with nothing added
+/// beyond its placeholders.
+pub fn custom_command(argv: &[String], model: &LocalModel) -> Result<Command, PacaError> {
+    let argv = substitute_all(argv, model)?;
+    let (program, args) = argv.split_first().ok_or(PacaError::EmptyCommand)?;
+    let mut command = Command::new(program);
+    command.args(args);
+    Ok(command)
+}
+
+fn substitute_all(args: &[String], model: &LocalModel) -> Result<Vec<String>, PacaError> {
+    args.iter().map(|arg| substitute(arg, model)).collect()
+}
+
+/// Replaces every placeholder in `arg`. Naming `{mmproj}` for a model
+/// without one is an error rather than an empty argument, which the
+/// launched program would misread.
+fn substitute(arg: &str, model: &LocalModel) -> Result<String, PacaError> {
+    let missing = |placeholder: &str| PacaError::MissingPlaceholder {
+        model: model.model.display().to_string(),
+        placeholder: placeholder.to_string(),
+    };
+
+    let mut arg = arg.replace("{model}", &model.model.to_string_lossy());
+    if let Some(dir) = model.model.parent() {
+        arg = arg.replace("{dir}", &dir.to_string_lossy());
+    }
+    if arg.contains("{mmproj}") {
+        let mmproj = model.mmproj.as_ref().ok_or_else(|| missing("mmproj"))?;
+        arg = arg.replace("{mmproj}", &mmproj.to_string_lossy());
+    }
+    Ok(arg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+
+    fn local(mmproj: bool) -> LocalModel {
+        let model = PathBuf::from("/hub/snapshots/abc/Q8_0/Model-Q8_0-00001-of-00002.gguf");
+        LocalModel {
+            shards: vec![
+                model.clone(),
+                PathBuf::from("/hub/snapshots/abc/Q8_0/Model-Q8_0-00002-of-00002.gguf"),
+            ],
+            model,
+            mmproj: mmproj.then(|| PathBuf::from("/hub/snapshots/abc/mmproj-F16.gguf")),
+        }
+    }
+
+    /// A stand-in binary that writes each argument it receives to a line
+    /// of `argv`, next to it.
+    fn stub_binary(dir: &Path) -> PathBuf {
+        let path = dir.join("llama-server");
+        let argv = dir.join("argv");
+        fs::write(
+            &path,
+            format!("#!/bin/sh\nprintf '%s\\n' \"$@\" > '{}'\n", argv.display()),
+        )
+        .unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn recorded_argv(dir: &Path) -> Vec<String> {
+        fs::read_to_string(dir.join("argv"))
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn llama_command_passes_the_model_and_projector() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = stub_binary(dir.path());
+        let args = vec!["--ctx-size".to_string(), "32768".to_string()];
+
+        let status = llama_command(&binary, &local(true), &args)
+            .unwrap()
+            .status()
+            .unwrap();
+
+        assert!(status.success());
+        assert_eq!(
+            recorded_argv(dir.path()),
+            vec![
+                "-m",
+                "/hub/snapshots/abc/Q8_0/Model-Q8_0-00001-of-00002.gguf",
+                "--mmproj",
+                "/hub/snapshots/abc/mmproj-F16.gguf",
+                "--ctx-size",
+                "32768",
+            ]
+        );
+    }
+
+    #[test]
+    fn llama_command_omits_a_missing_projector() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = stub_binary(dir.path());
+
+        llama_command(&binary, &local(false), &[])
+            .unwrap()
+            .status()
+            .unwrap();
+
+        assert_eq!(
+            recorded_argv(dir.path()),
+            vec![
+                "-m",
+                "/hub/snapshots/abc/Q8_0/Model-Q8_0-00001-of-00002.gguf"
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_command_substitutes_placeholders() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = stub_binary(dir.path());
+        let argv = vec![
+            binary.display().to_string(),
+            "--model={model}".to_string(),
+            "{mmproj}".to_string(),
+            "{dir}".to_string(),
+        ];
+
+        custom_command(&argv, &local(true))
+            .unwrap()
+            .status()
+            .unwrap();
+
+        assert_eq!(
+            recorded_argv(dir.path()),
+            vec![
+                "--model=/hub/snapshots/abc/Q8_0/Model-Q8_0-00001-of-00002.gguf",
+                "/hub/snapshots/abc/mmproj-F16.gguf",
+                "/hub/snapshots/abc/Q8_0",
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_command_rejects_a_missing_projector() {
+        let argv = vec!["server".to_string(), "{mmproj}".to_string()];
+        let result = custom_command(&argv, &local(false));
+        assert!(matches!(
+            result,
+            Err(PacaError::MissingPlaceholder { placeholder, .. }) if placeholder == "mmproj"
+        ));
+    }
+
+    #[test]
+    fn custom_command_rejects_an_empty_command() {
+        let result = custom_command(&[], &local(false));
+        assert!(matches!(result, Err(PacaError::EmptyCommand)));
+    }
+}
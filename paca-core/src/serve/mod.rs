@@ -0,0 +1,731 @@
+//! An HTTP server that serves the hub cache as a HuggingFace mirror.
+//!
+//! One machine downloads; every other host on the network points
+//! `HF_ENDPOINT` at it, and paca and llama.cpp fetch from its cache over
+//! the endpoints they already use:
+//!
+//! - `GET /v2/{owner}/{repo}/manifests/{tag}`, the tag's manifest
+//! - `HEAD`/`GET /{owner}/{repo}/resolve/{revision}/{path}`, a file, with
+//!   `x-repo-commit`, its blob hash as the ETag, and `Range` support
+//! - `GET /api/models/{owner}/{repo}/tree/{revision}/{path}`, a listing,
+//!   `?recursive=true` for the whole subtree
+//!
+//! Only what the cache holds is served, and the server is read-only,
+//! unless [`Server::with_upstream`] names a hub to pull the rest from.
+
+use std::convert::Infallible;
+use std::fs;
+use std::io::{self, SeekFrom};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use futures_util::TryStreamExt;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::{Bytes, Frame, Incoming};
+use hyper::header::{self, HeaderValue};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio_util::io::ReaderStream;
+
+use crate::auth::resolve_token_for;
+use crate::cache::{HubLayout, collect_gguf_paths, installed_tag_files, is_mmproj};
+use crate::download::sha256_file_async;
+use crate::env;
+use crate::error::PacaError;
+use crate::http::HttpOptions;
+use crate::model::{DEFAULT_REVISION, DEFAULT_TAG, ModelRef, percent_decode};
+use crate::path::join_within;
+use crate::registry::manifest::{PREFERRED_TAG, tags_in};
+
+mod proxy;
+
+use proxy::Upstream;
+
+type Body = BoxBody<Bytes, io::Error>;
+
+/// A bound mirror server; [`Server::run`] starts answering requests.
+pub struct Server {
+    /// How the upstream is reached.
+    http: HttpOptions,
+    mirror: Mirror,
+    listener: TcpListener,
+}
+
+struct Mirror {
+    hub: HubLayout,
+    upstream: Option<Upstream>,
+}
+
+impl Server {
+    /// Binds `addr` to serve the hub cache at `hub_dir`, or the one the
+    /// environment names.
+    pub async fn bind(
+        addr: impl ToSocketAddrs,
+        hub_dir: Option<PathBuf>,
+    ) -> Result<Self, PacaError> {
+        Ok(Self {
+            http: HttpOptions::from_env()?,
+            mirror: Mirror {
+                hub: HubLayout::open(hub_dir)?,
+                upstream: None,
+            },
+            listener: TcpListener::bind(addr).await.map_err(PacaError::Serve)?,
+        })
+    }
+
+    /// Timeouts, proxy, certificates and other settings for reaching the
+    /// upstream, instead of the environment's. Set them before naming the
+    /// upstream.
+    #[must_use]
+    pub fn with_http(mut self, http: HttpOptions) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Pulls what the cache lacks from the hub at `endpoint`, serving it
+    /// while it is stored, so the cache fills as clients ask.
+    pub fn with_upstream(self, endpoint: impl Into<String>) -> Result<Self, PacaError> {
+        let endpoint = endpoint.into();
+        let token = resolve_token_for(&endpoint)?;
+        self.with_upstream_token(endpoint, token.as_deref())
+    }
+
+    /// [`Server::with_upstream`], authenticated with `token` rather than
+    /// the environment's, or anonymous without one.
+    pub fn with_upstream_token(
+        mut self,
+        endpoint: impl Into<String>,
+        token: Option<&str>,
+    ) -> Result<Self, PacaError> {
+        env::ensure_online()?;
+        self.mirror.upstream = Some(Upstream::new(endpoint.into(), token, &self.http)?);
+        Ok(self)
+    }
+
+    /// The address bound, which names the port when binding port 0.
+    pub fn local_addr(&self) -> Result<SocketAddr, PacaError> {
+        self.listener.local_addr().map_err(PacaError::Serve)
+    }
+
+    /// The directory being served.
+    pub fn hub_dir(&self) -> &Path {
+        self.mirror.hub.root()
+    }
+
+    /// Serves connections until accepting one fails.
+    pub async fn run(self) -> Result<(), PacaError> {
+        let mirror = Arc::new(self.mirror);
+        loop {
+            let (stream, _) = self.listener.accept().await.map_err(PacaError::Serve)?;
+            let mirror = Arc::clone(&mirror);
+            tokio::spawn(async move {
+                let service = service_fn(move |request| handle(Arc::clone(&mirror), request));
+                // A client hanging up mid-transfer is not the server's
+                // failure, and there is no one else to tell.
+                let _ = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await;
+            });
+        }
+    }
+}
+
+async fn handle(
+    mirror: Arc<Mirror>,
+    request: Request<Incoming>,
+) -> Result<Response<Body>, Infallible> {
+    let mut result = route(&mirror.hub, &request).await;
+    if let Err((StatusCode::NOT_FOUND, _)) = result
+        && let Some(upstream) = &mirror.upstream
+    {
+        result = upstream.route(&mirror.hub, &request).await;
+    }
+    Ok(result.unwrap_or_else(|(status, message)| error_response(status, &message)))
+}
+
+/// A failed request: its status and the message for the `error` field
+/// the hub puts in error bodies.
+type Failure = (StatusCode, String);
+
+async fn route(hub: &HubLayout, request: &Request<Incoming>) -> Result<Response<Body>, Failure> {
+    let Some(segments) = path_segments(request.uri().path()) else {
+        return Err(not_found("Malformed path"));
+    };
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let method = request.method();
+    let readable = method == Method::GET || method == Method::HEAD;
+
+    match segments.as_slice() {
+        ["v2", owner, model, "manifests", tag] if readable => {
+            manifest(hub, &model_ref(owner, model, tag)?)
+        }
+        ["api", "models", owner, model, "tree", revision, path @ ..] if readable => {
+            let recursive = request
+                .uri()
+                .query()
+                .is_some_and(|query| query.split('&').any(|pair| pair == "recursive=true"));
+            let model_ref = model_ref(owner, model, DEFAULT_TAG)?;
+            tree(hub, &model_ref, revision, &path.join("/"), recursive)
+        }
+        [owner, model, "resolve", revision, path @ ..] if readable && !path.is_empty() => {
+            let model_ref = model_ref(owner, model, DEFAULT_TAG)?;
+            let range = request.headers().get(header::RANGE);
+            resolve(hub, &model_ref, revision, &path.join("/"), range, method).await
+        }
+        _ if !readable => Err((
+            StatusCode::METHOD_NOT_ALLOWED,
+            "This mirror is read-only".to_string(),
+        )),
+        _ => Err(not_found("No such endpoint")),
+    }
+}
+
+fn model_ref(owner: &str, model: &str, tag: &str) -> Result<ModelRef, Failure> {
+    format!("{owner}/{model}:{tag}")
+        .parse()
+        .map_err(|e: crate::error::ModelRefError| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// The manifest the hub would give for the tag, naming its first shard
+/// and projector. [`DEFAULT_TAG`] picks [`PREFERRED_TAG`] when the cache
+/// has it and the first tag otherwise, as registries without a default
+/// of their own do.
+fn manifest(hub: &HubLayout, model_ref: &ModelRef) -> Result<Response<Body>, Failure> {
+    let mut model_ref = model_ref.clone();
+    if model_ref.tag == DEFAULT_TAG {
+        model_ref.tag = default_tag(hub, &model_ref)?;
+    }
+
+    let files = installed_tag_files(hub, &model_ref).map_err(|e| not_found(&e.to_string()))?;
+    let entry = |relative: &str| -> Result<serde_json::Value, Failure> {
+        let size = fs::metadata(files.snapshot.join(relative))
+            .map_err(|_| not_found(&format!("{relative} is missing from the cache")))?
+            .len();
+        Ok(json!({ "rfilename": relative, "size": size }))
+    };
+
+    let mut body = json!({ "ggufFile": entry(&files.model[0])? });
+    if let Some(mmproj) = &files.mmproj {
+        body["mmprojFile"] = entry(mmproj)?;
+    }
+    Ok(json_response(&body))
+}
+
+fn default_tag(hub: &HubLayout, model_ref: &ModelRef) -> Result<String, Failure> {
+    let missing = || not_found(&format!("{} is not in the cache", model_ref.repo()));
+    let (snapshot, _) = snapshot(hub, model_ref, DEFAULT_REVISION)?;
+    let mut relatives: Vec<String> = Vec::new();
+    collect_gguf_paths(&snapshot, &snapshot, &mut relatives).map_err(|_| missing())?;
+
+    let tags = tags_in(
+        relatives
+            .iter()
+            .filter(|relative| !is_mmproj(relative))
+            .map(String::as_str),
+        &model_ref.model,
+    );
+    tags.iter()
+        .find(|tag| tag.eq_ignore_ascii_case(PREFERRED_TAG))
+        .or_else(|| tags.first())
+        .cloned()
+        .ok_or_else(missing)
+}
+
+/// Lists a snapshot directory the way the hub's tree API does. Directories
+/// carry a size of 0, as there.
+fn tree(
+    hub: &HubLayout,
+    model_ref: &ModelRef,
+    revision: &str,
+    path: &str,
+    recursive: bool,
+) -> Result<Response<Body>, Failure> {
+    let (snapshot, _) = snapshot(hub, model_ref, revision)?;
+    let dir = if path.is_empty() {
+        snapshot.clone()
+    } else {
+        join_within(&snapshot, path).map_err(|e| not_found(&e.to_string()))?
+    };
+    if !dir.is_dir() {
+        return Err(not_found(&format!("{path} is not a directory")));
+    }
+
+    let mut entries = Vec::new();
+    list_tree(&snapshot, &dir, recursive, &mut entries)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(json_response(&serde_json::Value::Array(entries)))
+}
+
+fn list_tree(
+    snapshot: &Path,
+    dir: &Path,
+    recursive: bool,
+    entries: &mut Vec<serde_json::Value>,
+) -> io::Result<()> {
+    let mut children: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    children.sort();
+
+    for child in children {
+        let relative = child
+            .strip_prefix(snapshot)
+            .unwrap_or(&child)
+            .to_string_lossy()
+            .into_owned();
+        if child.is_dir() {
+            entries.push(json!({ "type": "directory", "path": relative, "size": 0 }));
+            if recursive {
+                list_tree(snapshot, &child, recursive, entries)?;
+            }
+        } else if let Ok(metadata) = fs::metadata(&child) {
+            // Dangling links are not offered: they could not be served.
+            entries.push(json!({ "type": "file", "path": relative, "size": metadata.len() }));
+        }
+    }
+    Ok(())
+}
+
+async fn resolve(
+    hub: &HubLayout,
+    model_ref: &ModelRef,
+    revision: &str,
+    path: &str,
+    range: Option<&HeaderValue>,
+    method: &Method,
+) -> Result<Response<Body>, Failure> {
+    let (snapshot, commit) = snapshot(hub, model_ref, revision)?;
+    let missing = || not_found(&format!("{path} is not in the cache"));
+    let file = join_within(&snapshot, path).map_err(|_| missing())?;
+    let size = fs::metadata(&file)
+        .ok()
+        .filter(fs::Metadata::is_file)
+        .ok_or_else(missing)?
+        .len();
+    let info = FileInfo {
+        commit,
+        etag: blob_hash(&file)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+        size,
+    };
+
+    let Some((status, start, length)) = span(range, size) else {
+        return Ok(unsatisfiable(size));
+    };
+    let body = if method == Method::HEAD {
+        empty()
+    } else {
+        file_body(&file, start, length)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    };
+    Ok(file_response(body, status, start, length, &info))
+}
+
+/// What the hub says about a file alongside its content.
+struct FileInfo {
+    commit: String,
+    /// The blob hash, unquoted.
+    etag: String,
+    size: u64,
+}
+
+/// The status and `(start, length)` to answer `range` with, or `None`
+/// when it cannot be.
+fn span(range: Option<&HeaderValue>, size: u64) -> Option<(StatusCode, u64, u64)> {
+    match range.map(|value| parse_range(value, size)) {
+        None | Some(Range::Ignored) => Some((StatusCode::OK, 0, size)),
+        Some(Range::Satisfiable(start, end)) => {
+            Some((StatusCode::PARTIAL_CONTENT, start, end - start + 1))
+        }
+        Some(Range::Unsatisfiable) => None,
+    }
+}
+
+fn unsatisfiable(size: u64) -> Response<Body> {
+    let mut response = error_response(StatusCode::RANGE_NOT_SATISFIABLE, "Range not satisfiable");
+    response.headers_mut().insert(
+        header::CONTENT_RANGE,
+        header_value(&format!("bytes */{size}")),
+    );
+    response
+}
+
+fn file_response(
+    body: Body,
+    status: StatusCode,
+    start: u64,
+    length: u64,
+    info: &FileInfo,
+) -> Response<Body> {
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert("x-repo-commit", header_value(&info.commit));
+    headers.insert(header::ETAG, header_value(&format!("\"{}\"", info.etag)));
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    headers.insert(header::CONTENT_LENGTH, header_value(&length.to_string()));
+    if status == StatusCode::PARTIAL_CONTENT {
+        let end = start + length - 1;
+        headers.insert(
+            header::CONTENT_RANGE,
+            header_value(&format!("bytes {start}-{end}/{}", info.size)),
+        );
+    }
+    response
+}
+
+/// The snapshot a revision names, and its commit: `main` and any other
+/// recorded ref are read from `refs/`, anything else must be a commit the
+/// cache has a snapshot of.
+fn snapshot(
+    hub: &HubLayout,
+    model_ref: &ModelRef,
+    revision: &str,
+) -> Result<(PathBuf, String), Failure> {
+    let not_cached = || {
+        not_found(&format!(
+            "{}@{revision} is not in the cache",
+            model_ref.repo()
+        ))
+    };
+    let paths = hub.model(model_ref).map_err(|_| not_cached())?;
+    let commit = match join_within(&paths.refs(), revision)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+    {
+        Some(commit) => commit.trim().to_string(),
+        None => revision.to_string(),
+    };
+    let snapshot = paths.snapshot(&commit).map_err(|_| not_cached())?;
+    if !snapshot.is_dir() {
+        return Err(not_cached());
+    }
+    Ok((snapshot, commit))
+}
+
+/// The name of the blob a snapshot file links to, which is the hash the
+/// hub gave it. A file placed in the snapshot directly is hashed instead.
+async fn blob_hash(file: &Path) -> io::Result<String> {
+    match fs::read_link(file) {
+        Ok(target) => Ok(target
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()),
+        Err(_) => sha256_file_async(file).await,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Range {
+    /// Inclusive byte offsets.
+    Satisfiable(u64, u64),
+    Unsatisfiable,
+    /// Not a single byte range; served as a whole file, which RFC 9110
+    /// allows.
+    Ignored,
+}
+
+fn parse_range(value: &HeaderValue, size: u64) -> Range {
+    let Some(spec) = value
+        .to_str()
+        .ok()
+        .and_then(|v| v.trim().strip_prefix("bytes="))
+    else {
+        return Range::Ignored;
+    };
+    let Some((start, end)) = spec.split_once('-') else {
+        return Range::Ignored;
+    };
+    if spec.contains(',') {
+        return Range::Ignored;
+    }
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return Range::Unsatisfiable,
+            Ok(suffix) => (size.saturating_sub(suffix), size.saturating_sub(1)),
+            Err(_) => return Range::Ignored,
+        },
+        (start, "") => match start.parse() {
+            Ok(start) => (start, size.saturating_sub(1)),
+            Err(_) => return Range::Ignored,
+        },
+        (start, end) => match (start.parse(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(size.saturating_sub(1))),
+            _ => return Range::Ignored,
+        },
+    };
+
+    if size == 0 || start >= size {
+        Range::Unsatisfiable
+    } else {
+        Range::Satisfiable(start, end)
+    }
+}
+
+async fn file_body(file: &Path, start: u64, length: u64) -> io::Result<Body> {
+    let mut file = tokio::fs::File::open(file).await?;
+    file.seek(SeekFrom::Start(start)).await?;
+    let stream = ReaderStream::with_capacity(file.take(length), 1 << 16).map_ok(Frame::data);
+    Ok(BodyExt::boxed(StreamBody::new(stream)))
+}
+
+/// The request path split into percent-decoded segments, or `None` when
+/// it does not decode to UTF-8.
+fn path_segments(path: &str) -> Option<Vec<String>> {
+    path.trim_start_matches('/')
+        .split('/')
+        .map(percent_decode)
+        .collect()
+}
+
+fn not_found(message: &str) -> Failure {
+    (StatusCode::NOT_FOUND, message.to_string())
+}
+
+fn json_response(value: &serde_json::Value) -> Response<Body> {
+    let mut response = Response::new(full(value.to_string()));
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = json_response(&json!({ "error": message }));
+    *response.status_mut() = status;
+    response
+}
+
+fn header_value(value: &str) -> HeaderValue {
+    HeaderValue::from_str(value).unwrap_or_else(|_| HeaderValue::from_static(""))
+}
+
+fn full(body: impl Into<Bytes>) -> Body {
+    Full::new(body.into())
+        .map_err(|never| match never {})
+        .boxed()
+}
+
+fn empty() -> Body {
+    full(String::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::download::{download_model, fetch_manifest};
+    use crate::progress::FileProgress;
+    use crate::registry::HuggingFace;
+    use crate::test_support::{setup_model_dir, write_ref};
+
+    struct NoProgress;
+
+    impl FileProgress for NoProgress {
+        fn start(&self, _: u64) {}
+        fn inc(&self, _: u64) {}
+        fn println(&self, _: &str) {}
+        fn finish(&self) {}
+    }
+
+    /// Fills a cache the way a download would, with each blob named by
+    /// the SHA-256 of its content.
+    pub(super) fn install(hub: &Path, commit: &str, files: &[(&str, &[u8])]) {
+        let model_dir = setup_model_dir(hub, "owner", "Model-GGUF");
+        write_ref(&model_dir, commit);
+        for (file, content) in files {
+            let blob = model_dir.join("blobs").join(crate::registry::sha256_hex(
+                std::str::from_utf8(content).unwrap(),
+            ));
+            fs::write(&blob, content).unwrap();
+            let link = model_dir.join("snapshots").join(commit).join(file);
+            fs::create_dir_all(link.parent().unwrap()).unwrap();
+            let depth = file.matches('/').count() + 2;
+            let target = Path::new(&"../".repeat(depth))
+                .join("blobs")
+                .join(blob.file_name().unwrap());
+            std::os::unix::fs::symlink(target, link).unwrap();
+        }
+    }
+
+    pub(super) async fn serve(hub: &Path) -> String {
+        let server = Server::bind("127.0.0.1:0", Some(hub.to_path_buf()))
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+        format!("http://{addr}")
+    }
+
+    pub(super) async fn download(endpoint: &str, model: &str, hub: &Path) -> Vec<PathBuf> {
+        let registry = Arc::new(HuggingFace::new(endpoint).unwrap());
+        let manifest = fetch_manifest(registry.as_ref(), model).await.unwrap();
+        let progress: Vec<Arc<dyn FileProgress>> = manifest
+            .files()
+            .map(|_| Arc::new(NoProgress) as Arc<dyn FileProgress>)
+            .collect();
+        download_model(registry, manifest, Some(hub.to_path_buf()), progress)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|file| file.path)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn paca_downloads_a_sharded_tag_from_the_mirror() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        install(
+            &source,
+            "commit1",
+            &[
+                ("Q8_0/Model-Q8_0-00001-of-00002.gguf", b"first shard"),
+                ("Q8_0/Model-Q8_0-00002-of-00002.gguf", b"second shard"),
+                ("Model-Q4_K_M.gguf", b"q4"),
+                ("mmproj-F16.gguf", b"projector"),
+            ],
+        );
+        let endpoint = serve(&source).await;
+
+        let target = dir.path().join("target");
+        let mut paths = temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            download(&endpoint, "owner/Model-GGUF:q8_0", &target).await
+        })
+        .await;
+        paths.sort();
+
+        let snapshot = target.join("models--owner--Model-GGUF/snapshots/commit1");
+        assert_eq!(
+            paths,
+            vec![
+                snapshot.join("Q8_0/Model-Q8_0-00001-of-00002.gguf"),
+                snapshot.join("Q8_0/Model-Q8_0-00002-of-00002.gguf"),
+                snapshot.join("mmproj-F16.gguf"),
+            ]
+        );
+        assert_eq!(fs::read(&paths[1]).unwrap(), b"second shard");
+        // The blob names carry over, so both caches agree on them.
+        assert_eq!(
+            fs::read_link(&paths[2]).unwrap().file_name(),
+            fs::read_link(
+                source.join("models--owner--Model-GGUF/snapshots/commit1/mmproj-F16.gguf")
+            )
+            .unwrap()
+            .file_name()
+        );
+    }
+
+    #[tokio::test]
+    async fn paca_downloads_the_default_tag_from_the_mirror() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        install(
+            &source,
+            "commit1",
+            &[("Model-Q4_K_M.gguf", b"q4"), ("Model-Q8_0.gguf", b"q8")],
+        );
+        let endpoint = serve(&source).await;
+
+        let target = dir.path().join("target");
+        let paths = temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            download(&endpoint, "owner/Model-GGUF", &target).await
+        })
+        .await;
+
+        assert_eq!(paths.len(), 1);
+        assert!(paths[0].ends_with("Model-Q4_K_M.gguf"));
+    }
+
+    #[tokio::test]
+    async fn resolve_serves_ranges_and_head() {
+        let dir = tempfile::tempdir().unwrap();
+        install(
+            dir.path(),
+            "commit1",
+            &[("Model-Q4_K_M.gguf", b"0123456789")],
+        );
+        let endpoint = serve(dir.path()).await;
+        let url = format!("{endpoint}/owner/Model-GGUF/resolve/main/Model-Q4_K_M.gguf");
+        let client = reqwest::Client::new();
+
+        let head = client.head(&url).send().await.unwrap();
+        assert_eq!(head.status(), 200);
+        assert_eq!(head.headers()["x-repo-commit"], "commit1");
+        assert_eq!(head.headers()["content-length"], "10");
+        assert_eq!(
+            head.headers()["etag"].to_str().unwrap(),
+            format!("\"{}\"", crate::registry::sha256_hex("0123456789"))
+        );
+
+        let partial = client
+            .get(&url)
+            .header("Range", "bytes=2-5")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(partial.status(), 206);
+        assert_eq!(partial.headers()["content-range"], "bytes 2-5/10");
+        assert_eq!(partial.bytes().await.unwrap().as_ref(), b"2345");
+
+        let beyond = client
+            .get(&url)
+            .header("Range", "bytes=10-")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(beyond.status(), 416);
+    }
+
+    #[tokio::test]
+    async fn serves_nothing_outside_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        install(dir.path(), "commit1", &[("Model-Q4_K_M.gguf", b"q4")]);
+        let endpoint = serve(dir.path()).await;
+        let client = reqwest::Client::new();
+
+        for path in [
+            "/owner/Model-GGUF/resolve/main/..%2F..%2F..%2Frefs%2Fmain",
+            "/owner/Model-GGUF/resolve/main/Model-Q8_0.gguf",
+            "/owner/Other-GGUF/resolve/main/Model-Q4_K_M.gguf",
+            "/v2/owner/Model-GGUF/manifests/Q8_0",
+        ] {
+            let response = client
+                .get(format!("{endpoint}{path}"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 404, "{path}");
+        }
+
+        let response = client
+            .post(format!("{endpoint}/v2/owner/Model-GGUF/manifests/Q4_K_M"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 405);
+    }
+
+    #[test]
+    fn parse_range_reads_single_byte_ranges() {
+        let range = |value: &str| parse_range(&HeaderValue::from_str(value).unwrap(), 10);
+        assert_eq!(range("bytes=0-3"), Range::Satisfiable(0, 3));
+        assert_eq!(range("bytes=4-"), Range::Satisfiable(4, 9));
+        assert_eq!(range("bytes=-3"), Range::Satisfiable(7, 9));
+        assert_eq!(range("bytes=8-20"), Range::Satisfiable(8, 9));
+        assert_eq!(range("bytes=10-"), Range::Unsatisfiable);
+        assert_eq!(range("bytes=0-1,4-5"), Range::Ignored);
+        assert_eq!(range("items=0-1"), Range::Ignored);
+    }
+}
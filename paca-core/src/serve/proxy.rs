@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use futures_util::stream;
+use http_body_util::{BodyExt, StreamBody};
+use hyper::body::{Bytes, Frame, Incoming};
+use hyper::header::{self, HeaderValue};
+use hyper::{Method, Request, Response, StatusCode};
+use reqwest::Client;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::watch;
+
+use crate::cache::HubLayout;
+use crate::download::{Fetch, ResolvedFile, install_file, is_commit_hash, partial_path};
+use crate::error::PacaError;
+use crate::http::HttpOptions;
+use crate::model::{DEFAULT_REVISION, DEFAULT_TAG, ModelRef};
+use crate::progress::FileProgress;
+use crate::registry::huggingface::Credentials;
+use crate::registry::{GgufFile, HuggingFace, Origin, Registry, ResolveInfo};
+use crate::serve::{
+    Body, Failure, FileInfo, empty, file_response, full, model_ref, not_found, path_segments, span,
+    unsatisfiable,
+};
+
+/// How much of a growing blob a follower reads at a time.
+const READ_SIZE: usize = 64 * 1024;
+
+/// The hub a pull-through server fetches what the cache lacks from.
+///
+/// Manifests and listings are forwarded as they are. A file is installed
+/// into the cache with [`install_file`], sequentially so that its
+/// `.partial` file grows in order, and every request for it follows that
+/// file as it grows: concurrent requests for one blob share a single
+/// upstream transfer, and each gets its own range of it.
+pub(super) struct Upstream {
+    client: Client,
+    endpoint: String,
+    /// One connection per file, with the configured rate limit.
+    fetch: Fetch,
+    registry: Arc<HuggingFace>,
+    /// In-flight transfers by blob path.
+    transfers: Arc<Mutex<HashMap<PathBuf, watch::Receiver<Transfer>>>>,
+}
+
+#[derive(Clone, Debug)]
+enum Transfer {
+    /// Bytes reported so far; only the changes matter, as wake-ups.
+    Running(u64),
+    Done,
+    Failed(String),
+}
+
+/// Publishes an install's progress to the requests following it.
+struct TransferProgress(watch::Sender<Transfer>);
+
+impl FileProgress for TransferProgress {
+    fn start(&self, initial_position: u64) {
+        self.0.send_replace(Transfer::Running(initial_position));
+    }
+
+    fn inc(&self, delta: u64) {
+        self.0.send_modify(|transfer| {
+            if let Transfer::Running(bytes) = transfer {
+                *bytes += delta;
+            }
+        });
+    }
+
+    fn println(&self, _: &str) {}
+
+    fn finish(&self) {}
+}
+
+impl Upstream {
+    pub(super) fn new(
+        endpoint: String,
+        token: Option<&str>,
+        http: &HttpOptions,
+    ) -> Result<Self, PacaError> {
+        let credentials = Credentials::Given(token.map(String::from));
+        let registry = Arc::new(HuggingFace::with_http(endpoint.clone(), credentials, http)?);
+        Ok(Self {
+            client: http.download_client(registry.headers()?)?,
+            endpoint,
+            fetch: Fetch {
+                connections: 1,
+                ..Fetch::new(http)
+            },
+            registry,
+            transfers: Arc::default(),
+        })
+    }
+
+    /// Answers a request the cache could not.
+    pub(super) async fn route(
+        &self,
+        hub: &HubLayout,
+        request: &Request<Incoming>,
+    ) -> Result<Response<Body>, Failure> {
+        let Some(segments) = path_segments(request.uri().path()) else {
+            return Err(not_found("Malformed path"));
+        };
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+        match segments.as_slice() {
+            ["v2", _, _, "manifests", _] | ["api", "models", _, _, "tree", ..] => {
+                self.forward(request).await
+            }
+            [owner, model, "resolve", revision, path @ ..] if !path.is_empty() => {
+                let mut model_ref = model_ref(owner, model, DEFAULT_TAG)?;
+                if *revision != DEFAULT_REVISION {
+                    model_ref.revision = Some((*revision).to_string());
+                }
+                let range = request.headers().get(header::RANGE);
+                self.pull(hub, model_ref, &path.join("/"), range, request.method())
+                    .await
+            }
+            _ => Err(not_found("No such endpoint")),
+        }
+    }
+
+    /// Relays a metadata request upstream, status and body as they are.
+    async fn forward(&self, request: &Request<Incoming>) -> Result<Response<Body>, Failure> {
+        let path = request
+            .uri()
+            .path_and_query()
+            .map_or("/", |path| path.as_str());
+        let upstream = self
+            .client
+            .get(format!("{}{path}", self.endpoint))
+            .send()
+            .await
+            .map_err(bad_gateway)?;
+
+        let status = upstream.status();
+        let content_type = upstream.headers().get(header::CONTENT_TYPE).cloned();
+        let body = upstream.bytes().await.map_err(bad_gateway)?;
+
+        let mut response = Response::new(full(body));
+        *response.status_mut() = StatusCode::from_u16(status.as_u16()).map_err(bad_gateway)?;
+        if let Some(content_type) = content_type
+            && let Ok(content_type) = HeaderValue::from_bytes(content_type.as_bytes())
+        {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, content_type);
+        }
+        Ok(response)
+    }
+
+    /// Serves a file from upstream while installing it into the cache.
+    async fn pull(
+        &self,
+        hub: &HubLayout,
+        model_ref: ModelRef,
+        path: &str,
+        range: Option<&HeaderValue>,
+        method: &Method,
+    ) -> Result<Response<Body>, Failure> {
+        let resolved = self
+            .registry
+            .resolve(&model_ref, path)
+            .await
+            .map_err(upstream_failure)?;
+        let size = resolved.size.ok_or_else(|| {
+            bad_gateway(PacaError::MissingFileSize(
+                self.registry.file_url(&model_ref, path),
+            ))
+        })?;
+        let info = FileInfo {
+            commit: resolved.commit_hash.clone(),
+            etag: resolved.blob_hash.clone(),
+            size,
+        };
+
+        let Some((status, start, length)) = span(range, size) else {
+            return Ok(unsatisfiable(size));
+        };
+        if method == Method::HEAD {
+            return Ok(file_response(empty(), status, start, length, &info));
+        }
+
+        // The upstream names the blob; containment decides whether it
+        // may name that path.
+        let blob = hub
+            .model(&model_ref)
+            .and_then(|paths| paths.blob(&resolved.blob_hash))
+            .map_err(bad_gateway)?;
+        let transfer = self.transfer(hub, model_ref, path, resolved, &blob);
+
+        let follower = Follower {
+            partial: partial_path(&blob),
+            blob,
+            end: start + length,
+            file: None,
+            offset: start,
+            transfer,
+        };
+        Ok(file_response(follower.body(), status, start, length, &info))
+    }
+
+    /// Joins the transfer already filling `blob`, or starts one.
+    fn transfer(
+        &self,
+        hub: &HubLayout,
+        model_ref: ModelRef,
+        path: &str,
+        resolved: ResolveInfo,
+        blob: &Path,
+    ) -> watch::Receiver<Transfer> {
+        let mut transfers = self.transfers.lock().expect("transfer map poisoned");
+        if let Some(transfer) = transfers.get(blob) {
+            return transfer.clone();
+        }
+
+        let (sender, receiver) = watch::channel(Transfer::Running(0));
+        transfers.insert(blob.to_path_buf(), receiver.clone());
+        let progress = Arc::new(TransferProgress(sender));
+        let file = ResolvedFile {
+            urls: self.registry.blob_urls(&model_ref, path, &resolved),
+            gguf_file: GgufFile {
+                filename: path.to_string(),
+                size: resolved.size.unwrap_or_default(),
+            },
+            progress: Arc::clone(&progress) as Arc<dyn FileProgress>,
+            registry: Arc::clone(&self.registry) as Arc<dyn Registry>,
+            resolve_info: resolved,
+            fetch: self.fetch.clone(),
+        };
+
+        let hub = hub.clone();
+        let transfers = Arc::clone(&self.transfers);
+        let blob = blob.to_path_buf();
+        tokio::spawn(async move {
+            let result = install(&hub, &model_ref, file).await;
+            // Gone from the map before anyone hears it finished, so a
+            // later request starts afresh and finds the blob in place.
+            transfers
+                .lock()
+                .expect("transfer map poisoned")
+                .remove(&blob);
+            progress.0.send_replace(match result {
+                Ok(()) => Transfer::Done,
+                Err(e) => Transfer::Failed(e.to_string()),
+            });
+        });
+
+        receiver
+    }
+}
+
+/// Installs the file and records the commit under its branch, as a
+/// download would.
+async fn install(
+    hub: &HubLayout,
+    model_ref: &ModelRef,
+    file: ResolvedFile,
+) -> Result<(), PacaError> {
+    let paths = hub.model(model_ref)?;
+    paths.check_origin(&Origin::HuggingFace)?;
+    fs::create_dir_all(paths.blobs()).map_err(PacaError::CacheDir)?;
+    let (_, commit) = install_file(hub, model_ref, file).await?;
+    paths.save_origin(&Origin::HuggingFace)?;
+    match &model_ref.revision {
+        None => paths.save_ref(&commit),
+        Some(revision) if !is_commit_hash(revision) => paths.save_named_ref(revision, &commit),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Reads a byte range of a blob that may still be downloading: from the
+/// `.partial` file while it grows, which stays readable through the
+/// rename that completes it, else from the finished blob.
+struct Follower {
+    blob: PathBuf,
+    /// Exclusive.
+    end: u64,
+    file: Option<tokio::fs::File>,
+    offset: u64,
+    partial: PathBuf,
+    transfer: watch::Receiver<Transfer>,
+}
+
+impl Follower {
+    fn body(self) -> Body {
+        let frames = stream::try_unfold(self, |mut follower| async move {
+            let chunk = follower.next_chunk().await?;
+            Ok::<_, io::Error>(chunk.map(|bytes| (Frame::data(bytes), follower)))
+        });
+        BodyExt::boxed(StreamBody::new(frames))
+    }
+
+    async fn next_chunk(&mut self) -> io::Result<Option<Bytes>> {
+        while self.offset < self.end {
+            // Marked seen before reading, so that a write landing after
+            // the read still wakes the wait below.
+            let transfer = self.transfer.borrow_and_update().clone();
+            if let Transfer::Failed(message) = transfer {
+                return Err(io::Error::other(message));
+            }
+
+            let wanted = usize::try_from(self.end - self.offset).unwrap_or(READ_SIZE);
+            if let Some(file) = self.open().await? {
+                let mut buffer = vec![0; wanted.min(READ_SIZE)];
+                let read = file.read(&mut buffer).await?;
+                if read > 0 {
+                    buffer.truncate(read);
+                    self.offset += read as u64;
+                    return Ok(Some(Bytes::from(buffer)));
+                }
+            }
+
+            if matches!(transfer, Transfer::Done) || self.transfer.changed().await.is_err() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "upstream transfer ended before the requested range",
+                ));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The file to read from, once the transfer has created one.
+    async fn open(&mut self) -> io::Result<Option<&mut tokio::fs::File>> {
+        if self.file.is_none() {
+            for path in [&self.blob, &self.partial] {
+                match tokio::fs::File::open(path).await {
+                    Ok(mut file) => {
+                        file.seek(io::SeekFrom::Start(self.offset)).await?;
+                        self.file = Some(file);
+                        break;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(self.file.as_mut())
+    }
+}
+
+fn bad_gateway(error: impl ToString) -> Failure {
+    (StatusCode::BAD_GATEWAY, error.to_string())
+}
+
+/// Passes on an upstream's refusal, so a client sees a missing or gated
+/// file as such; anything else is the gateway's failure.
+fn upstream_failure(error: PacaError) -> Failure {
+    let status = match &error {
+        PacaError::Http(e) | PacaError::ManifestFetch(e) => e.status(),
+        _ => None,
+    };
+    match status.map(|status| status.as_u16()) {
+        Some(code @ (401 | 403 | 404)) => (
+            StatusCode::from_u16(code).expect("a valid status"),
+            error.to_string(),
+        ),
+        _ => bad_gateway(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serve::Server;
+    use crate::serve::tests::{download, install as install_source, serve};
+    use std::time::Duration;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn proxy(hub: &std::path::Path, upstream: &str) -> String {
+        let server = Server::bind("127.0.0.1:0", Some(hub.to_path_buf()))
+            .await
+            .unwrap()
+            .with_upstream(upstream)
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run());
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn paca_downloads_through_the_proxy_into_both_caches() {
+        temp_env::async_with_vars([("HF_HUB_OFFLINE", None::<&str>)], async {
+            let dir = tempfile::tempdir().unwrap();
+            let source = dir.path().join("source");
+            install_source(
+                &source,
+                "commit1",
+                &[
+                    ("Model-Q4_K_M.gguf", b"q4"),
+                    ("Model-Q8_0.gguf", b"q8"),
+                    ("mmproj-F16.gguf", b"projector"),
+                ],
+            );
+            let upstream = serve(&source).await;
+            let proxy_hub = dir.path().join("proxy");
+            let endpoint = proxy(&proxy_hub, &upstream).await;
+
+            let target = dir.path().join("target");
+            let mut paths = download(&endpoint, "owner/Model-GGUF:Q4_K_M", &target).await;
+            paths.sort();
+
+            assert_eq!(fs::read(&paths[0]).unwrap(), b"q4");
+            assert_eq!(fs::read(&paths[1]).unwrap(), b"projector");
+            let cached = proxy_hub.join("models--owner--Model-GGUF");
+            assert_eq!(
+                fs::read_to_string(cached.join("refs/main")).unwrap(),
+                "commit1"
+            );
+            assert_eq!(
+                fs::read(cached.join("snapshots/commit1/Model-Q4_K_M.gguf")).unwrap(),
+                b"q4"
+            );
+            assert!(!cached.join("snapshots/commit1/Model-Q8_0.gguf").exists());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_share_one_upstream_transfer() {
+        let body = b"0123456789".repeat(10_000);
+        let upstream = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-repo-commit", "commit1")
+                    .insert_header("etag", "\"blob1\"")
+                    .insert_header("x-linked-size", body.len().to_string().as_str()),
+            )
+            .mount(&upstream)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(body.clone())
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .expect(1)
+            .mount(&upstream)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let endpoint = proxy(dir.path(), &upstream.uri()).await;
+        let url = format!("{endpoint}/owner/Model-GGUF/resolve/main/Model-Q4_K_M.gguf");
+        let client = reqwest::Client::new();
+
+        let whole = client.get(&url).send();
+        let part = client.get(&url).header("Range", "bytes=5-14").send();
+        let (whole, part) = tokio::join!(whole, part);
+
+        let whole = whole.unwrap();
+        assert_eq!(whole.headers()["x-repo-commit"], "commit1");
+        assert_eq!(whole.bytes().await.unwrap().as_ref(), body.as_slice());
+        let part = part.unwrap();
+        assert_eq!(part.status(), 206);
+        assert_eq!(part.bytes().await.unwrap().as_ref(), b"5678901234");
+        assert_eq!(
+            fs::read(dir.path().join("models--owner--Model-GGUF/blobs/blob1")).unwrap(),
+            body
+        );
+    }
+
+    #[tokio::test]
+    async fn passes_upstream_refusals_through() {
+        let upstream = MockServer::start().await;
+        for (file, status) in [("missing", 404), ("gated", 401), ("forbidden", 403)] {
+            Mock::given(method("HEAD"))
+                .and(path(format!("/owner/Model-GGUF/resolve/main/{file}.gguf")))
+                .respond_with(ResponseTemplate::new(status))
+                .mount(&upstream)
+                .await;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let endpoint = proxy(dir.path(), &upstream.uri()).await;
+        for (file, status) in [("missing", 404), ("gated", 401), ("forbidden", 403)] {
+            let response = reqwest::get(format!(
+                "{endpoint}/owner/Model-GGUF/resolve/main/{file}.gguf"
+            ))
+            .await
+            .unwrap();
+            assert_eq!(response.status(), status, "{file}");
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_upstream_failures_as_bad_gateway() {
+        let upstream = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&upstream)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let endpoint = proxy(dir.path(), &upstream.uri()).await;
+        let response = reqwest::get(format!(
+            "{endpoint}/owner/Model-GGUF/resolve/main/Model-Q4_K_M.gguf"
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(response.status(), 502);
+    }
+}
@@ -12,7 +12,7 @@ pub fn available_disk_space(path: &Path) -> Result<u64, std::io::Error> {
         if libc::statvfs(c_path.as_ptr(), &raw mut stat) != 0 {
             return Err(std::io::Error::last_os_error());
         }
-        Ok(u64::from(stat.f_bavail) * stat.f_frsize as u64)
+        Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
     }
 }
 